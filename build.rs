@@ -0,0 +1,261 @@
+use ::serde_json::Value;
+use ::std::{
+	collections::HashMap,
+	env,
+	error::Error,
+	fmt::Write as _,
+	fs,
+	path::{Path, PathBuf},
+};
+
+/// Generates registries from the vanilla data reports in `data`, where each protocol version has a
+/// `data/<protocol>/blocks.json` and `data/<protocol>/registries.json` as produced by the vanilla
+/// server's `--reports` option, along with how each block behaves from `data/materials.json`
+///
+/// Recipes and tags laid out like a data pack in `data/<namespace>` are bundled as well.
+fn main() -> Result<(), Box<dyn Error>> {
+	println!("cargo:rerun-if-changed=data");
+	let mut versions = Vec::new();
+	let mut namespaces = Vec::new();
+	for entry in fs::read_dir("data")? {
+		let path = entry?.path();
+		if !path.is_dir() {
+			continue;
+		}
+		match path
+			.file_name()
+			.and_then(|name| name.to_str())
+			.and_then(|name| name.parse::<i32>().ok())
+		{
+			Some(protocol) => versions.push((protocol, path)),
+			None => namespaces.push(path),
+		}
+	}
+	versions.sort_by_key(|(protocol, _)| *protocol);
+	namespaces.sort();
+	let out = PathBuf::from(env::var("OUT_DIR")?);
+	fs::write(out.join("blocks.rs"), blocks(&versions)?)?;
+	fs::write(out.join("items.rs"), items(&versions)?)?;
+	fs::write(out.join("datapack.rs"), datapack(&namespaces)?)?;
+	Ok(())
+}
+
+fn read_json(path: &Path) -> Result<Value, Box<dyn Error>> {
+	println!("cargo:rerun-if-changed={}", path.display());
+	Ok(::serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Generates the block states of each version along with how they map to classic blocks
+fn blocks(versions: &[(i32, PathBuf)]) -> Result<String, Box<dyn Error>> {
+	let materials = read_materials(Path::new("data/materials.json"))?;
+	let mut code = String::new();
+	writeln!(code, "pub static PALETTES: &[(i32, &[BlockState])] = &[")?;
+	for (protocol, path) in versions {
+		writeln!(code, "\t({}, &[", protocol)?;
+		for state in read_report(&path.join("blocks.json"), &materials)? {
+			writeln!(code, "\t\t{},", state)?;
+		}
+		writeln!(code, "\t]),")?;
+	}
+	writeln!(code, "];")?;
+	writeln!(code, "pub static CLASSIC: &[ClassicBlock] = &[")?;
+	for block in read_json(Path::new("data/classic.json"))?
+		.as_array()
+		.ok_or("data/classic.json should be a list")?
+	{
+		let substitutes: Vec<String> = block["substitutes"]
+			.as_array()
+			.map(|names| names.iter().map(|name| format!("{}", name)).collect())
+			.unwrap_or_default();
+		writeln!(
+			code,
+			"\tClassicBlock {{ id: {}, block: {}, properties: &[{}], substitutes: &[{}] }},",
+			block["id"],
+			block["block"],
+			properties(&block["properties"]),
+			substitutes.join(", ")
+		)?;
+	}
+	writeln!(code, "];")?;
+	Ok(code)
+}
+
+/// Reads the materials file into a map of block names to how they behave
+fn read_materials(path: &Path) -> Result<HashMap<String, Value>, Box<dyn Error>> {
+	let mut materials = HashMap::new();
+	for material in read_json(path)?
+		.as_array()
+		.ok_or_else(|| format!("{} should be a list", path.display()))?
+	{
+		for name in material["blocks"].as_array().into_iter().flatten() {
+			let name = name
+				.as_str()
+				.ok_or_else(|| format!("{} should only list block names", path.display()))?;
+			if materials
+				.insert(String::from(name), material.clone())
+				.is_some()
+			{
+				return Err(format!("{} is listed twice in {}", name, path.display()).into());
+			}
+		}
+	}
+	Ok(materials)
+}
+
+/// Builds the material of a single block state, where a hardness below 0 means the block can't be
+/// broken and the light given off can depend on one of the state's properties
+fn material(name: &str, material: &Value, properties: &Value) -> Result<String, Box<dyn Error>> {
+	let hardness = material["hardness"]
+		.as_f64()
+		.ok_or_else(|| format!("{} has no hardness", name))?;
+	let tool = match material["tool"].as_str() {
+		Some(tool) => {
+			let mut chars = tool.chars();
+			let first = chars.next().ok_or("Tools can't be empty")?;
+			format!(
+				"Some(ToolKind::{}{})",
+				first.to_ascii_uppercase(),
+				chars.as_str()
+			)
+		}
+		None => String::from("None"),
+	};
+	let solid = material["solid"].as_bool().unwrap_or(true);
+	let emission = match &material["emission"] {
+		Value::Object(levels) => levels
+			.iter()
+			.find_map(|(property, levels)| levels[properties[property].as_str()?].as_u64())
+			.ok_or_else(|| format!("{} has no light level for one of its states", name))?,
+		emission => emission.as_u64().unwrap_or(0),
+	};
+	Ok(format!(
+		"Material {{ hardness: {}, tool: {}, harvest_level: {:?}, opacity: {}, emission: {}, solid: {}, climbable: {} }}",
+		if hardness < 0.0 {
+			String::from("None")
+		} else {
+			format!("Some({:?})", hardness as f32)
+		},
+		tool,
+		material["harvest_level"].as_u64(),
+		material["opacity"]
+			.as_u64()
+			.unwrap_or(if solid { 15 } else { 0 }),
+		emission,
+		solid,
+		material["climbable"].as_bool().unwrap_or(false)
+	))
+}
+
+/// Reads a blocks report into a list of block state constructors, ordered by ID
+fn read_report(
+	path: &Path,
+	materials: &HashMap<String, Value>,
+) -> Result<Vec<String>, Box<dyn Error>> {
+	let report = read_json(path)?;
+	let blocks = report
+		.as_object()
+		.ok_or_else(|| format!("{} should map block names to blocks", path.display()))?;
+	let mut states = Vec::new();
+	for (name, block) in blocks {
+		let block_material = materials
+			.get(name)
+			.ok_or_else(|| format!("{} isn't listed in data/materials.json", name))?;
+		for state in block["states"].as_array().into_iter().flatten() {
+			let id = state["id"]
+				.as_u64()
+				.ok_or_else(|| format!("A state of {} in {} has no ID", name, path.display()))?;
+			states.push((
+				id,
+				format!(
+					"BlockState {{ id: {}, name: {:?}, properties: &[{}], default: {}, material: {} }}",
+					id,
+					name,
+					properties(&state["properties"]),
+					state["default"].as_bool().unwrap_or(false),
+					material(name, block_material, &state["properties"])?
+				),
+			));
+		}
+	}
+	states.sort_by_key(|(id, _)| *id);
+	Ok(states.into_iter().map(|(_, state)| state).collect())
+}
+
+/// Turns a JSON object of property names and values into a list of pairs
+fn properties(value: &Value) -> String {
+	value
+		.as_object()
+		.map(|properties| {
+			properties
+				.iter()
+				.filter_map(|(name, value)| Some(format!("({:?}, {:?})", name, value.as_str()?)))
+				.collect::<Vec<_>>()
+				.join(", ")
+		})
+		.unwrap_or_default()
+}
+
+/// Generates the items of each version from the item registry in its registries report
+fn items(versions: &[(i32, PathBuf)]) -> Result<String, Box<dyn Error>> {
+	let mut code = String::new();
+	writeln!(code, "pub static ITEMS: &[(i32, &[Item])] = &[")?;
+	for (protocol, path) in versions {
+		let path = path.join("registries.json");
+		let report = read_json(&path)?;
+		let entries = report["minecraft:item"]["entries"]
+			.as_object()
+			.ok_or_else(|| format!("{} should have an item registry", path.display()))?;
+		let mut items = Vec::new();
+		for (name, entry) in entries {
+			let id = entry["protocol_id"]
+				.as_i64()
+				.ok_or_else(|| format!("{} in {} has no ID", name, path.display()))?;
+			items.push((id, name));
+		}
+		items.sort();
+		writeln!(code, "\t({}, &[", protocol)?;
+		for (id, name) in items {
+			writeln!(code, "\t\tItem {{ id: {}, name: {:?} }},", id, name)?;
+		}
+		writeln!(code, "\t]),")?;
+	}
+	writeln!(code, "];")?;
+	Ok(code)
+}
+
+/// Bundles every file of the data pack, keyed by their path within it
+fn datapack(namespaces: &[PathBuf]) -> Result<String, Box<dyn Error>> {
+	let mut files = Vec::new();
+	for namespace in namespaces {
+		list_files(namespace, &mut files)?;
+	}
+	files.sort();
+	let root = fs::canonicalize("data")?;
+	let mut code = String::new();
+	writeln!(code, "pub static BUNDLED: &[(&str, &str)] = &[")?;
+	for file in files {
+		let file = fs::canonicalize(file)?;
+		let name = file
+			.strip_prefix(&root)?
+			.iter()
+			.filter_map(|part| part.to_str())
+			.collect::<Vec<_>>()
+			.join("/");
+		println!("cargo:rerun-if-changed={}", file.display());
+		writeln!(code, "\t({:?}, include_str!({:?})),", name, file)?;
+	}
+	writeln!(code, "];")?;
+	Ok(code)
+}
+
+fn list_files(directory: &Path, files: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+	for entry in fs::read_dir(directory)? {
+		let path = entry?.path();
+		if path.is_dir() {
+			list_files(&path, files)?;
+		} else {
+			files.push(path);
+		}
+	}
+	Ok(())
+}
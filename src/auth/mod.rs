@@ -1,4 +1,9 @@
+use ::openssl::{
+	error::ErrorStack,
+	hash::{hash, MessageDigest},
+};
 use ::std::error::Error;
+use ::uuid::Uuid;
 
 use crate::config::Config;
 
@@ -40,3 +45,38 @@ impl Authentication {
 		})
 	}
 }
+
+/// A property attached to a profile, such as the player's skin
+#[derive(Clone, Debug)]
+pub struct ProfileProperty {
+	pub name: String,
+	pub value: String,
+	pub signature: Option<String>,
+}
+
+/// Who a player is, as established during login
+#[derive(Clone, Debug)]
+pub struct GameProfile {
+	pub uuid: Uuid,
+	pub name: String,
+	pub properties: Vec<ProfileProperty>,
+}
+
+impl GameProfile {
+	/// Creates a profile for a player who hasn't been authenticated, using the same UUID vanilla would
+	pub fn offline(name: String) -> Result<Self, ErrorStack> {
+		let mut bytes = [0u8; 16];
+		bytes.copy_from_slice(&hash(
+			MessageDigest::md5(),
+			format!("OfflinePlayer:{}", name).as_bytes(),
+		)?);
+		// Mark the UUID as a version 3 (name based) UUID
+		bytes[6] = (bytes[6] & 0x0F) | 0x30;
+		bytes[8] = (bytes[8] & 0x3F) | 0x80;
+		Ok(Self {
+			uuid: Uuid::from_bytes(bytes),
+			name,
+			properties: Vec::new(),
+		})
+	}
+}
@@ -0,0 +1,43 @@
+use crate::{
+	gzip,
+	world::{chunk::ChunkPos, registry::BlockRegistry, World},
+};
+
+/// Size of the level sent to classic clients along each axis
+pub const WIDTH: i32 = 128;
+pub const HEIGHT: i32 = 64;
+pub const DEPTH: i32 = 128;
+/// Classic levels start at zero, so the level is shifted to be centered on the world's origin
+pub const OFFSET: i32 = 64;
+
+/// Whether a block position in the world falls inside the classic level
+pub fn contains(x: i32, y: i32, z: i32) -> bool {
+	(0..WIDTH).contains(&(x + OFFSET))
+		&& (0..HEIGHT).contains(&y)
+		&& (0..DEPTH).contains(&(z + OFFSET))
+}
+
+/// Builds the gzipped level data sent while the client is loading the level
+pub fn encode(world: &mut World, blocks: &BlockRegistry) -> Vec<u8> {
+	let volume = (WIDTH * HEIGHT * DEPTH) as usize;
+	let mut data = Vec::with_capacity(4 + volume);
+	data.extend_from_slice(&(volume as i32).to_be_bytes());
+	let mut level = vec![0u8; volume];
+	for cz in 0..DEPTH / 16 {
+		for cx in 0..WIDTH / 16 {
+			let pos = ChunkPos::new(cx - OFFSET / 16, cz - OFFSET / 16);
+			let chunk = world.chunk(pos);
+			for y in 0..HEIGHT {
+				for z in 0..16 {
+					for x in 0..16 {
+						let state = chunk.get_block(x as usize, y as usize, z as usize);
+						let index = (y * DEPTH + cz * 16 + z) * WIDTH + cx * 16 + x;
+						level[index as usize] = blocks.to_classic(state);
+					}
+				}
+			}
+		}
+	}
+	data.extend_from_slice(&level);
+	gzip::compress(&data)
+}
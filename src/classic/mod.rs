@@ -1 +1,5 @@
+mod level;
 pub mod types;
+mod v7;
+
+pub use v7::V7;
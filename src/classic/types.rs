@@ -1,10 +1,13 @@
 use ::async_trait::async_trait;
 use ::std::{
-	io::{Error, ErrorKind, Read, Result, Write},
+	io::{Read, Result, Write},
 	mem,
 };
 use ::tokio::io::{AsyncReadExt, AsyncWriteExt};
 
+/// Length of every string sent in the classic protocol
+pub const STRING_LENGTH: usize = 64;
+
 #[async_trait]
 pub trait ClassicEncodable {
 	async fn async_read<R: AsyncReadExt + Send + Unpin>(stream: &mut R) -> Result<Self>
@@ -65,43 +68,49 @@ impl ClassicEncodable for i16 {
 	}
 }
 
+/// Strings are always 64 bytes of ASCII, padded with spaces
 #[async_trait]
 impl ClassicEncodable for String {
 	async fn async_read<R: AsyncReadExt + Send + Unpin>(stream: &mut R) -> Result<Self> {
-		let mut buffer8 = [0u8; 64];
-		stream.read(&mut buffer8).await?;
-		let buffer16: [u16; 32] = unsafe { mem::transmute(buffer8) };
-		match String::from_utf16(&buffer16) {
-			Ok(v) => Ok(v),
-			Err(e) => Err(Error::new(ErrorKind::InvalidData, e)),
-		}
+		let mut buffer = [0u8; STRING_LENGTH];
+		stream.read_exact(&mut buffer).await?;
+		Ok(decode_string(&buffer))
 	}
 
 	async fn async_write<W: AsyncWriteExt + Send + Unpin>(&self, stream: &mut W) -> Result<()> {
-		for c in self.as_str().encode_utf16() {
-			stream.write_u16(c).await?;
-		}
+		stream.write_all(&encode_string(self)).await?;
 		Ok(())
 	}
 
 	fn read<R: Read>(stream: &mut R) -> Result<Self> {
-		let mut buffer8 = [0u8; 64];
-		stream.read(&mut buffer8)?;
-		let buffer16: [u16; 32] = unsafe { mem::transmute(buffer8) };
-		match String::from_utf16(&buffer16) {
-			Ok(v) => Ok(v),
-			Err(e) => Err(Error::new(ErrorKind::InvalidData, e)),
-		}
+		let mut buffer = [0u8; STRING_LENGTH];
+		stream.read_exact(&mut buffer)?;
+		Ok(decode_string(&buffer))
 	}
 
 	fn write<W: Write>(&self, stream: &mut W) -> Result<()> {
-		for c in self.as_str().encode_utf16() {
-			stream.write_all(&c.to_be_bytes())?;
-		}
+		stream.write_all(&encode_string(self))?;
 		Ok(())
 	}
 }
 
+fn decode_string(buffer: &[u8; STRING_LENGTH]) -> String {
+	buffer
+		.iter()
+		.map(|&b| if b.is_ascii() { b as char } else { '?' })
+		.collect::<String>()
+		.trim_end_matches(' ')
+		.to_string()
+}
+
+fn encode_string(value: &str) -> [u8; STRING_LENGTH] {
+	let mut buffer = [b' '; STRING_LENGTH];
+	for (i, c) in value.chars().take(STRING_LENGTH).enumerate() {
+		buffer[i] = if c.is_ascii() { c as u8 } else { b'?' };
+	}
+	buffer
+}
+
 #[async_trait]
 impl ClassicEncodable for u8 {
 	async fn async_read<R: AsyncReadExt + Send + Unpin>(stream: &mut R) -> Result<Self> {
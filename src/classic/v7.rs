@@ -0,0 +1,675 @@
+use ::async_trait::async_trait;
+use ::log::debug;
+use ::std::{
+	collections::{HashMap, HashSet},
+	io::{Error, ErrorKind, Result},
+	sync::Arc,
+};
+use ::tokio::{
+	self,
+	io::AsyncWriteExt,
+	net::{
+		tcp::{OwnedReadHalf, OwnedWriteHalf},
+		TcpStream,
+	},
+	sync::mpsc::{self, UnboundedSender},
+};
+use ::uuid::Uuid;
+
+use crate::{
+	auth::GameProfile,
+	classic::{
+		level::{self, DEPTH, HEIGHT, OFFSET, WIDTH},
+		types::{ClassicEncodable, STRING_LENGTH},
+	},
+	command::CommandNode,
+	entity::EntityKind,
+	inventory::ContainerKind,
+	lists,
+	modern::types::chat::ChatComponent,
+	player::{Gamemode, Position, Rotation, Session},
+	state::ServerState,
+	types::{BasaltError, DigStatus, Event, Events, Hand, MessagePosition, ProtocolHandler, Slot},
+	world::{chunk::ChunkPos, registry::BlockRegistry, BlockPos, Difficulty, Weather},
+};
+
+const PROTOCOL_VERSION: u8 = 7;
+/// Entity ID a client uses for itself
+const SELF_ID: i8 = -1;
+/// Distance from a player's feet to the point classic positions refer to
+const EYE_HEIGHT: f64 = 1.59375;
+
+pub struct V7 {
+	conn: OwnedWriteHalf,
+	/// Classic entity IDs are a single byte, so server entity IDs are given one as they are shown
+	entity_ids: HashMap<i32, i8>,
+	/// Read half of the connection, until it is handed to the task that reads events
+	reader: Option<OwnedReadHalf>,
+	state: Arc<ServerState>,
+}
+
+impl V7 {
+	/// Creates a handler for a client whose first packet ID has already been read
+	pub fn new(conn: TcpStream, state: Arc<ServerState>) -> Box<Self> {
+		let (reader, conn) = conn.into_split();
+		Box::new(Self {
+			conn,
+			entity_ids: HashMap::new(),
+			reader: Some(reader),
+			state,
+		})
+	}
+
+	fn reader(&mut self) -> Result<&mut OwnedReadHalf> {
+		self.reader.as_mut().ok_or_else(|| {
+			Error::new(
+				ErrorKind::Other,
+				BasaltError::new(String::from("Connection is already being read from")),
+			)
+		})
+	}
+
+	async fn send(&mut self, buffer: Vec<u8>) -> Result<()> {
+		self.conn.write_all(&buffer).await
+	}
+
+	/// Sends the level the player is in, which replaces any level the client had loaded
+	async fn send_level(&mut self, world: &str) -> Result<()> {
+		let data = match self.state.world(world) {
+			Some(world) => level::encode(&mut world.lock().unwrap(), &self.state.blocks),
+			None => {
+				return Err(Error::new(
+					ErrorKind::NotFound,
+					BasaltError::new(format!("No world named \"{}\"", world)),
+				))
+			}
+		};
+		self.send(vec![0x02]).await?;
+		let count = (data.len() + 1023) / 1024;
+		for (i, chunk) in data.chunks(1024).enumerate() {
+			let mut buffer = Vec::with_capacity(1028);
+			0x03u8.write(&mut buffer)?;
+			(chunk.len() as i16).write(&mut buffer)?;
+			let mut padded = [0u8; 1024];
+			padded[..chunk.len()].copy_from_slice(chunk);
+			padded.write(&mut buffer)?;
+			(((i + 1) * 100 / count) as u8).write(&mut buffer)?;
+			self.send(buffer).await?;
+		}
+		let mut buffer = Vec::new();
+		0x04u8.write(&mut buffer)?;
+		(WIDTH as i16).write(&mut buffer)?;
+		(HEIGHT as i16).write(&mut buffer)?;
+		(DEPTH as i16).write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	/// Finds the byte sized ID an entity is shown with, giving it one if it doesn't have one yet
+	fn entity_id(&mut self, entity_id: i32) -> Option<i8> {
+		if let Some(id) = self.entity_ids.get(&entity_id) {
+			return Some(*id);
+		}
+		let id = (0..=i8::MAX).find(|id| !self.entity_ids.values().any(|used| used == id))?;
+		self.entity_ids.insert(entity_id, id);
+		Some(id)
+	}
+
+	/// Spawns the client's own player, which is where the client puts its camera
+	async fn spawn_self(&mut self, position: Position, rotation: Rotation) -> Result<()> {
+		let mut buffer = Vec::new();
+		0x07u8.write(&mut buffer)?;
+		SELF_ID.write(&mut buffer)?;
+		String::new().write(&mut buffer)?;
+		write_position(position, rotation, &mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn send_position(
+		&mut self,
+		id: i8,
+		position: Position,
+		rotation: Rotation,
+	) -> Result<()> {
+		let mut buffer = Vec::new();
+		0x08u8.write(&mut buffer)?;
+		id.write(&mut buffer)?;
+		write_position(position, rotation, &mut buffer)?;
+		self.send(buffer).await
+	}
+}
+
+#[async_trait]
+impl ProtocolHandler for V7 {
+	/// Classic clients are only told when they weren't allowed to break a block, by putting it back
+	async fn acknowledge_dig(
+		&mut self,
+		position: BlockPos,
+		state: u16,
+		_status: DigStatus,
+		successful: bool,
+	) -> Result<()> {
+		if successful {
+			return Ok(());
+		}
+		self.block_change(position, state).await
+	}
+
+	/// Classic clients have no player list
+	async fn add_to_player_list(
+		&mut self,
+		_profile: &GameProfile,
+		_gamemode: Gamemode,
+		_ping: i32,
+	) -> Result<()> {
+		Ok(())
+	}
+
+	/// Classic clients can't show other players breaking blocks
+	async fn block_break_animation(
+		&mut self,
+		_entity_id: i32,
+		_position: BlockPos,
+		_stage: i8,
+	) -> Result<()> {
+		Ok(())
+	}
+
+	async fn block_change(&mut self, position: BlockPos, state: u16) -> Result<()> {
+		if !level::contains(position.x, position.y, position.z) {
+			return Ok(());
+		}
+		let mut buffer = Vec::new();
+		0x06u8.write(&mut buffer)?;
+		((position.x + OFFSET) as i16).write(&mut buffer)?;
+		(position.y as i16).write(&mut buffer)?;
+		((position.z + OFFSET) as i16).write(&mut buffer)?;
+		self.state.blocks.to_classic(state).write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	/// Classic clients light the level themselves
+	async fn block_changes(
+		&mut self,
+		blocks: &[(BlockPos, u16)],
+		_light: &HashSet<ChunkPos>,
+	) -> Result<()> {
+		for (position, state) in blocks {
+			self.block_change(*position, *state).await?;
+		}
+		Ok(())
+	}
+
+	fn breaks_instantly(&self) -> bool {
+		true
+	}
+
+	fn can_always_fly(&self) -> bool {
+		true
+	}
+
+	async fn close_window(&mut self, _window: u8) -> Result<()> {
+		Ok(())
+	}
+
+	/// Classic players can't die
+	async fn death_screen(&mut self, _entity_id: i32, _message: &ChatComponent) -> Result<()> {
+		Ok(())
+	}
+
+	/// Classic players type commands as plain chat, and can find them with /help
+	async fn declare_commands(&mut self, _root: &CommandNode) -> Result<()> {
+		Ok(())
+	}
+
+	async fn despawn_entities(&mut self, entity_ids: &[i32]) -> Result<()> {
+		let mut buffer = Vec::new();
+		for entity_id in entity_ids {
+			if let Some(id) = self.entity_ids.remove(entity_id) {
+				0x0Cu8.write(&mut buffer)?;
+				id.write(&mut buffer)?;
+			}
+		}
+		self.send(buffer).await
+	}
+
+	async fn disconnect(&mut self, reason: String) -> Result<()> {
+		let mut buffer = Vec::new();
+		0x0Eu8.write(&mut buffer)?;
+		reason.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn entity_teleport(
+		&mut self,
+		entity_id: i32,
+		position: Position,
+		rotation: Rotation,
+		_on_ground: bool,
+	) -> Result<()> {
+		match self.entity_ids.get(&entity_id) {
+			Some(id) => self.send_position(*id, position, rotation).await,
+			None => Ok(()),
+		}
+	}
+
+	fn has_health(&self) -> bool {
+		false
+	}
+
+	async fn join(&mut self, _entity_id: i32, session: &Session) -> Result<Events> {
+		self.send_level(&session.world).await?;
+		self.spawn_self(session.position, session.rotation).await?;
+		let reader = self.reader.take().ok_or_else(|| {
+			Error::new(
+				ErrorKind::Other,
+				BasaltError::new(String::from("Player has already joined")),
+			)
+		})?;
+		let (sender, events) = mpsc::unbounded_channel();
+		tokio::spawn(read_events(reader, sender, self.state.clone()));
+		Ok(events)
+	}
+
+	/// Classic clients don't answer pings, but they send their position every tick instead
+	async fn keep_alive(&mut self, _id: i64) -> Result<()> {
+		self.send(vec![0x01]).await
+	}
+
+	async fn login(&mut self) -> Result<GameProfile> {
+		let reader = self.reader()?;
+		let version = u8::async_read(reader).await?;
+		let name = String::async_read(reader).await?;
+		// Verification key, which is only useful with a server list
+		let _key = String::async_read(reader).await?;
+		let _unused = u8::async_read(reader).await?;
+		if version != PROTOCOL_VERSION {
+			self.disconnect(String::from("Unsupported protocol version"))
+				.await?;
+			return Err(Error::new(
+				ErrorKind::InvalidData,
+				BasaltError::new(format!("Unsupported classic protocol {}", version)),
+			));
+		}
+		let profile = GameProfile::offline(name).map_err(|e| Error::new(ErrorKind::Other, e))?;
+		if let Err(reason) = lists::check_login(&self.state, &profile) {
+			self.disconnect(reason.clone()).await?;
+			return Err(lists::refused(&profile, &reason));
+		}
+		let mut buffer = Vec::new();
+		0x00u8.write(&mut buffer)?;
+		PROTOCOL_VERSION.write(&mut buffer)?;
+		String::from("Basalt").write(&mut buffer)?;
+		self.state
+			.config()
+			.minecraft
+			.motd
+			.clone()
+			.write(&mut buffer)?;
+		user_type(lists::op_level(&self.state, &profile)).write(&mut buffer)?;
+		self.send(buffer).await?;
+		Ok(profile)
+	}
+
+	async fn move_entity(
+		&mut self,
+		entity_id: i32,
+		from: Position,
+		to: Position,
+		rotation: Rotation,
+		_on_ground: bool,
+	) -> Result<()> {
+		let id = match self.entity_ids.get(&entity_id) {
+			Some(id) => *id,
+			None => return Ok(()),
+		};
+		let delta = |from: f64, to: f64| fixed(to) - fixed(from);
+		let (dx, dy, dz) = (
+			delta(from.x, to.x),
+			delta(from.y, to.y),
+			delta(from.z, to.z),
+		);
+		let fits = |d: i32| d >= i8::MIN as i32 && d <= i8::MAX as i32;
+		// Relative moves can only cover 4 blocks at once
+		if !(fits(dx) && fits(dy) && fits(dz)) {
+			return self.send_position(id, to, rotation).await;
+		}
+		let mut buffer = Vec::new();
+		0x09u8.write(&mut buffer)?;
+		id.write(&mut buffer)?;
+		(dx as i8).write(&mut buffer)?;
+		(dy as i8).write(&mut buffer)?;
+		(dz as i8).write(&mut buffer)?;
+		angle(rotation.yaw - YAW_OFFSET).write(&mut buffer)?;
+		angle(rotation.pitch).write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	/// Classic clients have no windows, so containers can't be opened
+	async fn open_window(
+		&mut self,
+		_window: u8,
+		_kind: ContainerKind,
+		_title: &ChatComponent,
+	) -> Result<()> {
+		Ok(())
+	}
+
+	async fn remove_from_player_list(&mut self, _uuid: Uuid) -> Result<()> {
+		Ok(())
+	}
+
+	/// The level is sent again, since classic clients only ever have one loaded
+	async fn respawn(&mut self, session: &Session) -> Result<()> {
+		let entity_ids: Vec<i32> = self.entity_ids.keys().copied().collect();
+		self.despawn_entities(&entity_ids).await?;
+		self.send_level(&session.world).await?;
+		self.spawn_self(session.position, session.rotation).await
+	}
+
+	async fn rotate_entity(
+		&mut self,
+		entity_id: i32,
+		rotation: Rotation,
+		_on_ground: bool,
+	) -> Result<()> {
+		let id = match self.entity_ids.get(&entity_id) {
+			Some(id) => *id,
+			None => return Ok(()),
+		};
+		let mut buffer = Vec::new();
+		0x0Bu8.write(&mut buffer)?;
+		id.write(&mut buffer)?;
+		angle(rotation.yaw - YAW_OFFSET).write(&mut buffer)?;
+		angle(rotation.pitch).write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	/// Classic clients only understand plain text, so long messages are split over several lines
+	async fn send_message(
+		&mut self,
+		message: &ChatComponent,
+		_position: MessagePosition,
+		_sender: Option<Uuid>,
+	) -> Result<()> {
+		let mut buffer = Vec::new();
+		for line in wrap(&message.to_plain()) {
+			0x0Du8.write(&mut buffer)?;
+			SELF_ID.write(&mut buffer)?;
+			line.write(&mut buffer)?;
+		}
+		self.send(buffer).await
+	}
+
+	/// Classic clients have no difficulty
+	async fn set_difficulty(&mut self, _difficulty: Difficulty) -> Result<()> {
+		Ok(())
+	}
+
+	/// Classic clients can always fly and break blocks instantly, whatever the gamemode
+	async fn set_gamemode(&mut self, _gamemode: Gamemode) -> Result<()> {
+		Ok(())
+	}
+
+	/// Classic clients have no inventory
+	async fn set_inventory(&mut self, _window: u8, _items: &[Option<Slot>]) -> Result<()> {
+		Ok(())
+	}
+
+	/// Classic clients only know whether the player is an operator, which lets them break bedrock
+	async fn set_op_level(&mut self, _entity_id: i32, level: u8) -> Result<()> {
+		let mut buffer = Vec::new();
+		0x0Fu8.write(&mut buffer)?;
+		user_type(level).write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn set_slot(&mut self, _window: u8, _slot: i16, _item: Option<&Slot>) -> Result<()> {
+		Ok(())
+	}
+
+	/// Classic clients have no weather
+	async fn set_weather(&mut self, _weather: Weather) -> Result<()> {
+		Ok(())
+	}
+
+	/// Classic clients can only show players
+	async fn spawn_entity(
+		&mut self,
+		_entity_id: i32,
+		_uuid: Uuid,
+		_kind: EntityKind,
+		_position: Position,
+		_rotation: Rotation,
+	) -> Result<()> {
+		Ok(())
+	}
+
+	async fn spawn_player(
+		&mut self,
+		entity_id: i32,
+		profile: &GameProfile,
+		position: Position,
+		rotation: Rotation,
+	) -> Result<()> {
+		let id = match self.entity_id(entity_id) {
+			Some(id) => id,
+			None => {
+				debug!("No classic entity IDs left to show {}", profile.name);
+				return Ok(());
+			}
+		};
+		let mut buffer = Vec::new();
+		0x07u8.write(&mut buffer)?;
+		id.write(&mut buffer)?;
+		profile.name.write(&mut buffer)?;
+		write_position(position, rotation, &mut buffer)?;
+		self.send(buffer).await
+	}
+
+	/// Classic clients can't complete commands
+	async fn tab_complete(
+		&mut self,
+		_id: i32,
+		_start: usize,
+		_length: usize,
+		_matches: &[String],
+	) -> Result<()> {
+		Ok(())
+	}
+
+	/// Classic clients never confirm teleports
+	async fn teleport(&mut self, position: Position, rotation: Rotation) -> Result<Option<i32>> {
+		self.send_position(SELF_ID, position, rotation).await?;
+		Ok(None)
+	}
+
+	/// Titles are shown as chat messages
+	async fn title(
+		&mut self,
+		title: &ChatComponent,
+		subtitle: Option<&ChatComponent>,
+		_fade_in: i32,
+		_stay: i32,
+		_fade_out: i32,
+	) -> Result<()> {
+		self.send_message(title, MessagePosition::System, None)
+			.await?;
+		if let Some(subtitle) = subtitle {
+			self.send_message(subtitle, MessagePosition::System, None)
+				.await?;
+		}
+		Ok(())
+	}
+
+	/// Classic clients have no health or hunger
+	async fn update_health(&mut self, _session: &Session) -> Result<()> {
+		Ok(())
+	}
+
+	/// Classic clients have no player list
+	async fn update_latency(&mut self, _uuid: Uuid, _ping: i32) -> Result<()> {
+		Ok(())
+	}
+
+	/// Classic clients have no day and night
+	async fn update_time(&mut self, _age: i64, _time: i64) -> Result<()> {
+		Ok(())
+	}
+
+	/// The whole level is sent when joining, so there is nothing to load
+	async fn update_view(&mut self, _position: Position) -> Result<()> {
+		Ok(())
+	}
+
+	async fn window_confirmation(
+		&mut self,
+		_window: u8,
+		_action: i16,
+		_accepted: bool,
+	) -> Result<()> {
+		Ok(())
+	}
+}
+
+/// Yaw that classic clients face at zero, as they face north rather than south
+const YAW_OFFSET: f32 = 180.0;
+
+/// The user type classic clients are sent for an operator level, which is 0x64 for operators
+fn user_type(level: u8) -> u8 {
+	if level > 0 {
+		0x64
+	} else {
+		0
+	}
+}
+
+/// Converts an angle in degrees into 256ths of a turn
+fn angle(degrees: f32) -> u8 {
+	(degrees / 360.0 * 256.0) as i32 as u8
+}
+
+fn degrees(angle: u8) -> f32 {
+	angle as f32 * 360.0 / 256.0
+}
+
+/// Converts a coordinate into the 32nds of a block positions are measured in
+fn fixed(value: f64) -> i32 {
+	(value * 32.0).floor() as i32
+}
+
+/// Writes a position as fixed point numbers, followed by the rotation
+fn write_position(position: Position, rotation: Rotation, buffer: &mut Vec<u8>) -> Result<()> {
+	(fixed(position.x + OFFSET as f64) as i16).write(buffer)?;
+	(fixed(position.y + EYE_HEIGHT) as i16).write(buffer)?;
+	(fixed(position.z + OFFSET as f64) as i16).write(buffer)?;
+	angle(rotation.yaw - YAW_OFFSET).write(buffer)?;
+	angle(rotation.pitch).write(buffer)
+}
+
+/// Splits text into lines that fit in a classic string, breaking between words where possible
+fn wrap(text: &str) -> Vec<String> {
+	let mut lines = Vec::new();
+	for line in text.lines() {
+		let mut current = String::new();
+		for word in line.split(' ') {
+			let length = current.chars().count();
+			if length > 0 && length + 1 + word.chars().count() > STRING_LENGTH {
+				lines.push(::std::mem::take(&mut current));
+			} else if length > 0 {
+				current.push(' ');
+			}
+			let mut chars: Vec<char> = word.chars().collect();
+			while chars.len() > STRING_LENGTH {
+				lines.push(chars.drain(..STRING_LENGTH).collect());
+			}
+			current.extend(chars);
+		}
+		lines.push(current);
+	}
+	lines
+}
+
+async fn read_position(conn: &mut OwnedReadHalf) -> Result<BlockPos> {
+	let x = i16::async_read(conn).await? as i32 - OFFSET;
+	let y = i16::async_read(conn).await? as i32;
+	let z = i16::async_read(conn).await? as i32 - OFFSET;
+	Ok(BlockPos::new(x, y, z))
+}
+
+/// Whether a block can be placed by classic clients, which can't place bedrock, water or lava
+fn is_placeable(block: u8) -> bool {
+	(1..=49).contains(&block) && !(7..=11).contains(&block)
+}
+
+/// Turns a packet from the client into an event, returning None for packets a client shouldn't send
+async fn read_event(conn: &mut OwnedReadHalf, blocks: &BlockRegistry) -> Result<Option<Event>> {
+	let id = u8::async_read(conn).await?;
+	Ok(match id {
+		0x05 => {
+			let position = read_position(conn).await?;
+			let mode = u8::async_read(conn).await?;
+			let block = u8::async_read(conn).await?;
+			Some(if mode == 0 {
+				Event::Dig {
+					status: DigStatus::Finished,
+					position,
+					face: 1,
+				}
+			} else {
+				// Blocks that aren't in a classic client's inventory are placed as air, which is refused
+				// and undone like any other placement that isn't allowed
+				let block = if is_placeable(block) { block } else { 0 };
+				Event::Place {
+					position,
+					face: None,
+					hand: Hand::Main,
+					block: Some(blocks.from_classic(block)),
+				}
+			})
+		}
+		0x08 => {
+			let _id = i8::async_read(conn).await?;
+			let x = i16::async_read(conn).await? as f64 / 32.0 - OFFSET as f64;
+			let y = i16::async_read(conn).await? as f64 / 32.0 - EYE_HEIGHT;
+			let z = i16::async_read(conn).await? as f64 / 32.0 - OFFSET as f64;
+			let yaw = degrees(u8::async_read(conn).await?) + YAW_OFFSET;
+			let pitch = degrees(u8::async_read(conn).await?);
+			Some(Event::Move {
+				position: Some(Position::new(x, y, z)),
+				rotation: Some(Rotation { yaw, pitch }),
+				on_ground: true,
+			})
+		}
+		0x0D => {
+			let _unused = u8::async_read(conn).await?;
+			Some(Event::Chat(String::async_read(conn).await?))
+		}
+		_ => {
+			debug!("Unexpected classic packet {:#04X}", id);
+			None
+		}
+	})
+}
+
+/// Reads packets until the connection closes, forwarding them as events
+async fn read_events(
+	mut conn: OwnedReadHalf,
+	events: UnboundedSender<Event>,
+	state: Arc<ServerState>,
+) {
+	loop {
+		match read_event(&mut conn, &state.blocks).await {
+			Ok(Some(event)) => {
+				if events.send(event).is_err() {
+					break;
+				}
+			}
+			// Packets have no length, so there is no way to skip one that isn't understood
+			Ok(None) => break,
+			Err(e) => {
+				debug!("Stopped reading from client: {}", e);
+				break;
+			}
+		}
+	}
+}
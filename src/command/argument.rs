@@ -0,0 +1,631 @@
+use ::uuid::Uuid;
+
+use crate::{
+	item::ItemRegistry,
+	modern::types::chat::ChatComponent,
+	player::{Position, Rotation},
+	state::ServerState,
+	world::BlockPos,
+};
+
+use super::{
+	selector::{Selector, Target},
+	Sender,
+};
+
+/// How much of the input a string argument takes
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StringKind {
+	/// A single word
+	Word,
+	/// Everything up to the end of the input
+	Greedy,
+}
+
+/// The type of a command argument, which decides how it is parsed and how clients complete it
+#[derive(Clone, Debug, PartialEq)]
+pub enum Argument {
+	Integer {
+		min: Option<i32>,
+		max: Option<i32>,
+	},
+	String(StringKind),
+	/// Entities given by name, UUID or selector, where `single` allows only one and `players` allows
+	/// only players
+	Entity {
+		single: bool,
+		players: bool,
+	},
+	/// Players who may not be online, given by name or by a selector for those who are
+	GameProfile,
+	/// Whole block coordinates, which may be relative to the sender
+	BlockPos,
+	/// Coordinates of any precision, which may be relative to the sender
+	Vec3,
+	/// The rest of the input, to be sent as a chat message
+	Message,
+	/// The rest of the input, read as a chat component written in JSON
+	Component,
+	/// An item such as `minecraft:stone`, where the namespace can be left out
+	Item,
+	/// A length of time in ticks, which can also be given in days or seconds with a `d` or `s` suffix
+	Time,
+}
+
+/// A parsed argument
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+	Integer(i32),
+	String(String),
+	Entity(EntityTarget),
+	Coordinates(Coordinates),
+	Item(i32),
+	Component(ChatComponent),
+}
+
+/// Who an entity argument refers to
+#[derive(Clone, Debug, PartialEq)]
+pub enum EntityTarget {
+	Name(String),
+	Uuid(Uuid),
+	Selector(Selector),
+}
+
+impl EntityTarget {
+	/// Finds the entities being referred to, which are only ever players when given by name
+	pub fn resolve(&self, sender: &Sender, state: &ServerState) -> Vec<Target> {
+		match self {
+			Self::Name(name) => {
+				let player = state
+					.players
+					.read()
+					.unwrap()
+					.values()
+					.find(|player| player.name().eq_ignore_ascii_case(name))
+					.map(|player| (player.entity_id, player.session().world));
+				player
+					.and_then(|(id, world)| {
+						let entity = state
+							.world(&world)?
+							.lock()
+							.unwrap()
+							.entities
+							.get(id)?
+							.clone();
+						Some(Target { world, entity })
+					})
+					.into_iter()
+					.collect()
+			}
+			Self::Uuid(uuid) => {
+				let mut worlds: Vec<&String> = state.worlds.keys().collect();
+				worlds.sort();
+				for world in worlds {
+					let store = state.worlds[world].lock().unwrap();
+					let found = store.entities.values().find(|e| e.uuid == *uuid).cloned();
+					if let Some(entity) = found {
+						return vec![Target {
+							world: world.clone(),
+							entity,
+						}];
+					}
+				}
+				Vec::new()
+			}
+			Self::Selector(selector) => selector.select(sender, state),
+		}
+	}
+}
+
+/// One axis of a position, which is an offset from the sender's position if it is relative
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Coordinate {
+	pub value: f64,
+	pub relative: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Coordinates {
+	/// Coordinates given with numbers and `~`
+	World([Coordinate; 3]),
+	/// Distances left, up and forwards from where the sender is looking, given with `^`
+	Local([f64; 3]),
+}
+
+impl Coordinates {
+	/// Works out the position the coordinates refer to from where the sender is and is looking
+	pub fn resolve(&self, origin: Position, rotation: Rotation) -> Position {
+		match self {
+			Self::World([x, y, z]) => {
+				let axis = |coordinate: &Coordinate, origin: f64| {
+					if coordinate.relative {
+						origin + coordinate.value
+					} else {
+						coordinate.value
+					}
+				};
+				Position::new(axis(x, origin.x), axis(y, origin.y), axis(z, origin.z))
+			}
+			Self::Local([left, up, forwards]) => {
+				// The same vectors vanilla uses, pointing forwards, up and left of the rotation
+				let yaw = (rotation.yaw as f64 + 90.0).to_radians();
+				let pitch = -(rotation.pitch as f64).to_radians();
+				let raised = -(rotation.pitch as f64 - 90.0).to_radians();
+				let forward = [
+					yaw.cos() * pitch.cos(),
+					pitch.sin(),
+					yaw.sin() * pitch.cos(),
+				];
+				let upward = [
+					yaw.cos() * raised.cos(),
+					raised.sin(),
+					yaw.sin() * raised.cos(),
+				];
+				let leftward = [
+					-(forward[1] * upward[2] - forward[2] * upward[1]),
+					-(forward[2] * upward[0] - forward[0] * upward[2]),
+					-(forward[0] * upward[1] - forward[1] * upward[0]),
+				];
+				let offset = |axis: usize| {
+					forward[axis] * forwards + upward[axis] * up + leftward[axis] * left
+				};
+				Position::new(
+					origin.x + offset(0),
+					origin.y + offset(1),
+					origin.z + offset(2),
+				)
+			}
+		}
+	}
+
+	/// Works out the block the coordinates refer to
+	pub fn block(&self, origin: Position, rotation: Rotation) -> BlockPos {
+		let position = self.resolve(origin, rotation);
+		BlockPos::new(
+			position.x.floor() as i32,
+			position.y.floor() as i32,
+			position.z.floor() as i32,
+		)
+	}
+}
+
+/// Steps through the text of a command
+pub struct Reader<'a> {
+	pub input: &'a str,
+	pub cursor: usize,
+}
+
+impl<'a> Reader<'a> {
+	pub fn new(input: &'a str) -> Self {
+		Self { input, cursor: 0 }
+	}
+
+	pub fn remaining(&self) -> &'a str {
+		&self.input[self.cursor..]
+	}
+
+	pub fn at_end(&self) -> bool {
+		self.cursor >= self.input.len()
+	}
+
+	pub fn peek(&self) -> Option<char> {
+		self.remaining().chars().next()
+	}
+
+	/// Reads up to the next space or the end of the input
+	pub fn read_word(&mut self) -> &'a str {
+		let remaining = self.remaining();
+		let length = remaining.find(' ').unwrap_or_else(|| remaining.len());
+		self.cursor += length;
+		&remaining[..length]
+	}
+
+	pub fn read_rest(&mut self) -> &'a str {
+		let remaining = self.remaining();
+		self.cursor = self.input.len();
+		remaining
+	}
+
+	fn read_coordinate(&mut self, whole: bool) -> Result<Coordinate, String> {
+		let word = self.read_word();
+		if word.is_empty() {
+			return Err(String::from("Expected a coordinate"));
+		}
+		let (relative, number) = match word.strip_prefix('~') {
+			Some(offset) => (true, offset),
+			None => (false, word),
+		};
+		let value = if number.is_empty() {
+			0.0
+		} else if whole && !relative {
+			number
+				.parse::<i32>()
+				.map_err(|_| format!("Invalid integer '{}'", number))? as f64
+		} else {
+			number
+				.parse::<f64>()
+				.map_err(|_| format!("Invalid double '{}'", number))?
+		};
+		Ok(Coordinate { value, relative })
+	}
+
+	/// Reads three coordinates separated by spaces, where `whole` only allows whole numbers that
+	/// aren't relative
+	fn read_coordinates(&mut self, whole: bool) -> Result<Coordinates, String> {
+		if self.peek() == Some('^') {
+			let mut local = [0.0; 3];
+			for (i, axis) in local.iter_mut().enumerate() {
+				if i > 0 && !self.skip_space() {
+					return Err(String::from("Incomplete (expected 3 coordinates)"));
+				}
+				let word = self.read_word();
+				let number = word
+					.strip_prefix('^')
+					.ok_or_else(|| String::from("Cannot mix world & local coordinates"))?;
+				if !number.is_empty() {
+					*axis = number
+						.parse()
+						.map_err(|_| format!("Invalid double '{}'", number))?;
+				}
+			}
+			return Ok(Coordinates::Local(local));
+		}
+		let mut coordinates = [Coordinate {
+			value: 0.0,
+			relative: false,
+		}; 3];
+		for (i, axis) in coordinates.iter_mut().enumerate() {
+			if i > 0 && !self.skip_space() {
+				return Err(String::from("Incomplete (expected 3 coordinates)"));
+			}
+			if self.peek() == Some('^') {
+				return Err(String::from("Cannot mix world & local coordinates"));
+			}
+			*axis = self.read_coordinate(whole)?;
+		}
+		Ok(Coordinates::World(coordinates))
+	}
+
+	/// Moves past a single space, returning false if there isn't one
+	pub fn skip_space(&mut self) -> bool {
+		if self.peek() == Some(' ') {
+			self.cursor += 1;
+			true
+		} else {
+			false
+		}
+	}
+}
+
+fn check_range<T: PartialOrd + ToString>(
+	value: T,
+	min: Option<T>,
+	max: Option<T>,
+	kind: &str,
+) -> Result<T, String> {
+	if let Some(min) = min {
+		if value < min {
+			return Err(format!(
+				"{} must not be less than {}, found {}",
+				kind,
+				min.to_string(),
+				value.to_string()
+			));
+		}
+	}
+	if let Some(max) = max {
+		if value > max {
+			return Err(format!(
+				"{} must not be more than {}, found {}",
+				kind,
+				max.to_string(),
+				value.to_string()
+			));
+		}
+	}
+	Ok(value)
+}
+
+/// Whether a suggestion completes what has been typed so far
+fn completes(suggestion: &str, typed: &str) -> bool {
+	suggestion.len() >= typed.len()
+		&& suggestion.is_char_boundary(typed.len())
+		&& suggestion[..typed.len()].eq_ignore_ascii_case(typed)
+}
+
+impl Argument {
+	/// Reads the argument from the command, leaving the reader just after it
+	pub fn parse(&self, reader: &mut Reader, items: &ItemRegistry) -> Result<Value, String> {
+		match self {
+			Self::Integer { min, max } => {
+				let word = reader.read_word();
+				let value = word
+					.parse::<i32>()
+					.map_err(|_| format!("Invalid integer '{}'", word))?;
+				check_range(value, *min, *max, "Integer").map(Value::Integer)
+			}
+			Self::String(StringKind::Word) => Ok(Value::String(String::from(reader.read_word()))),
+			Self::String(StringKind::Greedy) | Self::Message => {
+				Ok(Value::String(String::from(reader.read_rest())))
+			}
+			Self::Component => ChatComponent::from_json(reader.read_rest())
+				.map(Value::Component)
+				.map_err(|e| format!("Invalid chat component: {}", e)),
+			Self::Entity { single, players } => {
+				if reader.peek() == Some('@') {
+					let selector = Selector::parse(reader)?;
+					if *single && !selector.is_single() {
+						return Err(format!(
+							"Only one {} is allowed, but the provided selector allows more than one",
+							if *players { "player" } else { "entity" }
+						));
+					}
+					if *players && !selector.is_players() {
+						return Err(String::from(
+							"Only players may be affected by this command, but the provided selector \
+							 includes entities",
+						));
+					}
+					return Ok(Value::Entity(EntityTarget::Selector(selector)));
+				}
+				let word = reader.read_word();
+				if word.is_empty() {
+					return Err(String::from("Expected a player name or UUID"));
+				}
+				Ok(Value::Entity(match Uuid::parse_str(word) {
+					Ok(uuid) => EntityTarget::Uuid(uuid),
+					Err(_) => EntityTarget::Name(String::from(word)),
+				}))
+			}
+			Self::GameProfile => {
+				if reader.peek() == Some('@') {
+					let selector = Selector::parse(reader)?;
+					if !selector.is_players() {
+						return Err(String::from(
+							"Only players may be affected by this command, but the provided selector \
+							 includes entities",
+						));
+					}
+					return Ok(Value::Entity(EntityTarget::Selector(selector)));
+				}
+				let word = reader.read_word();
+				if word.is_empty() {
+					return Err(String::from("Expected a player name or UUID"));
+				}
+				Ok(Value::String(String::from(word)))
+			}
+			Self::BlockPos => reader.read_coordinates(true).map(Value::Coordinates),
+			Self::Vec3 => {
+				let mut coordinates = reader.read_coordinates(false)?;
+				// Whole numbers mean the middle of a block along the horizontal axes, as in vanilla
+				if let Coordinates::World([x, _, z]) = &mut coordinates {
+					for axis in [x, z].iter_mut() {
+						if !axis.relative && axis.value.fract() == 0.0 {
+							axis.value += 0.5;
+						}
+					}
+				}
+				Ok(Value::Coordinates(coordinates))
+			}
+			Self::Item => {
+				let word = reader.read_word();
+				let name = if word.contains(':') {
+					String::from(word)
+				} else {
+					format!("minecraft:{}", word)
+				};
+				match items.id(&name) {
+					Some(id) if id != 0 => Ok(Value::Item(id)),
+					_ => Err(format!("Unknown item '{}'", word)),
+				}
+			}
+			Self::Time => {
+				let word = reader.read_word();
+				let (number, scale) = match word.chars().last() {
+					Some('d') => (&word[..word.len() - 1], 24000.0),
+					Some('s') => (&word[..word.len() - 1], 20.0),
+					Some('t') => (&word[..word.len() - 1], 1.0),
+					_ => (word, 1.0),
+				};
+				let value = number
+					.parse::<f64>()
+					.map_err(|_| format!("Invalid double '{}'", number))?;
+				if value < 0.0 {
+					return Err(String::from("Tick count must be non-negative"));
+				}
+				Ok(Value::Integer((value * scale).round() as i32))
+			}
+		}
+	}
+
+	/// Possible ways to finish typing the argument
+	pub fn suggest(&self, typed: &str, state: &ServerState) -> Vec<String> {
+		let suggestions: Vec<String> = match self {
+			Self::Entity { players, .. } => {
+				let selectors: &[&str] = if *players {
+					&["@a", "@p", "@r", "@s"]
+				} else {
+					&["@a", "@e", "@p", "@r", "@s"]
+				};
+				let names = state.players.read().unwrap();
+				selectors
+					.iter()
+					.map(|selector| String::from(*selector))
+					.chain(names.values().map(|player| String::from(player.name())))
+					.collect()
+			}
+			Self::GameProfile => state
+				.players
+				.read()
+				.unwrap()
+				.values()
+				.map(|player| String::from(player.name()))
+				.collect(),
+			Self::BlockPos | Self::Vec3 if typed.is_empty() => vec![String::from("~ ~ ~")],
+			Self::Item => {
+				let namespaced = typed.contains(':');
+				state
+					.items
+					.names()
+					.filter(|name| *name != "minecraft:air")
+					.map(|name| {
+						if namespaced {
+							String::from(name)
+						} else {
+							String::from(name.trim_start_matches("minecraft:"))
+						}
+					})
+					.collect()
+			}
+			_ => Vec::new(),
+		};
+		suggestions
+			.into_iter()
+			.filter(|suggestion| completes(suggestion, typed))
+			.collect()
+	}
+
+	/// Whether clients should ask the server how to complete the argument, rather than working it out
+	/// themselves
+	pub fn asks_server(&self) -> bool {
+		matches!(self, Self::GameProfile | Self::String(StringKind::Word))
+	}
+}
+
+pub(super) fn complete(options: &[&str], typed: &str) -> Vec<String> {
+	options
+		.iter()
+		.filter(|option| completes(option, typed))
+		.map(|option| String::from(*option))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse(argument: Argument, text: &str) -> Result<Value, String> {
+		argument.parse(&mut Reader::new(text), &ItemRegistry::new())
+	}
+
+	fn world(coordinates: [(f64, bool); 3]) -> Value {
+		let [x, y, z] = coordinates;
+		let axis = |(value, relative)| Coordinate { value, relative };
+		Value::Coordinates(Coordinates::World([axis(x), axis(y), axis(z)]))
+	}
+
+	#[test]
+	fn parses_numbers() {
+		let integer = Argument::Integer {
+			min: Some(1),
+			max: Some(64),
+		};
+		assert_eq!(parse(integer.clone(), "64"), Ok(Value::Integer(64)));
+		assert!(parse(integer.clone(), "0").is_err());
+		assert!(parse(integer.clone(), "65").is_err());
+		assert!(parse(integer, "1.5").is_err());
+		assert_eq!(parse(Argument::Time, "1d"), Ok(Value::Integer(24000)));
+		assert_eq!(parse(Argument::Time, "0.5s"), Ok(Value::Integer(10)));
+		assert_eq!(parse(Argument::Time, "7t"), Ok(Value::Integer(7)));
+		assert!(parse(Argument::Time, "-1").is_err());
+		assert!(parse(Argument::Time, "d").is_err());
+	}
+
+	#[test]
+	fn parses_strings() {
+		let mut reader = Reader::new("one two");
+		let items = ItemRegistry::new();
+		assert_eq!(
+			Argument::String(StringKind::Word).parse(&mut reader, &items),
+			Ok(Value::String(String::from("one")))
+		);
+		assert_eq!(reader.remaining(), " two");
+		assert_eq!(
+			parse(Argument::Message, "hello @a and all"),
+			Ok(Value::String(String::from("hello @a and all")))
+		);
+	}
+
+	#[test]
+	fn parses_components() {
+		assert_eq!(
+			parse(Argument::Component, r#"{"text": "hi", "color": "gold"}"#),
+			Ok(Value::Component(ChatComponent::text("hi").color("gold")))
+		);
+		assert_eq!(
+			parse(Argument::Component, r#""plain text""#),
+			Ok(Value::Component(ChatComponent::text("plain text")))
+		);
+		assert!(parse(Argument::Component, "plain text").is_err());
+	}
+
+	#[test]
+	fn parses_coordinates() {
+		assert_eq!(
+			parse(Argument::BlockPos, "~ ~1 -3"),
+			Ok(world([(0.0, true), (1.0, true), (-3.0, false)]))
+		);
+		assert!(parse(Argument::BlockPos, "1.5 2 3").is_err());
+		assert_eq!(
+			parse(Argument::Vec3, "1 2 ~-0.5"),
+			Ok(world([(1.5, false), (2.0, false), (-0.5, true)]))
+		);
+		assert_eq!(
+			parse(Argument::Vec3, "^ ^2 ^-1"),
+			Ok(Value::Coordinates(Coordinates::Local([0.0, 2.0, -1.0])))
+		);
+		assert!(parse(Argument::Vec3, "1 2").is_err());
+		assert!(parse(Argument::Vec3, "^ 1 ^").is_err());
+		assert!(parse(Argument::Vec3, "~ ^ ~").is_err());
+		assert!(parse(Argument::Vec3, "~ ~ ~x").is_err());
+	}
+
+	#[test]
+	fn parses_entities() {
+		let many = Argument::Entity {
+			single: false,
+			players: false,
+		};
+		let player = Argument::Entity {
+			single: true,
+			players: true,
+		};
+		assert_eq!(
+			parse(many.clone(), "Steve"),
+			Ok(Value::Entity(EntityTarget::Name(String::from("Steve"))))
+		);
+		let uuid = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+		assert_eq!(
+			parse(many.clone(), uuid),
+			Ok(Value::Entity(EntityTarget::Uuid(
+				Uuid::parse_str(uuid).unwrap()
+			)))
+		);
+		assert!(parse(many.clone(), "@e[type=pig]").is_ok());
+		assert!(parse(many, "@e[limit=]").is_err());
+		assert!(parse(player.clone(), "@p").is_ok());
+		assert!(parse(player.clone(), "@a[limit=1]").is_ok());
+		assert!(parse(player.clone(), "@a").is_err());
+		assert!(parse(player.clone(), "@e[limit=1]").is_err());
+		assert!(parse(player, "").is_err());
+		assert!(parse(Argument::GameProfile, "@e").is_err());
+		assert_eq!(
+			parse(Argument::GameProfile, "Notch"),
+			Ok(Value::String(String::from("Notch")))
+		);
+	}
+
+	#[test]
+	fn parses_items() {
+		let items = ItemRegistry::new();
+		let stone = items.id("minecraft:stone");
+		assert!(stone.is_some());
+		assert_eq!(parse(Argument::Item, "stone").ok(), stone.map(Value::Item));
+		assert_eq!(
+			parse(Argument::Item, "minecraft:stone").ok(),
+			stone.map(Value::Item)
+		);
+		assert!(parse(Argument::Item, "air").is_err());
+		assert!(parse(Argument::Item, "not_an_item").is_err());
+	}
+}
@@ -0,0 +1,275 @@
+use ::chrono::{DateTime, Duration, Local};
+use ::std::net::IpAddr;
+
+use crate::{
+	lists::{self, Ban},
+	modern::types::chat::ChatComponent,
+	player::PlayerHandle,
+};
+
+use super::{
+	argument,
+	argument::{Argument, StringKind},
+	literal, CommandContext, CommandError, Dispatcher,
+};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("ban").requires("basalt.command.ban", 3).then(
+			argument("targets", Argument::GameProfile)
+				.executes(ban)
+				.then(argument("reason", Argument::Message).executes(ban)),
+		),
+	);
+	dispatcher.register(
+		literal("tempban")
+			.requires("basalt.command.tempban", 3)
+			.then(
+				argument("targets", Argument::GameProfile).then(
+					argument("duration", Argument::String(StringKind::Word))
+						.executes(tempban)
+						.then(argument("reason", Argument::Message).executes(tempban)),
+				),
+			),
+	);
+	dispatcher.register(
+		literal("ban-ip").requires("basalt.command.ban-ip", 3).then(
+			argument("target", Argument::String(StringKind::Word))
+				.executes(ban_ip)
+				.then(argument("reason", Argument::Message).executes(ban_ip)),
+		),
+	);
+	dispatcher.register(
+		literal("pardon")
+			.requires("basalt.command.pardon", 3)
+			.then(argument("targets", Argument::GameProfile).executes(pardon)),
+	);
+	dispatcher.register(
+		literal("pardon-ip")
+			.requires("basalt.command.pardon-ip", 3)
+			.then(argument("target", Argument::String(StringKind::Word)).executes(pardon_ip)),
+	);
+	dispatcher.register(
+		literal("banlist")
+			.requires("basalt.command.banlist", 3)
+			.executes(banlist)
+			.then(literal("players").executes(banlist_players))
+			.then(literal("ips").executes(banlist_ips)),
+	);
+}
+
+/// The reason given for a ban, or vanilla's default if there wasn't one
+fn reason(context: &CommandContext) -> ChatComponent {
+	match context.get("reason") {
+		Some(_) => context.message("reason"),
+		None => ChatComponent::text("Banned by an operator"),
+	}
+}
+
+/// Reads a length of time such as `1d12h`, made up of numbers of weeks, days, hours, minutes and
+/// seconds
+fn parse_duration(text: &str) -> Option<Duration> {
+	let mut total = Duration::zero();
+	let mut number = String::new();
+	for c in text.chars() {
+		if c.is_ascii_digit() {
+			number.push(c);
+			continue;
+		}
+		let amount: i64 = number.parse().ok()?;
+		number.clear();
+		total += match c {
+			'w' => Duration::weeks(amount),
+			'd' => Duration::days(amount),
+			'h' => Duration::hours(amount),
+			'm' => Duration::minutes(amount),
+			's' => Duration::seconds(amount),
+			_ => return None,
+		};
+	}
+	if !number.is_empty() || total <= Duration::zero() {
+		return None;
+	}
+	Some(total)
+}
+
+fn ban(context: &CommandContext) -> Result<i32, CommandError> {
+	ban_profiles(context, None)
+}
+
+fn tempban(context: &CommandContext) -> Result<i32, CommandError> {
+	let text = context.string("duration").unwrap_or_default();
+	let duration = parse_duration(text)
+		.ok_or_else(|| CommandError::new(format!("Invalid duration '{}'", text)))?;
+	ban_profiles(context, Some(Local::now() + duration))
+}
+
+fn ban_profiles(
+	context: &CommandContext,
+	expires: Option<DateTime<Local>>,
+) -> Result<i32, CommandError> {
+	let reason = reason(context);
+	let mut banned = 0;
+	for profile in context.profiles("targets")? {
+		let ban = Ban::new(
+			String::from(context.sender.name()),
+			reason.to_plain(),
+			expires,
+		);
+		if !lists::ban_player(context.state, &profile, ban.clone())? {
+			continue;
+		}
+		banned += 1;
+		let message = match expires {
+			Some(expires) => format!(
+				"Banned {} until {}: ",
+				profile.name,
+				expires.format("%Y-%m-%d %H:%M:%S")
+			),
+			None => format!("Banned {}: ", profile.name),
+		};
+		context.success(ChatComponent::text(message).append(reason.clone()));
+		let online = context
+			.state
+			.players
+			.read()
+			.unwrap()
+			.get(&profile.uuid)
+			.cloned();
+		if let Some(player) = online {
+			player.disconnect(ban.message("You are banned from this server."));
+		}
+	}
+	if banned == 0 {
+		return Err(CommandError::new(
+			"Nothing changed. The player is already banned",
+		));
+	}
+	Ok(banned)
+}
+
+fn ban_ip(context: &CommandContext) -> Result<i32, CommandError> {
+	let target = context.string("target").unwrap_or_default();
+	let address = match target.parse::<IpAddr>() {
+		Ok(address) => address,
+		Err(_) => context
+			.state
+			.players
+			.read()
+			.unwrap()
+			.values()
+			.find(|player| player.name().eq_ignore_ascii_case(target))
+			.map(|player| player.address.ip())
+			.ok_or_else(|| CommandError::new("Invalid IP address or unknown player"))?,
+	};
+	let reason = reason(context);
+	let ban = Ban::new(String::from(context.sender.name()), reason.to_plain(), None);
+	if !lists::ban_ip(context.state, address, ban.clone())? {
+		return Err(CommandError::new(
+			"Nothing changed. That IP is already banned",
+		));
+	}
+	context.success(ChatComponent::text(format!("Banned IP {}: ", address)).append(reason));
+	let affected: Vec<PlayerHandle> = context
+		.state
+		.players
+		.read()
+		.unwrap()
+		.values()
+		.filter(|player| player.address.ip() == address)
+		.cloned()
+		.collect();
+	if !affected.is_empty() {
+		let names: Vec<&str> = affected.iter().map(PlayerHandle::name).collect();
+		context.success(ChatComponent::text(format!(
+			"This ban affects {} player(s): {}",
+			affected.len(),
+			names.join(", ")
+		)));
+	}
+	for player in &affected {
+		player.disconnect(ban.message("Your IP address is banned from this server."));
+	}
+	Ok(affected.len() as i32)
+}
+
+fn pardon(context: &CommandContext) -> Result<i32, CommandError> {
+	let mut pardoned = 0;
+	for profile in context.profiles("targets")? {
+		if lists::pardon_player(context.state, &profile)? {
+			pardoned += 1;
+			context.success(ChatComponent::text(format!("Unbanned {}", profile.name)));
+		}
+	}
+	if pardoned == 0 {
+		return Err(CommandError::new(
+			"Nothing changed. The player isn't banned",
+		));
+	}
+	Ok(pardoned)
+}
+
+fn pardon_ip(context: &CommandContext) -> Result<i32, CommandError> {
+	let address = context
+		.string("target")
+		.unwrap_or_default()
+		.parse::<IpAddr>()
+		.map_err(|_| CommandError::new("Invalid IP address"))?;
+	if !lists::pardon_ip(context.state, address)? {
+		return Err(CommandError::new("Nothing changed. That IP isn't banned"));
+	}
+	context.success(ChatComponent::text(format!("Unbanned IP {}", address)));
+	Ok(1)
+}
+
+/// Names of the players who are banned, along with their bans
+fn player_bans(context: &CommandContext) -> Vec<(String, Ban)> {
+	let bans = context.state.banned_players.lock().unwrap();
+	bans.entries()
+		.iter()
+		.filter(|entry| !entry.ban.is_expired())
+		.map(|entry| (entry.name.clone(), entry.ban.clone()))
+		.collect()
+}
+
+/// Addresses that are banned, along with their bans
+fn ip_bans(context: &CommandContext) -> Vec<(String, Ban)> {
+	let bans = context.state.banned_ips.lock().unwrap();
+	bans.entries()
+		.iter()
+		.filter(|entry| !entry.ban.is_expired())
+		.map(|entry| (entry.ip.clone(), entry.ban.clone()))
+		.collect()
+}
+
+fn banlist(context: &CommandContext) -> Result<i32, CommandError> {
+	let mut bans = ip_bans(context);
+	bans.extend(player_bans(context));
+	list_bans(context, bans)
+}
+
+fn banlist_players(context: &CommandContext) -> Result<i32, CommandError> {
+	list_bans(context, player_bans(context))
+}
+
+fn banlist_ips(context: &CommandContext) -> Result<i32, CommandError> {
+	list_bans(context, ip_bans(context))
+}
+
+fn list_bans(context: &CommandContext, bans: Vec<(String, Ban)>) -> Result<i32, CommandError> {
+	if bans.is_empty() {
+		context.reply(ChatComponent::text("There are no bans"));
+		return Ok(0);
+	}
+	context.reply(ChatComponent::text(format!(
+		"There are {} ban(s):",
+		bans.len()
+	)));
+	for (banned, ban) in &bans {
+		context.reply(ChatComponent::text(format!(
+			"{} was banned by {}: {}",
+			banned, ban.source, ban.reason
+		)));
+	}
+	Ok(bans.len() as i32)
+}
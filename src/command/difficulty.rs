@@ -0,0 +1,51 @@
+use crate::{modern::types::chat::ChatComponent, player::PlayerMessage, world::Difficulty};
+
+use super::{literal, title_case, CommandContext, CommandError, Dispatcher, Executor};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	let mut command = literal("difficulty")
+		.requires("basalt.command.difficulty", 2)
+		.executes(query);
+	for (difficulty, executor) in Difficulty::ALL.iter().zip(EXECUTORS) {
+		command = command.then(literal(difficulty.name()).executes(*executor));
+	}
+	dispatcher.register(command);
+}
+
+/// What runs for each difficulty, in the same order as `Difficulty::ALL`
+const EXECUTORS: &[Executor] = &[
+	|context| set(context, Difficulty::Peaceful),
+	|context| set(context, Difficulty::Easy),
+	|context| set(context, Difficulty::Normal),
+	|context| set(context, Difficulty::Hard),
+];
+
+fn query(context: &CommandContext) -> Result<i32, CommandError> {
+	let difficulty = *context.state.difficulty.read().unwrap();
+	context.reply(ChatComponent::text(format!(
+		"The difficulty is {}",
+		title_case(difficulty.name())
+	)));
+	Ok(difficulty.id() as i32)
+}
+
+fn set(context: &CommandContext, difficulty: Difficulty) -> Result<i32, CommandError> {
+	{
+		let mut current = context.state.difficulty.write().unwrap();
+		if *current == difficulty {
+			return Err(CommandError::new(format!(
+				"The difficulty did not change; it is already set to {}",
+				title_case(difficulty.name())
+			)));
+		}
+		*current = difficulty;
+	}
+	for player in context.state.players.read().unwrap().values() {
+		player.send(PlayerMessage::Difficulty(difficulty));
+	}
+	context.success(ChatComponent::text(format!(
+		"The difficulty has been set to {}",
+		title_case(difficulty.name())
+	)));
+	Ok(difficulty.id() as i32)
+}
@@ -0,0 +1,81 @@
+use crate::{
+	modern::types::chat::ChatComponent,
+	player::{Gamemode, PlayerMessage},
+	types::MessagePosition,
+};
+
+use super::{
+	argument, argument::Argument, literal, title_case, CommandContext, CommandError, Dispatcher,
+	Executor,
+};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	let mut command = literal("gamemode").requires("basalt.command.gamemode", 2);
+	for (gamemode, executor) in Gamemode::ALL.iter().zip(EXECUTORS) {
+		command = command.then(
+			literal(gamemode.name()).executes(*executor).then(
+				argument(
+					"target",
+					Argument::Entity {
+						single: false,
+						players: true,
+					},
+				)
+				.executes(*executor),
+			),
+		);
+	}
+	dispatcher.register(command);
+}
+
+/// What runs for each gamemode, in the same order as `Gamemode::ALL`
+const EXECUTORS: &[Executor] = &[
+	|context| set(context, Gamemode::Survival),
+	|context| set(context, Gamemode::Creative),
+	|context| set(context, Gamemode::Adventure),
+	|context| set(context, Gamemode::Spectator),
+];
+
+/// How a gamemode is shown in messages, such as `Creative Mode`
+fn display(gamemode: Gamemode) -> String {
+	format!("{} Mode", title_case(gamemode.name()))
+}
+
+/// Changes the gamemode of the targets or the sender, skipping anyone already in it like vanilla
+fn set(context: &CommandContext, gamemode: Gamemode) -> Result<i32, CommandError> {
+	let players = if context.get("target").is_some() {
+		context.players("target")?
+	} else {
+		vec![context.player()?]
+	};
+	let own_id = context.sender.player().map(|player| player.entity_id);
+	let mut changed = 0;
+	for player in players {
+		if player.session().gamemode == gamemode {
+			continue;
+		}
+		player.send(PlayerMessage::Gamemode(gamemode));
+		if Some(player.entity_id) == own_id {
+			context.success(ChatComponent::text(format!(
+				"Set own game mode to {}",
+				display(gamemode)
+			)));
+		} else {
+			player.send(PlayerMessage::Message {
+				message: ChatComponent::text(format!(
+					"Your game mode has been updated to {}",
+					display(gamemode)
+				)),
+				position: MessagePosition::System,
+				sender: None,
+			});
+			context.success(ChatComponent::text(format!(
+				"Set {}'s game mode to {}",
+				player.name(),
+				display(gamemode)
+			)));
+		}
+		changed += 1;
+	}
+	Ok(changed)
+}
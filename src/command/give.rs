@@ -0,0 +1,63 @@
+use crate::{item, modern::types::chat::ChatComponent, player::PlayerMessage, state::ServerState};
+
+use super::{
+	argument, argument::Argument, literal, title_case, CommandContext, CommandError, Dispatcher,
+};
+
+/// Most stacks of an item that can be given at once
+const MAX_STACKS: i32 = 100;
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("give").requires("basalt.command.give", 2).then(
+			argument(
+				"targets",
+				Argument::Entity {
+					single: false,
+					players: true,
+				},
+			)
+			.then(
+				argument("item", Argument::Item).executes(give).then(
+					argument(
+						"count",
+						Argument::Integer {
+							min: Some(1),
+							max: None,
+						},
+					)
+					.executes(give),
+				),
+			),
+		),
+	);
+}
+
+/// How an item is shown in messages, such as `Diamond Sword`
+fn display(state: &ServerState, item: i32) -> String {
+	let name = state.items.name(item).unwrap_or("minecraft:air");
+	title_case(name.trim_start_matches("minecraft:"))
+}
+
+fn give(context: &CommandContext) -> Result<i32, CommandError> {
+	let item = context.item("item").unwrap_or_default();
+	let count = context.integer("count").unwrap_or(1);
+	let name = display(context.state, item);
+	let limit = item::max_stack(item) as i32 * MAX_STACKS;
+	if count > limit {
+		return Err(CommandError::new(format!(
+			"Can't give more than {} of {}",
+			limit, name
+		)));
+	}
+	let players = context.players("targets")?;
+	for player in &players {
+		player.send(PlayerMessage::Give { item, count });
+	}
+	let message = match players.as_slice() {
+		[player] => format!("Gave {} [{}] to {}", count, name, player.name()),
+		_ => format!("Gave {} [{}] to {} players", count, name, players.len()),
+	};
+	context.success(ChatComponent::text(message));
+	Ok(players.len() as i32)
+}
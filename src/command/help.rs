@@ -0,0 +1,35 @@
+use crate::modern::types::chat::ChatComponent;
+
+use super::{
+	argument,
+	argument::{Argument, StringKind},
+	literal, CommandContext, CommandError, Dispatcher,
+};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("help")
+			.requires("basalt.command.help", 0)
+			.executes(help)
+			.then(argument("command", Argument::String(StringKind::Greedy)).executes(help)),
+	);
+}
+
+/// Lists how to use each command, which is also how classic players find out what commands there
+/// are since their clients can't complete them
+fn help(context: &CommandContext) -> Result<i32, CommandError> {
+	let command = context.string("command");
+	let usage = context
+		.state
+		.commands
+		.usage(command, context.sender, context.state);
+	if usage.is_empty() {
+		return Err(CommandError::new(
+			"Unknown command or insufficient permissions",
+		));
+	}
+	for line in &usage {
+		context.reply(ChatComponent::text(format!("/{}", line)));
+	}
+	Ok(usage.len() as i32)
+}
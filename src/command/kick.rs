@@ -0,0 +1,34 @@
+use crate::modern::types::chat::ChatComponent;
+
+use super::{argument, argument::Argument, literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("kick").requires("basalt.command.kick", 3).then(
+			argument(
+				"targets",
+				Argument::Entity {
+					single: false,
+					players: true,
+				},
+			)
+			.executes(kick)
+			.then(argument("reason", Argument::Message).executes(kick)),
+		),
+	);
+}
+
+fn kick(context: &CommandContext) -> Result<i32, CommandError> {
+	let reason = match context.get("reason") {
+		Some(_) => context.message("reason"),
+		None => ChatComponent::text("Kicked by an operator"),
+	};
+	let players = context.players("targets")?;
+	for player in &players {
+		player.disconnect(reason.to_plain());
+		context.success(
+			ChatComponent::text(format!("Kicked {}: ", player.name())).append(reason.clone()),
+		);
+	}
+	Ok(players.len() as i32)
+}
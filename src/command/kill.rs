@@ -0,0 +1,48 @@
+use crate::{modern::types::chat::ChatComponent, player::PlayerMessage};
+
+use super::{argument, argument::Argument, literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("kill")
+			.requires("basalt.command.kill", 2)
+			.executes(kill)
+			.then(
+				argument(
+					"targets",
+					Argument::Entity {
+						single: false,
+						players: false,
+					},
+				)
+				.executes(kill),
+			),
+	);
+}
+
+/// Kills players, and removes any other entity as there is nothing for it to leave behind
+fn kill(context: &CommandContext) -> Result<i32, CommandError> {
+	let targets = if context.get("targets").is_some() {
+		context.entities("targets")?
+	} else {
+		vec![context.entity()?]
+	};
+	for target in &targets {
+		match target.player(context.state) {
+			Some(player) => {
+				player.send(PlayerMessage::Kill);
+			}
+			None => {
+				if let Some(world) = context.state.world(&target.world) {
+					world.lock().unwrap().entities.remove(target.entity.id);
+				}
+			}
+		}
+	}
+	let message = match targets.as_slice() {
+		[target] => ChatComponent::text("Killed ").append(target.name()),
+		_ => ChatComponent::text(format!("Killed {} entities", targets.len())),
+	};
+	context.success(message);
+	Ok(targets.len() as i32)
+}
@@ -0,0 +1,46 @@
+use crate::modern::types::chat::ChatComponent;
+
+use super::{literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("list")
+			.requires("basalt.command.list", 0)
+			.executes(list)
+			.then(literal("uuids").executes(list_uuids)),
+	);
+}
+
+fn list(context: &CommandContext) -> Result<i32, CommandError> {
+	show(context, false)
+}
+
+fn list_uuids(context: &CommandContext) -> Result<i32, CommandError> {
+	show(context, true)
+}
+
+/// Lists who is online, by name and optionally by UUID as well
+fn show(context: &CommandContext, uuids: bool) -> Result<i32, CommandError> {
+	let mut names: Vec<String> = context
+		.state
+		.players
+		.read()
+		.unwrap()
+		.values()
+		.map(|player| {
+			if uuids {
+				format!("{} ({})", player.name(), player.uuid())
+			} else {
+				String::from(player.name())
+			}
+		})
+		.collect();
+	names.sort_by_key(|name| name.to_lowercase());
+	context.reply(ChatComponent::text(format!(
+		"There are {} of a max of {} players online: {}",
+		names.len(),
+		context.state.config().minecraft.max_players,
+		names.join(", ")
+	)));
+	Ok(names.len() as i32)
+}
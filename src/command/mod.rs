@@ -0,0 +1,866 @@
+use ::log::{info, warn};
+use ::std::{io, sync::Arc};
+
+use crate::{
+	auth::GameProfile,
+	item::ItemRegistry,
+	modern::types::chat::ChatComponent,
+	permissions,
+	player::{PlayerHandle, PlayerMessage, Position, Rotation},
+	state::ServerState,
+	types::MessagePosition,
+};
+
+pub mod argument;
+mod bans;
+mod difficulty;
+mod gamemode;
+mod give;
+mod help;
+mod kick;
+mod kill;
+mod list;
+mod msg;
+mod ops;
+mod reload;
+mod save;
+mod say;
+mod seed;
+pub mod selector;
+mod spawn;
+mod stop;
+mod teleport;
+mod tellraw;
+mod time;
+mod tps;
+mod weather;
+mod whitelist;
+
+use argument::{Argument, Coordinates, EntityTarget, Reader, Value};
+use selector::Target;
+
+/// Runs a command once its arguments are parsed, returning how many things it affected
+pub type Executor = fn(&CommandContext) -> Result<i32, CommandError>;
+
+/// Why a command failed, as shown to whoever ran it
+#[derive(Clone, Debug)]
+pub struct CommandError(pub ChatComponent);
+
+impl From<io::Error> for CommandError {
+	fn from(error: io::Error) -> Self {
+		warn!("Couldn't execute a command: {}", error);
+		Self::new("An unexpected error occurred trying to execute that command")
+	}
+}
+
+impl CommandError {
+	pub fn new<S: Into<String>>(message: S) -> Self {
+		Self(ChatComponent::text(message))
+	}
+
+	/// An error in the text of a command, shown with the part of the command it happened in
+	fn syntax(message: &str, input: &str, cursor: usize) -> Self {
+		// Like vanilla, only the last few characters before the error are shown
+		let start = input[..cursor]
+			.char_indices()
+			.rev()
+			.nth(9)
+			.map_or(0, |(i, _)| i);
+		let before = if start > 0 {
+			format!("...{}", &input[start..cursor])
+		} else {
+			String::from(&input[..cursor])
+		};
+		Self(
+			ChatComponent::text(format!("{}\n", message))
+				.append(ChatComponent::text(before).color("gray"))
+				.append(ChatComponent::text(&input[cursor..]).color("red"))
+				.append(ChatComponent::text("<--[HERE]").color("red").italic()),
+		)
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeKind {
+	Root,
+	Literal(String),
+	Argument { name: String, argument: Argument },
+}
+
+/// A node of the command tree, which is a word or argument that can follow the node above it
+#[derive(Clone)]
+pub struct CommandNode {
+	pub kind: NodeKind,
+	pub children: Vec<CommandNode>,
+	/// What runs when the command ends at this node, if it can
+	pub executor: Option<Executor>,
+	/// Permission node needed to use this node, along with the operator level that grants it when
+	/// the player's permissions don't mention it
+	pub permission: Option<(&'static str, u8)>,
+}
+
+/// Creates a node for a fixed word
+pub fn literal(name: &str) -> CommandNode {
+	CommandNode::new(NodeKind::Literal(String::from(name)))
+}
+
+/// Creates a node for an argument
+pub fn argument(name: &str, argument: Argument) -> CommandNode {
+	CommandNode::new(NodeKind::Argument {
+		name: String::from(name),
+		argument,
+	})
+}
+
+impl CommandNode {
+	fn new(kind: NodeKind) -> Self {
+		Self {
+			kind,
+			children: Vec::new(),
+			executor: None,
+			permission: None,
+		}
+	}
+
+	pub fn then(mut self, child: CommandNode) -> Self {
+		self.children.push(child);
+		self
+	}
+
+	pub fn executes(mut self, executor: Executor) -> Self {
+		self.executor = Some(executor);
+		self
+	}
+
+	pub fn requires(mut self, node: &'static str, level: u8) -> Self {
+		self.permission = Some((node, level));
+		self
+	}
+
+	pub fn name(&self) -> &str {
+		match &self.kind {
+			NodeKind::Root => "",
+			NodeKind::Literal(name) | NodeKind::Argument { name, .. } => name,
+		}
+	}
+
+	/// How the node is shown in usage, which is `<name>` for arguments
+	fn label(&self) -> String {
+		match &self.kind {
+			NodeKind::Argument { name, .. } => format!("<{}>", name),
+			_ => String::from(self.name()),
+		}
+	}
+
+	/// A short summary of how the command continues from this node, in the style vanilla uses
+	fn usage(&self) -> String {
+		let mut usage = self.label();
+		let rest = match self.children.as_slice() {
+			[] => return usage,
+			[child] => match child.children.as_slice() {
+				[] => child.label(),
+				_ => child.usage(),
+			},
+			children => {
+				let labels: Vec<String> = children.iter().map(CommandNode::label).collect();
+				labels.join("|")
+			}
+		};
+		let multiple = self.children.len() > 1;
+		usage.push(' ');
+		usage.push_str(&match (self.executor.is_some(), multiple) {
+			(true, _) => format!("[{}]", rest),
+			(false, true) => format!("({})", rest),
+			(false, false) => rest,
+		});
+		usage
+	}
+
+	/// A copy of the tree with only the nodes a sender can use
+	fn filter(&self, sender: &Sender, state: &ServerState) -> Self {
+		Self {
+			kind: self.kind.clone(),
+			children: self
+				.children
+				.iter()
+				.filter(|child| sender.can_use(child, state))
+				.map(|child| child.filter(sender, state))
+				.collect(),
+			executor: self.executor,
+			permission: self.permission,
+		}
+	}
+}
+
+/// Whoever is running a command
+#[derive(Clone)]
+pub enum Sender {
+	/// The server console, which has every permission
+	Console,
+	Player(PlayerHandle),
+}
+
+impl Sender {
+	/// The name commands refer to the sender by, which is `Server` for the console like in vanilla
+	pub fn name(&self) -> &str {
+		match self {
+			Self::Console => "Server",
+			Self::Player(player) => player.name(),
+		}
+	}
+
+	pub fn player(&self) -> Option<&PlayerHandle> {
+		match self {
+			Self::Console => None,
+			Self::Player(player) => Some(player),
+		}
+	}
+
+	/// Whether the sender has a permission, which the console always does
+	pub fn has_permission(&self, state: &ServerState, node: &str, level: u8) -> bool {
+		match self {
+			Self::Console => true,
+			Self::Player(player) => {
+				let world = player.session().world;
+				permissions::check(state, &player.profile, &world, node, level)
+			}
+		}
+	}
+
+	fn can_use(&self, node: &CommandNode, state: &ServerState) -> bool {
+		match node.permission {
+			Some((permission, level)) => self.has_permission(state, permission, level),
+			None => true,
+		}
+	}
+
+	pub fn send_message(&self, message: ChatComponent) {
+		match self {
+			Self::Console => info!("{}", message.to_plain()),
+			Self::Player(player) => {
+				player.send(PlayerMessage::Message {
+					message,
+					position: MessagePosition::System,
+					sender: None,
+				});
+			}
+		}
+	}
+
+	/// Where the sender is, which is the spawn of the default world for the console, along with the
+	/// name of the world and which way they are facing
+	pub fn location(&self, state: &ServerState) -> (String, Position, Rotation) {
+		match self {
+			Self::Console => {
+				let world = state.default_world.clone();
+				let position = state.worlds[&world].lock().unwrap().spawn_position();
+				(world, position, Rotation::default())
+			}
+			Self::Player(player) => {
+				let session = player.session();
+				(session.world, session.position, session.rotation)
+			}
+		}
+	}
+}
+
+/// Everything a command has to go on when it runs
+pub struct CommandContext<'a> {
+	pub state: &'a Arc<ServerState>,
+	pub sender: &'a Sender,
+	arguments: Vec<(String, Value)>,
+}
+
+impl<'a> CommandContext<'a> {
+	/// Looks up an argument by name, which is None if the command was used without it
+	pub fn get(&self, name: &str) -> Option<&Value> {
+		self.arguments
+			.iter()
+			.find(|(argument, _)| argument == name)
+			.map(|(_, value)| value)
+	}
+
+	pub fn integer(&self, name: &str) -> Option<i32> {
+		match self.get(name) {
+			Some(Value::Integer(value)) => Some(*value),
+			_ => None,
+		}
+	}
+
+	pub fn string(&self, name: &str) -> Option<&str> {
+		match self.get(name) {
+			Some(Value::String(value)) => Some(value),
+			_ => None,
+		}
+	}
+
+	pub fn coordinates(&self, name: &str) -> Option<Coordinates> {
+		match self.get(name) {
+			Some(Value::Coordinates(value)) => Some(*value),
+			_ => None,
+		}
+	}
+
+	pub fn item(&self, name: &str) -> Option<i32> {
+		match self.get(name) {
+			Some(Value::Item(value)) => Some(*value),
+			_ => None,
+		}
+	}
+
+	/// A message argument as chat, where selectors are shown as names if the sender may use them
+	pub fn message(&self, name: &str) -> ChatComponent {
+		let text = self.string(name).unwrap_or_default();
+		if self.may_use_selectors() {
+			selector::message(text, self.sender, self.state)
+		} else {
+			ChatComponent::text(text)
+		}
+	}
+
+	/// A chat component argument, showing any selectors in it as the names of what they pick out
+	/// if the sender may use selectors, and as they were written if not
+	pub fn component(&self, name: &str) -> ChatComponent {
+		let component = match self.get(name) {
+			Some(Value::Component(component)) => component.clone(),
+			_ => ChatComponent::text(""),
+		};
+		if self.may_use_selectors() {
+			selector::resolve_component(component, self.sender, self.state)
+		} else {
+			component.resolve_selectors(&|text| ChatComponent::text(text))
+		}
+	}
+
+	fn may_use_selectors(&self) -> bool {
+		self.sender
+			.has_permission(self.state, "basalt.command.selector", 2)
+	}
+
+	/// Resolves an entity argument, which may only use a selector if the sender is allowed to
+	fn resolve(&self, name: &str) -> Result<Vec<Target>, CommandError> {
+		match self.get(name) {
+			Some(Value::Entity(EntityTarget::Selector(_))) if !self.may_use_selectors() => {
+				Err(CommandError::new("Selector not allowed"))
+			}
+			Some(Value::Entity(target)) => Ok(target.resolve(self.sender, self.state)),
+			_ => Ok(Vec::new()),
+		}
+	}
+
+	/// The entities an entity argument refers to, which is an error if there are none
+	pub fn entities(&self, name: &str) -> Result<Vec<Target>, CommandError> {
+		let targets = self.resolve(name)?;
+		if targets.is_empty() {
+			return Err(CommandError::new("No entity was found"));
+		}
+		Ok(targets)
+	}
+
+	/// The online players an entity argument refers to, which is an error if there are none
+	pub fn players(&self, name: &str) -> Result<Vec<PlayerHandle>, CommandError> {
+		let players: Vec<_> = self
+			.resolve(name)?
+			.iter()
+			.filter_map(|target| target.player(self.state))
+			.collect();
+		if players.is_empty() {
+			return Err(CommandError::new("No player was found"));
+		}
+		Ok(players)
+	}
+
+	/// The players a game profile argument refers to, who don't have to be online when given by name
+	pub fn profiles(&self, name: &str) -> Result<Vec<GameProfile>, CommandError> {
+		let unknown = || CommandError::new("That player does not exist");
+		let name = match self.get(name) {
+			Some(Value::Entity(_)) => {
+				let profiles: Vec<_> = self
+					.players(name)
+					.map_err(|_| unknown())?
+					.into_iter()
+					.map(|player| player.profile)
+					.collect();
+				return Ok(profiles);
+			}
+			Some(Value::String(name)) => name,
+			_ => return Err(unknown()),
+		};
+		let online = self
+			.state
+			.players
+			.read()
+			.unwrap()
+			.values()
+			.find(|player| player.name().eq_ignore_ascii_case(name))
+			.map(|player| player.profile.clone());
+		if let Some(profile) = online {
+			return Ok(vec![profile]);
+		}
+		let valid = (3..=16).contains(&name.len())
+			&& name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+		if !valid {
+			return Err(unknown());
+		}
+		GameProfile::offline(name.clone())
+			.map(|profile| vec![profile])
+			.map_err(|_| unknown())
+	}
+
+	/// The sender as a player, for commands that only make sense for one
+	pub fn player(&self) -> Result<PlayerHandle, CommandError> {
+		self.sender
+			.player()
+			.cloned()
+			.ok_or_else(|| CommandError::new("A player is required to run this command here"))
+	}
+
+	/// The sender as an entity, for commands that act on whoever runs them by default
+	pub fn entity(&self) -> Result<Target, CommandError> {
+		let player = self
+			.sender
+			.player()
+			.ok_or_else(|| CommandError::new("An entity is required to run this command here"))?;
+		EntityTarget::Uuid(player.uuid())
+			.resolve(self.sender, self.state)
+			.pop()
+			.ok_or_else(|| CommandError::new("An entity is required to run this command here"))
+	}
+
+	/// Sends feedback to whoever ran the command
+	pub fn reply(&self, message: ChatComponent) {
+		self.sender.send_message(message);
+	}
+
+	/// Sends feedback to whoever ran the command, and lets operators and the console know what was
+	/// done the way vanilla does
+	pub fn success(&self, message: ChatComponent) {
+		self.reply(message.clone());
+		let notice = ChatComponent::text(format!("[{}: ", self.sender.name()))
+			.append(message)
+			.append(ChatComponent::text("]"))
+			.color("gray")
+			.italic();
+		let own_id = self.sender.player().map(|player| player.entity_id);
+		let players: Vec<_> = self
+			.state
+			.players
+			.read()
+			.unwrap()
+			.values()
+			.filter(|player| Some(player.entity_id) != own_id)
+			.cloned()
+			.collect();
+		for player in players {
+			let sender = Sender::Player(player);
+			if sender.has_permission(self.state, "basalt.command.feedback", 1) {
+				sender.send_message(notice.clone());
+			}
+		}
+		if own_id.is_some() {
+			info!("{}", notice.to_plain());
+		}
+	}
+}
+
+/// Capitalises each word of a name such as `diamond_sword`, for showing it in messages
+pub fn title_case(name: &str) -> String {
+	name.split('_')
+		.map(|word| {
+			let mut chars = word.chars();
+			match chars.next() {
+				Some(first) => first.to_uppercase().chain(chars).collect(),
+				None => String::new(),
+			}
+		})
+		.collect::<Vec<String>>()
+		.join(" ")
+}
+
+/// Keeps a parse error if it is at least as far into the input as any other so far
+fn fail(error: &mut Option<(usize, String)>, cursor: usize, message: String) {
+	if error
+		.as_ref()
+		.map_or(true, |(furthest, _)| cursor >= *furthest)
+	{
+		*error = Some((cursor, message));
+	}
+}
+
+/// Every command the server knows, which parses and runs commands and works out how to complete them
+pub struct Dispatcher {
+	root: CommandNode,
+}
+
+impl Dispatcher {
+	pub fn new() -> Self {
+		let mut dispatcher = Self {
+			root: CommandNode::new(NodeKind::Root),
+		};
+		bans::register(&mut dispatcher);
+		difficulty::register(&mut dispatcher);
+		gamemode::register(&mut dispatcher);
+		give::register(&mut dispatcher);
+		help::register(&mut dispatcher);
+		kick::register(&mut dispatcher);
+		kill::register(&mut dispatcher);
+		list::register(&mut dispatcher);
+		msg::register(&mut dispatcher);
+		ops::register(&mut dispatcher);
+		reload::register(&mut dispatcher);
+		save::register(&mut dispatcher);
+		say::register(&mut dispatcher);
+		seed::register(&mut dispatcher);
+		spawn::register(&mut dispatcher);
+		stop::register(&mut dispatcher);
+		teleport::register(&mut dispatcher);
+		tellraw::register(&mut dispatcher);
+		time::register(&mut dispatcher);
+		tps::register(&mut dispatcher);
+		weather::register(&mut dispatcher);
+		whitelist::register(&mut dispatcher);
+		dispatcher
+	}
+
+	pub fn register(&mut self, command: CommandNode) {
+		self.root.children.push(command);
+	}
+
+	/// The commands a sender can use, to be sent to their client so it can complete them
+	pub fn tree(&self, sender: &Sender, state: &ServerState) -> CommandNode {
+		self.root.filter(sender, state)
+	}
+
+	/// Parses and runs a command, which is given without the leading slash
+	pub fn execute(
+		&self,
+		input: &str,
+		sender: &Sender,
+		state: &Arc<ServerState>,
+	) -> Result<i32, CommandError> {
+		let mut reader = Reader::new(input);
+		let mut arguments = Vec::new();
+		let mut error = None;
+		let executor = self.parse(
+			&self.root,
+			&mut reader,
+			&mut arguments,
+			&mut error,
+			&|node| sender.can_use(node, state),
+			&state.items,
+		);
+		match executor {
+			Some(executor) => executor(&CommandContext {
+				state,
+				sender,
+				arguments,
+			}),
+			None => {
+				let (cursor, message) = error.unwrap_or_else(|| {
+					(
+						0,
+						String::from("Unknown or incomplete command, see below for error"),
+					)
+				});
+				Err(CommandError::syntax(&message, input, cursor))
+			}
+		}
+	}
+
+	/// Runs a command, telling the sender if it failed
+	pub fn run(&self, input: &str, sender: &Sender, state: &Arc<ServerState>) {
+		if let Err(CommandError(message)) = self.execute(input, sender, state) {
+			sender.send_message(message.color("red"));
+		}
+	}
+
+	/// Parses the children of a node after the node itself has been read, returning what should run
+	/// if the rest of the input makes up a command. Only the nodes `can_use` allows are tried, and the
+	/// error furthest into the input is kept so the one closest to what the sender meant can be shown.
+	fn parse(
+		&self,
+		node: &CommandNode,
+		reader: &mut Reader,
+		arguments: &mut Vec<(String, Value)>,
+		error: &mut Option<(usize, String)>,
+		can_use: &dyn Fn(&CommandNode) -> bool,
+		items: &ItemRegistry,
+	) -> Option<Executor> {
+		if reader.at_end() {
+			if node.executor.is_none() {
+				fail(
+					error,
+					reader.cursor,
+					String::from("Unknown or incomplete command, see below for error"),
+				);
+			}
+			return node.executor;
+		}
+		if node.kind != NodeKind::Root && !reader.skip_space() {
+			fail(
+				error,
+				reader.cursor,
+				String::from("Expected whitespace to end one argument, but found trailing data"),
+			);
+			return None;
+		}
+		let start = reader.cursor;
+		for child in &node.children {
+			if !can_use(child) {
+				continue;
+			}
+			reader.cursor = start;
+			let count = arguments.len();
+			match &child.kind {
+				NodeKind::Literal(name) => {
+					if reader.read_word() != name {
+						continue;
+					}
+				}
+				NodeKind::Argument { name, argument } => match argument.parse(reader, items) {
+					Ok(value) => arguments.push((name.clone(), value)),
+					Err(message) => {
+						fail(error, start, message);
+						continue;
+					}
+				},
+				NodeKind::Root => continue,
+			}
+			if let Some(executor) = self.parse(child, reader, arguments, error, can_use, items) {
+				return Some(executor);
+			}
+			arguments.truncate(count);
+		}
+		// An argument that failed to parse here has more to say about what went wrong
+		if error
+			.as_ref()
+			.map_or(true, |(furthest, _)| start > *furthest)
+		{
+			fail(
+				error,
+				start,
+				String::from("Unknown or incomplete command, see below for error"),
+			);
+		}
+		None
+	}
+
+	/// Works out how the last word of a partly typed command could be finished, returning where in the
+	/// input the suggestions start along with the suggestions themselves
+	pub fn suggest(
+		&self,
+		input: &str,
+		sender: &Sender,
+		state: &ServerState,
+	) -> (usize, Vec<String>) {
+		let mut suggestions = Vec::new();
+		self.suggest_from(
+			&self.root,
+			&mut Reader::new(input),
+			&mut suggestions,
+			sender,
+			state,
+		);
+		// Only the suggestions replacing the least text are kept, as they all have to start at once
+		let start = suggestions
+			.iter()
+			.map(|(start, _)| *start)
+			.max()
+			.unwrap_or_else(|| input.len());
+		let mut matches: Vec<String> = suggestions
+			.into_iter()
+			.filter(|(from, _)| *from == start)
+			.map(|(_, suggestion)| suggestion)
+			.collect();
+		matches.sort();
+		matches.dedup();
+		(start, matches)
+	}
+
+	fn suggest_from(
+		&self,
+		node: &CommandNode,
+		reader: &mut Reader,
+		suggestions: &mut Vec<(usize, String)>,
+		sender: &Sender,
+		state: &ServerState,
+	) {
+		if node.kind != NodeKind::Root && !reader.skip_space() {
+			return;
+		}
+		let start = reader.cursor;
+		for child in &node.children {
+			if !sender.can_use(child, state) {
+				continue;
+			}
+			reader.cursor = start;
+			let typed = reader.remaining();
+			let parsed = match &child.kind {
+				NodeKind::Literal(name) => reader.read_word() == name,
+				NodeKind::Argument { argument, .. } => argument.parse(reader, &state.items).is_ok(),
+				NodeKind::Root => false,
+			};
+			if parsed && !reader.at_end() {
+				self.suggest_from(child, reader, suggestions, sender, state);
+				continue;
+			}
+			// The child is what is being typed, so it is what gets completed
+			let completions = match &child.kind {
+				NodeKind::Literal(name) => argument::complete(&[name], typed),
+				NodeKind::Argument { argument, .. } => argument.suggest(typed, state),
+				NodeKind::Root => Vec::new(),
+			};
+			suggestions.extend(completions.into_iter().map(|s| (start, s)));
+		}
+	}
+
+	/// Usage of every command a sender can use, or of the commands starting with a word
+	pub fn usage(
+		&self,
+		command: Option<&str>,
+		sender: &Sender,
+		state: &ServerState,
+	) -> Vec<String> {
+		let tree = self.tree(sender, state);
+		match command {
+			Some(command) => tree
+				.children
+				.iter()
+				.filter(|child| child.name() == command)
+				.flat_map(|child| {
+					let mut lines = Vec::new();
+					if child.executor.is_some() {
+						lines.push(child.label());
+					}
+					lines.extend(
+						child
+							.children
+							.iter()
+							.map(|branch| format!("{} {}", child.label(), branch.usage())),
+					);
+					lines
+				})
+				.collect(),
+			None => tree.children.iter().map(CommandNode::usage).collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn ok(_: &CommandContext) -> Result<i32, CommandError> {
+		Ok(1)
+	}
+
+	/// Parses a command the way the console would, returning the arguments or where and why it failed
+	fn parse(
+		dispatcher: &Dispatcher,
+		input: &str,
+	) -> Result<Vec<(String, Value)>, (usize, String)> {
+		let mut reader = Reader::new(input);
+		let mut arguments = Vec::new();
+		let mut error = None;
+		let executor = dispatcher.parse(
+			&dispatcher.root,
+			&mut reader,
+			&mut arguments,
+			&mut error,
+			&|_| true,
+			&ItemRegistry::new(),
+		);
+		match executor {
+			Some(_) => Ok(arguments),
+			None => Err(error.unwrap()),
+		}
+	}
+
+	fn names(arguments: &[(String, Value)]) -> Vec<&str> {
+		arguments.iter().map(|(name, _)| name.as_str()).collect()
+	}
+
+	#[test]
+	fn parses_arguments_along_the_tree() {
+		let dispatcher = Dispatcher::new();
+		let arguments = parse(&dispatcher, "give @a[distance=..5] minecraft:stone 64").unwrap();
+		assert_eq!(names(&arguments), ["targets", "item", "count"]);
+		assert_eq!(arguments[2].1, Value::Integer(64));
+		assert_eq!(
+			names(&parse(&dispatcher, "give Steve stone").unwrap()),
+			["targets", "item"]
+		);
+		// A location and a destination can both follow the targets, and whichever parses is used
+		assert_eq!(
+			names(&parse(&dispatcher, "tp @a ~ ~10 ~").unwrap()),
+			["targets", "location"]
+		);
+		assert_eq!(
+			names(&parse(&dispatcher, "tp @a Steve").unwrap()),
+			["targets", "destination"]
+		);
+		assert!(parse(&dispatcher, "time set day").unwrap().is_empty());
+		assert_eq!(
+			parse(&dispatcher, "time add 2d").unwrap()[0].1,
+			Value::Integer(48000)
+		);
+	}
+
+	#[test]
+	fn reports_where_parsing_failed() {
+		let dispatcher = Dispatcher::new();
+		let failure = |input: &str| parse(&dispatcher, input).unwrap_err();
+		assert_eq!(failure("").0, 0);
+		assert_eq!(failure("nonexistent").0, 0);
+		// Incomplete commands fail at the end of the input
+		assert_eq!(failure("give @a").0, 7);
+		// The argument that didn't parse is blamed rather than the command
+		let (cursor, message) = failure("give @a[limit=0] stone");
+		assert_eq!(cursor, 5);
+		assert_eq!(message, "Limit must be at least 1");
+		let (cursor, message) = failure("give @a stone 0");
+		assert_eq!(cursor, 14);
+		assert_eq!(message, "Integer must not be less than 1, found 0");
+		assert_eq!(failure("time set day later").0, 13);
+		let (cursor, message) = failure("give @a[tag=x]stone");
+		assert_eq!(cursor, 14);
+		assert_eq!(
+			message,
+			"Expected whitespace to end one argument, but found trailing data"
+		);
+		assert_eq!(failure("time set  day").0, 9);
+	}
+
+	#[test]
+	fn only_tries_usable_nodes() {
+		let mut dispatcher = Dispatcher {
+			root: CommandNode::new(NodeKind::Root),
+		};
+		dispatcher.register(
+			literal("secret").requires("test.secret", 4).then(
+				argument(
+					"value",
+					Argument::Integer {
+						min: None,
+						max: None,
+					},
+				)
+				.executes(ok),
+			),
+		);
+		let mut reader = Reader::new("secret 1");
+		let executor = dispatcher.parse(
+			&dispatcher.root,
+			&mut reader,
+			&mut Vec::new(),
+			&mut None,
+			&|node| node.permission.is_none(),
+			&ItemRegistry::new(),
+		);
+		assert!(executor.is_none());
+		assert_eq!(
+			parse(&dispatcher, "secret 1").unwrap(),
+			[(String::from("value"), Value::Integer(1))]
+		);
+	}
+}
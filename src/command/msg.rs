@@ -0,0 +1,48 @@
+use crate::{modern::types::chat::ChatComponent, player::PlayerMessage, types::MessagePosition};
+
+use super::{
+	argument, argument::Argument, literal, CommandContext, CommandError, CommandNode, Dispatcher,
+};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(command("msg"));
+	dispatcher.register(command("tell"));
+	dispatcher.register(command("w"));
+}
+
+fn command(name: &str) -> CommandNode {
+	literal(name).requires("basalt.command.msg", 0).then(
+		argument(
+			"targets",
+			Argument::Entity {
+				single: false,
+				players: true,
+			},
+		)
+		.then(argument("message", Argument::Message).executes(msg)),
+	)
+}
+
+/// Sends a private message, which both the sender and the players it is sent to see in gray italics
+fn msg(context: &CommandContext) -> Result<i32, CommandError> {
+	let message = context.message("message");
+	let players = context.players("targets")?;
+	let sender = context.sender.player().map(|player| player.uuid());
+	for player in &players {
+		context.reply(
+			ChatComponent::text(format!("You whisper to {}: ", player.name()))
+				.append(message.clone())
+				.color("gray")
+				.italic(),
+		);
+		player.send(PlayerMessage::Message {
+			message: ChatComponent::text(format!("{} whispers to you: ", context.sender.name()))
+				.append(message.clone())
+				.color("gray")
+				.italic(),
+			position: MessagePosition::System,
+			sender,
+		});
+	}
+	Ok(players.len() as i32)
+}
@@ -0,0 +1,66 @@
+use ::uuid::Uuid;
+
+use crate::{lists, modern::types::chat::ChatComponent, player::PlayerMessage};
+
+use super::{argument, argument::Argument, literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("op")
+			.requires("basalt.command.op", 3)
+			.then(argument("targets", Argument::GameProfile).executes(op)),
+	);
+	dispatcher.register(
+		literal("deop")
+			.requires("basalt.command.deop", 3)
+			.then(argument("targets", Argument::GameProfile).executes(deop)),
+	);
+}
+
+/// Lets a player who is online know their permissions changed, so their client shows the commands
+/// they can use now
+fn update_permissions(context: &CommandContext, uuid: Uuid) {
+	if let Some(player) = context.state.players.read().unwrap().get(&uuid) {
+		player.send(PlayerMessage::UpdatePermissions);
+	}
+}
+
+fn op(context: &CommandContext) -> Result<i32, CommandError> {
+	let mut changed = 0;
+	for profile in context.profiles("targets")? {
+		if lists::op(context.state, &profile)? {
+			changed += 1;
+			update_permissions(context, profile.uuid);
+			context.success(ChatComponent::text(format!(
+				"Made {} a server operator",
+				profile.name
+			)));
+		}
+	}
+	if changed == 0 {
+		return Err(CommandError::new(
+			"Nothing changed. The player already is an operator",
+		));
+	}
+	Ok(changed)
+}
+
+fn deop(context: &CommandContext) -> Result<i32, CommandError> {
+	let mut changed = 0;
+	for profile in context.profiles("targets")? {
+		if lists::deop(context.state, &profile)? {
+			changed += 1;
+			update_permissions(context, profile.uuid);
+			context.success(ChatComponent::text(format!(
+				"Made {} no longer a server operator",
+				profile.name
+			)));
+		}
+	}
+	if changed == 0 {
+		return Err(CommandError::new(
+			"Nothing changed. The player is not an operator",
+		));
+	}
+	Ok(changed)
+}
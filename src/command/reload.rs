@@ -0,0 +1,20 @@
+use crate::modern::types::chat::ChatComponent;
+
+use super::{literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("reload")
+			.requires("basalt.command.reload", 2)
+			.executes(reload),
+	);
+}
+
+fn reload(context: &CommandContext) -> Result<i32, CommandError> {
+	context
+		.state
+		.reload_config()
+		.map_err(|e| CommandError::new(format!("Couldn't reload the config: {}", e)))?;
+	context.success(ChatComponent::text("Reloaded the config"));
+	Ok(1)
+}
@@ -0,0 +1,33 @@
+use crate::{modern::types::chat::ChatComponent, player::PlayerMessage};
+
+use super::{literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("save-all")
+			.requires("basalt.command.save-all", 4)
+			.executes(save_all)
+			.then(literal("flush").executes(save_all)),
+	);
+}
+
+/// Saves the chunks that changed in every world and asks each player to save their data, which they
+/// do as soon as their own task gets the message. Everything is written straight to disk, so `flush`
+/// makes no difference.
+fn save_all(context: &CommandContext) -> Result<i32, CommandError> {
+	context.success(ChatComponent::text(
+		"Saving the game (this may take a moment!)",
+	));
+	for player in context.state.players.read().unwrap().values() {
+		player.send(PlayerMessage::Save);
+	}
+	let failed = context.state.save_worlds();
+	if !failed.is_empty() {
+		return Err(CommandError::new(format!(
+			"Couldn't save {}, see the server log for why",
+			failed.join(", ")
+		)));
+	}
+	context.success(ChatComponent::text("Saved the game"));
+	Ok(1)
+}
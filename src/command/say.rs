@@ -0,0 +1,28 @@
+use ::log::info;
+
+use crate::{modern::types::chat::ChatComponent, player::PlayerMessage, types::MessagePosition};
+
+use super::{argument, argument::Argument, literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("say")
+			.requires("basalt.command.say", 2)
+			.then(argument("message", Argument::Message).executes(say)),
+	);
+}
+
+fn say(context: &CommandContext) -> Result<i32, CommandError> {
+	let message = ChatComponent::text(format!("[{}] ", context.sender.name()))
+		.append(context.message("message"));
+	info!("{}", message.to_plain());
+	let sender = context.sender.player().map(|player| player.uuid());
+	for player in context.state.players.read().unwrap().values() {
+		player.send(PlayerMessage::Message {
+			message: message.clone(),
+			position: MessagePosition::Chat,
+			sender,
+		});
+	}
+	Ok(1)
+}
@@ -0,0 +1,30 @@
+use crate::modern::types::chat::{ChatComponent, ClickEvent};
+
+use super::{literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("seed")
+			.requires("basalt.command.seed", 2)
+			.executes(seed),
+	);
+}
+
+/// Shows the seed of the sender's world, which can be clicked to copy it
+fn seed(context: &CommandContext) -> Result<i32, CommandError> {
+	let (world, _, _) = context.sender.location(context.state);
+	let seed = context
+		.state
+		.world(&world)
+		.map_or(0, |world| world.lock().unwrap().seed);
+	context.reply(
+		ChatComponent::text("Seed: [")
+			.append(ChatComponent::text(seed.to_string()).color("green").click(
+				ClickEvent::CopyToClipboard {
+					value: seed.to_string(),
+				},
+			))
+			.append(ChatComponent::text("]")),
+	);
+	Ok(seed as i32)
+}
@@ -0,0 +1,803 @@
+use ::openssl::rand::rand_bytes;
+use ::std::{cmp::Ordering, collections::HashMap};
+
+use crate::{
+	entity::{Entity, EntityKind},
+	modern::types::chat::ChatComponent,
+	player::{Gamemode, PlayerHandle, Position, Session},
+	state::ServerState,
+};
+
+use super::{argument::Reader, Sender};
+
+/// Who a selector starts out choosing from, before its options narrow it down
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SelectorKind {
+	/// `@p`, the nearest player
+	Nearest,
+	/// `@a`, every player
+	All,
+	/// `@e`, every entity
+	Entities,
+	/// `@r`, a random player
+	Random,
+	/// `@s`, whoever is running the command
+	Sender,
+}
+
+/// The order a selector picks entities in
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Sort {
+	Nearest,
+	Furthest,
+	Random,
+	Arbitrary,
+}
+
+/// A range of values such as `..5`, `1..` or `2..4`, where either end can be left open
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Bounds<T> {
+	pub min: Option<T>,
+	pub max: Option<T>,
+}
+
+impl<T: Copy + PartialOrd> Bounds<T> {
+	pub fn contains(&self, value: T) -> bool {
+		self.min.map_or(true, |min| value >= min) && self.max.map_or(true, |max| value <= max)
+	}
+
+	fn is_empty(&self) -> bool {
+		self.min.is_none() && self.max.is_none()
+	}
+}
+
+/// An entity a selector or name picked out, along with the world it is in
+#[derive(Clone, Debug)]
+pub struct Target {
+	pub world: String,
+	pub entity: Entity,
+}
+
+impl Target {
+	/// The player this is, if it is one who is online
+	pub fn player(&self, state: &ServerState) -> Option<PlayerHandle> {
+		state
+			.players
+			.read()
+			.unwrap()
+			.get(&self.entity.uuid)
+			.filter(|player| player.entity_id == self.entity.id)
+			.cloned()
+	}
+
+	/// What the entity is called in chat, which is the name of a player or the type of anything else
+	pub fn name(&self) -> ChatComponent {
+		match &self.entity.profile {
+			Some(profile) => ChatComponent::text(profile.name.clone()),
+			None => ChatComponent::translate(
+				format!(
+					"entity.minecraft.{}",
+					self.entity.kind.name().trim_start_matches("minecraft:")
+				),
+				Vec::new(),
+			),
+		}
+	}
+}
+
+/// A selector such as `@a[distance=..10,gamemode=survival]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selector {
+	pub kind: SelectorKind,
+	/// Where the selector measures from instead of the sender's position
+	position: [Option<f64>; 3],
+	distance: Bounds<f64>,
+	/// Size of the box, starting at the position, entities have to be in
+	volume: [Option<f64>; 3],
+	x_rotation: Bounds<f64>,
+	y_rotation: Bounds<f64>,
+	level: Bounds<i32>,
+	/// Conditions given as a value and whether the entity should match it, which is false when the
+	/// value is negated with `!`
+	types: Vec<(String, bool)>,
+	names: Vec<(String, bool)>,
+	tags: Vec<(String, bool)>,
+	teams: Vec<(String, bool)>,
+	gamemodes: Vec<(Gamemode, bool)>,
+	scores: Vec<(String, Bounds<i32>)>,
+	limit: Option<usize>,
+	sort: Option<Sort>,
+}
+
+impl Selector {
+	fn new(kind: SelectorKind) -> Self {
+		Self {
+			kind,
+			position: [None; 3],
+			distance: Bounds::default(),
+			volume: [None; 3],
+			x_rotation: Bounds::default(),
+			y_rotation: Bounds::default(),
+			level: Bounds::default(),
+			types: Vec::new(),
+			names: Vec::new(),
+			tags: Vec::new(),
+			teams: Vec::new(),
+			gamemodes: Vec::new(),
+			scores: Vec::new(),
+			limit: None,
+			sort: None,
+		}
+	}
+
+	/// Reads a selector, starting at its `@`
+	pub fn parse(reader: &mut Reader) -> Result<Self, String> {
+		reader.cursor += 1;
+		let kind = match reader.peek() {
+			Some('p') => SelectorKind::Nearest,
+			Some('a') => SelectorKind::All,
+			Some('e') => SelectorKind::Entities,
+			Some('r') => SelectorKind::Random,
+			Some('s') => SelectorKind::Sender,
+			Some(c) => return Err(format!("Unknown selector type '@{}'", c)),
+			None => return Err(String::from("Missing selector type")),
+		};
+		reader.cursor += 1;
+		let mut selector = Self::new(kind);
+		if reader.peek() == Some('[') {
+			reader.cursor += 1;
+			selector.parse_options(reader)?;
+		}
+		Ok(selector)
+	}
+
+	/// Parses a whole string as a selector, as found in chat components
+	pub fn parse_str(text: &str) -> Result<Self, String> {
+		let mut reader = Reader::new(text);
+		if reader.peek() != Some('@') {
+			return Err(String::from("Missing selector type"));
+		}
+		let selector = Self::parse(&mut reader)?;
+		if !reader.at_end() {
+			return Err(String::from("Expected end of options"));
+		}
+		Ok(selector)
+	}
+
+	/// Reads the options between the brackets, leaving the reader after the closing one
+	fn parse_options(&mut self, reader: &mut Reader) -> Result<(), String> {
+		let mut seen: Vec<String> = Vec::new();
+		loop {
+			skip_whitespace(reader);
+			if reader.peek() == Some(']') {
+				reader.cursor += 1;
+				return Ok(());
+			}
+			let option = read_unquoted(reader).to_owned();
+			skip_whitespace(reader);
+			if reader.peek() != Some('=') {
+				return Err(format!("Expected value for option '{}'", option));
+			}
+			reader.cursor += 1;
+			skip_whitespace(reader);
+			let negated = reader.peek() == Some('!');
+			// Only some options can be given more than once, and then only when they are negated
+			let repeatable = option == "tag"
+				|| (negated && matches!(option.as_str(), "type" | "name" | "gamemode" | "team"));
+			if !repeatable && seen.contains(&option) {
+				return Err(format!("Option '{}' isn't applicable here", option));
+			}
+			seen.push(option.clone());
+			match option.as_str() {
+				"x" => self.position[0] = Some(read_double(reader)?),
+				"y" => self.position[1] = Some(read_double(reader)?),
+				"z" => self.position[2] = Some(read_double(reader)?),
+				"dx" => self.volume[0] = Some(read_double(reader)?),
+				"dy" => self.volume[1] = Some(read_double(reader)?),
+				"dz" => self.volume[2] = Some(read_double(reader)?),
+				"distance" => {
+					self.distance = read_bounds(reader)?;
+					if self.distance.min.map_or(false, |min| min < 0.0)
+						|| self.distance.max.map_or(false, |max| max < 0.0)
+					{
+						return Err(String::from("Distance cannot be negative"));
+					}
+				}
+				"x_rotation" => self.x_rotation = read_bounds(reader)?,
+				"y_rotation" => self.y_rotation = read_bounds(reader)?,
+				"level" => {
+					self.level = read_bounds(reader)?;
+					if self.level.min.map_or(false, |min| min < 0) {
+						return Err(String::from("Level shouldn't be negative"));
+					}
+				}
+				"limit" => {
+					let limit = read_double(reader)?;
+					if limit.fract() != 0.0 {
+						return Err(format!("Invalid integer '{}'", limit));
+					}
+					if limit < 1.0 {
+						return Err(String::from("Limit must be at least 1"));
+					}
+					self.limit = Some(limit as usize);
+				}
+				"sort" => {
+					let sort = read_unquoted(reader);
+					self.sort = Some(match sort {
+						"nearest" => Sort::Nearest,
+						"furthest" => Sort::Furthest,
+						"random" => Sort::Random,
+						"arbitrary" => Sort::Arbitrary,
+						_ => return Err(format!("Invalid or unknown sort type '{}'", sort)),
+					});
+				}
+				"type" => {
+					reader.cursor += negated as usize;
+					let name = read_unquoted(reader);
+					if name.is_empty() {
+						return Err(format!("Invalid or unknown entity type '{}'", name));
+					}
+					let name = if name.contains(':') {
+						name.to_owned()
+					} else {
+						format!("minecraft:{}", name)
+					};
+					self.types.push((name, !negated));
+				}
+				"name" => {
+					reader.cursor += negated as usize;
+					let name = read_string(reader)?;
+					self.names.push((name, !negated));
+				}
+				"tag" => {
+					reader.cursor += negated as usize;
+					let tag = read_unquoted(reader).to_owned();
+					self.tags.push((tag, !negated));
+				}
+				"team" => {
+					reader.cursor += negated as usize;
+					let team = read_unquoted(reader).to_owned();
+					self.teams.push((team, !negated));
+				}
+				"gamemode" => {
+					reader.cursor += negated as usize;
+					let name = read_unquoted(reader);
+					let gamemode = Gamemode::ALL
+						.iter()
+						.find(|gamemode| gamemode.name() == name)
+						.ok_or_else(|| format!("Invalid or unknown game mode '{}'", name))?;
+					self.gamemodes.push((*gamemode, !negated));
+				}
+				"scores" => self.scores = read_scores(reader)?,
+				_ => return Err(format!("Unknown option '{}'", option)),
+			}
+			skip_whitespace(reader);
+			match reader.peek() {
+				Some(',') => reader.cursor += 1,
+				Some(']') => {}
+				_ => return Err(String::from("Expected end of options")),
+			}
+		}
+	}
+
+	/// Whether the selector can only ever pick out one entity
+	pub fn is_single(&self) -> bool {
+		match self.kind {
+			SelectorKind::Nearest | SelectorKind::Random | SelectorKind::Sender => {
+				self.limit.map_or(true, |limit| limit <= 1)
+			}
+			SelectorKind::All | SelectorKind::Entities => self.limit == Some(1),
+		}
+	}
+
+	/// Whether the selector can only ever pick out players
+	pub fn is_players(&self) -> bool {
+		self.kind != SelectorKind::Entities
+			|| self
+				.types
+				.iter()
+				.any(|(name, wanted)| *wanted && name == EntityKind::Player.name())
+	}
+
+	/// Whether the selector only looks in the world the sender is in, which it does when it is given
+	/// somewhere to measure from
+	fn is_local(&self) -> bool {
+		self.position.iter().any(Option::is_some)
+			|| self.volume.iter().any(Option::is_some)
+			|| !self.distance.is_empty()
+	}
+
+	/// Finds every entity the selector picks out for a sender
+	pub fn select(&self, sender: &Sender, state: &ServerState) -> Vec<Target> {
+		let (world, mut origin, _) = sender.location(state);
+		for (axis, value) in self.position.iter().enumerate() {
+			if let Some(value) = value {
+				match axis {
+					0 => origin.x = *value,
+					1 => origin.y = *value,
+					_ => origin.z = *value,
+				}
+			}
+		}
+		// Sessions are copied before any world is locked, as players come before worlds
+		let sessions: HashMap<_, _> = state
+			.players
+			.read()
+			.unwrap()
+			.values()
+			.map(|player| (player.entity_id, player.session()))
+			.collect();
+		let mut worlds: Vec<&String> = if self.is_local() {
+			state.worlds.keys().filter(|name| **name == world).collect()
+		} else {
+			state.worlds.keys().collect()
+		};
+		worlds.sort();
+		let mut targets = Vec::new();
+		for name in worlds {
+			let store = state.worlds[name].lock().unwrap();
+			for entity in store.entities.values() {
+				if self.matches(entity, sessions.get(&entity.id), sender, origin) {
+					targets.push(Target {
+						world: name.clone(),
+						entity: entity.clone(),
+					});
+				}
+			}
+		}
+		let distance = |target: &Target| squared_distance(target.entity.position, origin);
+		let sort = self.sort.unwrap_or(match self.kind {
+			SelectorKind::Nearest => Sort::Nearest,
+			SelectorKind::Random => Sort::Random,
+			_ => Sort::Arbitrary,
+		});
+		match sort {
+			Sort::Nearest => targets.sort_by(|a, b| {
+				distance(a)
+					.partial_cmp(&distance(b))
+					.unwrap_or(Ordering::Equal)
+			}),
+			Sort::Furthest => targets.sort_by(|a, b| {
+				distance(b)
+					.partial_cmp(&distance(a))
+					.unwrap_or(Ordering::Equal)
+			}),
+			Sort::Random => shuffle(&mut targets),
+			Sort::Arbitrary => targets.sort_by_key(|target| target.entity.id),
+		}
+		let limit = self.limit.unwrap_or(match self.kind {
+			SelectorKind::Nearest | SelectorKind::Random => 1,
+			_ => usize::MAX,
+		});
+		targets.truncate(limit);
+		targets
+	}
+
+	/// Whether an entity passes every option of the selector
+	fn matches(
+		&self,
+		entity: &Entity,
+		session: Option<&Session>,
+		sender: &Sender,
+		origin: Position,
+	) -> bool {
+		let kind = entity.kind.name();
+		match self.kind {
+			SelectorKind::Sender => {
+				if sender.player().map(|player| player.entity_id) != Some(entity.id) {
+					return false;
+				}
+			}
+			SelectorKind::Entities => {}
+			_ => {
+				if entity.kind != EntityKind::Player {
+					return false;
+				}
+			}
+		}
+		if !self
+			.types
+			.iter()
+			.all(|(name, wanted)| (name == kind) == *wanted)
+		{
+			return false;
+		}
+		let name = entity.profile.as_ref().map(|profile| profile.name.as_str());
+		if !self
+			.names
+			.iter()
+			.all(|(expected, wanted)| (name == Some(expected.as_str())) == *wanted)
+		{
+			return false;
+		}
+		if !self.tags.iter().all(|(tag, wanted)| {
+			// An empty tag matches entities without any tags
+			let has = if tag.is_empty() {
+				entity.tags.is_empty()
+			} else {
+				entity.tags.contains(tag)
+			};
+			has == *wanted
+		}) {
+			return false;
+		}
+		// There are no teams, so entities are only on one if the selector asks for them not to be
+		if !self
+			.teams
+			.iter()
+			.all(|(team, wanted)| team.is_empty() == *wanted)
+		{
+			return false;
+		}
+		// There is no scoreboard either, so no entity has a score to match
+		if !self.scores.is_empty() {
+			return false;
+		}
+		if !self.gamemodes.is_empty() || !self.level.is_empty() {
+			let session = match session {
+				Some(session) => session,
+				None => return false,
+			};
+			if !self
+				.gamemodes
+				.iter()
+				.all(|(gamemode, wanted)| (session.gamemode == *gamemode) == *wanted)
+			{
+				return false;
+			}
+			if !self.level.contains(session.experience.level) {
+				return false;
+			}
+		}
+		if !self.x_rotation.contains(entity.rotation.pitch as f64)
+			|| !self
+				.y_rotation
+				.contains(wrap_degrees(entity.rotation.yaw as f64))
+		{
+			return false;
+		}
+		if !self.distance.is_empty() {
+			let distance = squared_distance(entity.position, origin).sqrt();
+			if !self.distance.contains(distance) {
+				return false;
+			}
+		}
+		if self.volume.iter().any(Option::is_some) {
+			let origin = [origin.x, origin.y, origin.z];
+			let position = [entity.position.x, entity.position.y, entity.position.z];
+			for axis in 0..3 {
+				let size = self.volume[axis].unwrap_or(0.0);
+				let start = origin[axis].min(origin[axis] + size).floor();
+				let end = origin[axis].max(origin[axis] + size).floor() + 1.0;
+				if position[axis] < start || position[axis] >= end {
+					return false;
+				}
+			}
+		}
+		true
+	}
+}
+
+/// Shows the selectors in a chat component as the names of what they pick out for a sender, leaving
+/// any that aren't valid as they were written
+pub fn resolve_component(
+	component: ChatComponent,
+	sender: &Sender,
+	state: &ServerState,
+) -> ChatComponent {
+	component.resolve_selectors(&|text| match Selector::parse_str(text) {
+		Ok(selector) => names(&selector.select(sender, state)),
+		Err(_) => ChatComponent::text(text),
+	})
+}
+
+/// Builds a chat message from text, showing any selectors in it as the names of what they pick out
+pub fn message(text: &str, sender: &Sender, state: &ServerState) -> ChatComponent {
+	let mut message = ChatComponent::text("");
+	let mut reader = Reader::new(text);
+	// Start of the text that hasn't been added to the message yet
+	let mut plain = 0;
+	while let Some(offset) = reader.remaining().find('@') {
+		let start = reader.cursor + offset;
+		reader.cursor = start;
+		match Selector::parse(&mut reader) {
+			Ok(selector) => {
+				message = message
+					.append(ChatComponent::text(&text[plain..start]))
+					.append(names(&selector.select(sender, state)));
+				plain = reader.cursor;
+			}
+			Err(_) => reader.cursor = start + 1,
+		}
+	}
+	message.append(ChatComponent::text(&text[plain..]))
+}
+
+/// Lists the names of entities separated by commas, as vanilla does in chat
+pub fn names(targets: &[Target]) -> ChatComponent {
+	let mut component = ChatComponent::text("");
+	for (i, target) in targets.iter().enumerate() {
+		if i > 0 {
+			component = component.append(ChatComponent::text(", "));
+		}
+		component = component.append(target.name());
+	}
+	component
+}
+
+fn squared_distance(a: Position, b: Position) -> f64 {
+	(a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)
+}
+
+/// Brings an angle into the range of -180 to 180 degrees
+fn wrap_degrees(angle: f64) -> f64 {
+	let angle = angle % 360.0;
+	if angle >= 180.0 {
+		angle - 360.0
+	} else if angle < -180.0 {
+		angle + 360.0
+	} else {
+		angle
+	}
+}
+
+fn shuffle<T>(items: &mut [T]) {
+	for i in (1..items.len()).rev() {
+		let mut bytes = [0u8; 4];
+		if rand_bytes(&mut bytes).is_err() {
+			return;
+		}
+		let j = u32::from_le_bytes(bytes) as usize % (i + 1);
+		items.swap(i, j);
+	}
+}
+
+fn skip_whitespace(reader: &mut Reader) {
+	while reader.peek() == Some(' ') {
+		reader.cursor += 1;
+	}
+}
+
+/// Reads the characters that can be used in a string without quoting it
+fn read_unquoted<'a>(reader: &mut Reader<'a>) -> &'a str {
+	let remaining = reader.remaining();
+	let length = remaining
+		.find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+' | ':')))
+		.unwrap_or_else(|| remaining.len());
+	reader.cursor += length;
+	&remaining[..length]
+}
+
+/// Reads a string that may be in double quotes
+fn read_string(reader: &mut Reader) -> Result<String, String> {
+	if reader.peek() != Some('"') {
+		return Ok(read_unquoted(reader).to_owned());
+	}
+	let mut result = String::new();
+	let mut escaped = false;
+	for (offset, c) in reader.remaining().char_indices().skip(1) {
+		match c {
+			_ if escaped => {
+				result.push(c);
+				escaped = false;
+			}
+			'\\' => escaped = true,
+			'"' => {
+				reader.cursor += offset + 1;
+				return Ok(result);
+			}
+			_ => result.push(c),
+		}
+	}
+	Err(String::from("Unclosed quoted string"))
+}
+
+fn read_number<'a>(reader: &mut Reader<'a>) -> &'a str {
+	let remaining = reader.remaining();
+	let length = remaining
+		.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+		.unwrap_or_else(|| remaining.len());
+	reader.cursor += length;
+	&remaining[..length]
+}
+
+fn read_double(reader: &mut Reader) -> Result<f64, String> {
+	let number = read_number(reader);
+	number
+		.parse()
+		.map_err(|_| format!("Invalid double '{}'", number))
+}
+
+/// Reads a single value or a range of them
+fn read_bounds<T: Copy + PartialOrd + ::std::str::FromStr>(
+	reader: &mut Reader,
+) -> Result<Bounds<T>, String> {
+	let text = read_number(reader);
+	let bound = |text: &str| -> Result<Option<T>, String> {
+		if text.is_empty() {
+			Ok(None)
+		} else {
+			text.parse()
+				.map(Some)
+				.map_err(|_| format!("Invalid number '{}'", text))
+		}
+	};
+	let bounds = match text.find("..") {
+		Some(split) => Bounds {
+			min: bound(&text[..split])?,
+			max: bound(&text[split + 2..])?,
+		},
+		None => {
+			let value = bound(text)?;
+			Bounds {
+				min: value,
+				max: value,
+			}
+		}
+	};
+	if bounds.is_empty() {
+		return Err(String::from("Expected value or range of values"));
+	}
+	if let (Some(min), Some(max)) = (bounds.min, bounds.max) {
+		if min > max {
+			return Err(String::from("Min cannot be bigger than max"));
+		}
+	}
+	Ok(bounds)
+}
+
+/// Reads the objectives and ranges of the `scores` option, such as `{kills=1..,deaths=0}`
+fn read_scores(reader: &mut Reader) -> Result<Vec<(String, Bounds<i32>)>, String> {
+	if reader.peek() != Some('{') {
+		return Err(String::from("Expected '{'"));
+	}
+	reader.cursor += 1;
+	let mut scores = Vec::new();
+	loop {
+		skip_whitespace(reader);
+		if reader.peek() == Some('}') {
+			reader.cursor += 1;
+			return Ok(scores);
+		}
+		let objective = read_unquoted(reader).to_owned();
+		skip_whitespace(reader);
+		if reader.peek() != Some('=') {
+			return Err(String::from("Expected '='"));
+		}
+		reader.cursor += 1;
+		skip_whitespace(reader);
+		scores.push((objective, read_bounds(reader)?));
+		skip_whitespace(reader);
+		match reader.peek() {
+			Some(',') => reader.cursor += 1,
+			Some('}') => {}
+			_ => return Err(String::from("Expected '}'")),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse(text: &str) -> Result<Selector, String> {
+		Selector::parse_str(text)
+	}
+
+	fn bounds<T>(min: Option<T>, max: Option<T>) -> Bounds<T> {
+		Bounds { min, max }
+	}
+
+	#[test]
+	fn parses_kinds() {
+		assert_eq!(parse("@p").unwrap().kind, SelectorKind::Nearest);
+		assert_eq!(parse("@a").unwrap().kind, SelectorKind::All);
+		assert_eq!(parse("@e").unwrap().kind, SelectorKind::Entities);
+		assert_eq!(parse("@r").unwrap().kind, SelectorKind::Random);
+		assert_eq!(parse("@s").unwrap().kind, SelectorKind::Sender);
+		assert_eq!(parse("@a[]").unwrap(), Selector::new(SelectorKind::All));
+		assert!(parse("@x").is_err());
+		assert!(parse("@").is_err());
+		assert!(parse("a").is_err());
+	}
+
+	#[test]
+	fn parses_options() {
+		let selector = parse(
+			"@e[x=1,y=-2.5,z=3, dx=4 ,distance=..10,type=!minecraft:pig,type=!cow,name=\"A \\\"B\\\"\",\
+			 tag=red,tag=!,team=,gamemode=!creative,level=3..,limit=2,sort=furthest]",
+		)
+		.unwrap();
+		let mut expected = Selector::new(SelectorKind::Entities);
+		expected.position = [Some(1.0), Some(-2.5), Some(3.0)];
+		expected.volume = [Some(4.0), None, None];
+		expected.distance = bounds(None, Some(10.0));
+		expected.types = vec![
+			(String::from("minecraft:pig"), false),
+			(String::from("minecraft:cow"), false),
+		];
+		expected.names = vec![(String::from("A \"B\""), true)];
+		expected.tags = vec![(String::from("red"), true), (String::new(), false)];
+		expected.teams = vec![(String::new(), true)];
+		expected.gamemodes = vec![(Gamemode::Creative, false)];
+		expected.level = bounds(Some(3), None);
+		expected.limit = Some(2);
+		expected.sort = Some(Sort::Furthest);
+		assert_eq!(selector, expected);
+	}
+
+	#[test]
+	fn parses_ranges() {
+		let range = |text: &str| read_bounds::<f64>(&mut Reader::new(text));
+		assert_eq!(range("5"), Ok(bounds(Some(5.0), Some(5.0))));
+		assert_eq!(range("..5"), Ok(bounds(None, Some(5.0))));
+		assert_eq!(range("1.."), Ok(bounds(Some(1.0), None)));
+		assert_eq!(range("-1.5..2.5"), Ok(bounds(Some(-1.5), Some(2.5))));
+		assert!(range("..").is_err());
+		assert!(range("").is_err());
+		assert!(range("4..2").is_err());
+		assert!(range("1...2").is_err());
+		assert!(read_bounds::<i32>(&mut Reader::new("1.5")).is_err());
+		assert!(bounds(Some(1.0), Some(2.0)).contains(2.0));
+		assert!(!bounds(Some(1.0), None).contains(0.5));
+		assert_eq!(
+			parse("@a[scores={kills=1.., deaths=0}]").unwrap().scores,
+			vec![
+				(String::from("kills"), bounds(Some(1), None)),
+				(String::from("deaths"), bounds(Some(0), Some(0))),
+			]
+		);
+	}
+
+	#[test]
+	fn rejects_malformed_options() {
+		for text in &[
+			"@a[",
+			"@a[limit=1",
+			"@a[limit]",
+			"@a[limit=]",
+			"@a[limit=0]",
+			"@a[limit=1.5]",
+			"@a[limit=1,limit=2]",
+			"@a[limit=1;sort=random]",
+			"@a[sort=closest]",
+			"@a[colour=red]",
+			"@a[distance=-1..]",
+			"@a[level=-2..]",
+			"@a[type=]",
+			"@a[type=pig,type=cow]",
+			"@a[gamemode=creative,gamemode=survival]",
+			"@a[gamemode=hardcore]",
+			"@a[name=\"unclosed]",
+			"@a[scores=1]",
+			"@a[scores={kills=1]",
+			"@a]",
+			"@a[]x",
+		] {
+			assert!(parse(text).is_err(), "{} should be rejected", text);
+		}
+	}
+
+	#[test]
+	fn leaves_reader_after_selector() {
+		let mut reader = Reader::new("@a[tag=x] rest");
+		Selector::parse(&mut reader).unwrap();
+		assert_eq!(reader.remaining(), " rest");
+		let mut reader = Reader::new("@p rest");
+		Selector::parse(&mut reader).unwrap();
+		assert_eq!(reader.remaining(), " rest");
+	}
+
+	#[test]
+	fn counts_what_can_be_selected() {
+		assert!(parse("@p").unwrap().is_single());
+		assert!(parse("@s").unwrap().is_single());
+		assert!(!parse("@r[limit=2]").unwrap().is_single());
+		assert!(!parse("@a").unwrap().is_single());
+		assert!(parse("@e[limit=1]").unwrap().is_single());
+		assert!(parse("@a").unwrap().is_players());
+		assert!(!parse("@e").unwrap().is_players());
+		assert!(parse("@e[type=player]").unwrap().is_players());
+		assert!(!parse("@e[type=!player]").unwrap().is_players());
+	}
+}
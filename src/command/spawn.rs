@@ -0,0 +1,76 @@
+use crate::{modern::types::chat::ChatComponent, player::PlayerMessage, world::BlockPos};
+
+use super::{argument, argument::Argument, literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("setworldspawn")
+			.requires("basalt.command.setworldspawn", 2)
+			.executes(set_world_spawn)
+			.then(argument("pos", Argument::BlockPos).executes(set_world_spawn)),
+	);
+	dispatcher.register(
+		literal("spawnpoint")
+			.requires("basalt.command.spawnpoint", 2)
+			.executes(spawn_point)
+			.then(
+				argument(
+					"targets",
+					Argument::Entity {
+						single: false,
+						players: true,
+					},
+				)
+				.executes(spawn_point)
+				.then(argument("pos", Argument::BlockPos).executes(spawn_point)),
+			),
+	);
+}
+
+/// The block given by the `pos` argument, or the one the sender is in, along with the sender's world
+fn block(context: &CommandContext) -> (String, BlockPos) {
+	let (world, position, rotation) = context.sender.location(context.state);
+	let block = match context.coordinates("pos") {
+		Some(coordinates) => coordinates.block(position, rotation),
+		None => BlockPos::new(
+			position.x.floor() as i32,
+			position.y.floor() as i32,
+			position.z.floor() as i32,
+		),
+	};
+	(world, block)
+}
+
+/// Moves the spawn of the sender's world until the server restarts, as worlds aren't saved yet
+fn set_world_spawn(context: &CommandContext) -> Result<i32, CommandError> {
+	let (world, block) = block(context);
+	if let Some(world) = context.state.world(&world) {
+		world.lock().unwrap().spawn = (block.x, block.y, block.z);
+	}
+	context.success(ChatComponent::text(format!(
+		"Set the world spawn point to {}, {}, {}",
+		block.x, block.y, block.z
+	)));
+	Ok(1)
+}
+
+fn spawn_point(context: &CommandContext) -> Result<i32, CommandError> {
+	let players = if context.get("targets").is_some() {
+		context.players("targets")?
+	} else {
+		vec![context.player()?]
+	};
+	let (world, block) = block(context);
+	for player in &players {
+		player.send(PlayerMessage::SetSpawn(Some((world.clone(), block))));
+	}
+	let who = match players.as_slice() {
+		[player] => String::from(player.name()),
+		_ => format!("{} players", players.len()),
+	};
+	context.success(ChatComponent::text(format!(
+		"Set spawn point to {}, {}, {} for {}",
+		block.x, block.y, block.z, who
+	)));
+	Ok(players.len() as i32)
+}
@@ -0,0 +1,17 @@
+use crate::modern::types::chat::ChatComponent;
+
+use super::{literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("stop")
+			.requires("basalt.command.stop", 4)
+			.executes(stop),
+	);
+}
+
+fn stop(context: &CommandContext) -> Result<i32, CommandError> {
+	context.reply(ChatComponent::text("Stopping the server"));
+	context.state.stop();
+	Ok(1)
+}
@@ -0,0 +1,124 @@
+use crate::{
+	modern::types::chat::ChatComponent,
+	player::{PlayerMessage, Position, Rotation},
+	state::ServerState,
+};
+
+use super::{
+	argument, argument::Argument, literal, selector::Target, CommandContext, CommandError,
+	CommandNode, Dispatcher,
+};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(command("teleport"));
+	dispatcher.register(command("tp"));
+}
+
+fn command(name: &str) -> CommandNode {
+	let single = Argument::Entity {
+		single: true,
+		players: false,
+	};
+	let many = Argument::Entity {
+		single: false,
+		players: false,
+	};
+	literal(name)
+		.requires("basalt.command.teleport", 2)
+		.then(argument("location", Argument::Vec3).executes(to_location))
+		.then(argument("destination", single.clone()).executes(to_entity))
+		.then(
+			argument("targets", many)
+				.then(argument("location", Argument::Vec3).executes(to_location))
+				.then(argument("destination", single).executes(to_entity)),
+		)
+}
+
+/// The entities being teleported, which is the sender when no targets are given
+fn targets(context: &CommandContext) -> Result<Vec<Target>, CommandError> {
+	if context.get("targets").is_some() {
+		context.entities("targets")
+	} else {
+		Ok(vec![context.entity()?])
+	}
+}
+
+fn to_entity(context: &CommandContext) -> Result<i32, CommandError> {
+	let targets = targets(context)?;
+	let destination = context
+		.entities("destination")?
+		.pop()
+		.ok_or_else(|| CommandError::new("No entity was found"))?;
+	let entity = &destination.entity;
+	for target in &targets {
+		teleport(
+			context.state,
+			target,
+			&destination.world,
+			entity.position,
+			Some(entity.rotation),
+		);
+	}
+	let message = match targets.as_slice() {
+		[target] => ChatComponent::text("Teleported ")
+			.append(target.name())
+			.append(ChatComponent::text(" to "))
+			.append(destination.name()),
+		_ => ChatComponent::text(format!("Teleported {} entities to ", targets.len()))
+			.append(destination.name()),
+	};
+	context.success(message);
+	Ok(targets.len() as i32)
+}
+
+fn to_location(context: &CommandContext) -> Result<i32, CommandError> {
+	let targets = targets(context)?;
+	let (world, origin, rotation) = context.sender.location(context.state);
+	let position = context
+		.coordinates("location")
+		.map_or(origin, |location| location.resolve(origin, rotation));
+	for target in &targets {
+		teleport(context.state, target, &world, position, None);
+	}
+	let coordinates = format!("{:.6}, {:.6}, {:.6}", position.x, position.y, position.z);
+	let message = match targets.as_slice() {
+		[target] => ChatComponent::text("Teleported ")
+			.append(target.name())
+			.append(ChatComponent::text(format!(" to {}", coordinates))),
+		_ => ChatComponent::text(format!(
+			"Teleported {} entities to {}",
+			targets.len(),
+			coordinates
+		)),
+	};
+	context.success(message);
+	Ok(targets.len() as i32)
+}
+
+/// Moves an entity anywhere, keeping the way it is facing if no rotation is given. Players move
+/// themselves, since their clients have to be told.
+fn teleport(
+	state: &ServerState,
+	target: &Target,
+	world: &str,
+	position: Position,
+	rotation: Option<Rotation>,
+) {
+	if let Some(player) = target.player(state) {
+		player.send(PlayerMessage::Teleport {
+			world: String::from(world),
+			position,
+			rotation,
+		});
+		return;
+	}
+	let entity = match state.world(&target.world) {
+		Some(from) => from.lock().unwrap().entities.remove(target.entity.id),
+		None => None,
+	};
+	if let (Some(mut entity), Some(to)) = (entity, state.world(world)) {
+		entity.position = position;
+		entity.rotation = rotation.unwrap_or(entity.rotation);
+		to.lock().unwrap().entities.insert(entity);
+	}
+}
@@ -0,0 +1,34 @@
+use crate::{player::PlayerMessage, types::MessagePosition};
+
+use super::{argument, argument::Argument, literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("tellraw")
+			.requires("basalt.command.tellraw", 2)
+			.then(
+				argument(
+					"targets",
+					Argument::Entity {
+						single: false,
+						players: true,
+					},
+				)
+				.then(argument("message", Argument::Component).executes(tellraw)),
+			),
+	);
+}
+
+/// Sends a chat component to players exactly as it was written
+fn tellraw(context: &CommandContext) -> Result<i32, CommandError> {
+	let message = context.component("message");
+	let players = context.players("targets")?;
+	for player in &players {
+		player.send(PlayerMessage::Message {
+			message: message.clone(),
+			position: MessagePosition::System,
+			sender: None,
+		});
+	}
+	Ok(players.len() as i32)
+}
@@ -0,0 +1,91 @@
+use crate::{modern::types::chat::ChatComponent, player::PlayerMessage, world::DAY_LENGTH};
+
+use super::{
+	argument, argument::Argument, literal, CommandContext, CommandError, Dispatcher, Executor,
+};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	let mut set = literal("set").then(argument("time", Argument::Time).executes(set));
+	for (name, executor) in NAMED_TIMES {
+		set = set.then(literal(name).executes(*executor));
+	}
+	dispatcher.register(
+		literal("time")
+			.requires("basalt.command.time", 2)
+			.then(set)
+			.then(literal("add").then(argument("time", Argument::Time).executes(add)))
+			.then(
+				literal("query")
+					.then(literal("daytime").executes(query_daytime))
+					.then(literal("gametime").executes(query_gametime))
+					.then(literal("day").executes(query_day)),
+			),
+	);
+}
+
+/// Times of day that `/time set` accepts by name
+const NAMED_TIMES: &[(&str, Executor)] = &[
+	("day", |context| Ok(update(context, |_| 1000))),
+	("noon", |context| Ok(update(context, |_| 6000))),
+	("night", |context| Ok(update(context, |_| 13000))),
+	("midnight", |context| Ok(update(context, |_| 18000))),
+];
+
+fn set(context: &CommandContext) -> Result<i32, CommandError> {
+	let time = context.integer("time").unwrap_or_default() as i64;
+	Ok(update(context, |_| time))
+}
+
+fn add(context: &CommandContext) -> Result<i32, CommandError> {
+	let ticks = context.integer("time").unwrap_or_default() as i64;
+	Ok(update(context, |time| time + ticks))
+}
+
+/// Changes the time of day in every world like vanilla does, and tells everyone straight away. The
+/// result is the new time in the default world.
+fn update<F: Fn(i64) -> i64>(context: &CommandContext, change: F) -> i32 {
+	let mut result = 0;
+	let players = context.state.players.read().unwrap();
+	for world in context.state.worlds.values() {
+		let (name, age, time) = {
+			let mut world = world.lock().unwrap();
+			world.time = change(world.time).rem_euclid(DAY_LENGTH);
+			(world.name.clone(), world.age, world.time)
+		};
+		for player in players.values().filter(|p| p.session().world == name) {
+			player.send(PlayerMessage::Time { age, time });
+		}
+		if name == context.state.default_world {
+			result = time;
+		}
+	}
+	drop(players);
+	context.success(ChatComponent::text(format!("Set the time to {}", result)));
+	result as i32
+}
+
+fn query_daytime(context: &CommandContext) -> Result<i32, CommandError> {
+	query(context, |_, time| time)
+}
+
+fn query_gametime(context: &CommandContext) -> Result<i32, CommandError> {
+	query(context, |age, _| age)
+}
+
+fn query_day(context: &CommandContext) -> Result<i32, CommandError> {
+	query(context, |age, _| age / DAY_LENGTH)
+}
+
+/// Shows something about the time in the sender's world
+fn query<F: Fn(i64, i64) -> i64>(context: &CommandContext, pick: F) -> Result<i32, CommandError> {
+	let (world, _, _) = context.sender.location(context.state);
+	let value = match context.state.world(&world) {
+		Some(world) => {
+			let world = world.lock().unwrap();
+			pick(world.age, world.time)
+		}
+		None => 0,
+	};
+	context.reply(ChatComponent::text(format!("The time is {}", value)));
+	Ok(value as i32)
+}
@@ -0,0 +1,32 @@
+use crate::modern::types::chat::ChatComponent;
+
+use super::{literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("tps")
+			.requires("basalt.command.tps", 2)
+			.executes(tps),
+	);
+}
+
+/// Shows how many ticks the server has managed each second lately and how long they took
+fn tps(context: &CommandContext) -> Result<i32, CommandError> {
+	let stats = context.state.tick.stats();
+	let color = if stats.tps >= 18.0 {
+		"green"
+	} else if stats.tps >= 15.0 {
+		"yellow"
+	} else {
+		"red"
+	};
+	context.reply(
+		ChatComponent::text("TPS: ")
+			.append(ChatComponent::text(format!("{:.1}", stats.tps)).color(color))
+			.append(ChatComponent::text(format!(
+				", MSPT: {:.2}, Tick: {}",
+				stats.mspt, stats.tick
+			))),
+	);
+	Ok(stats.tps.round() as i32)
+}
@@ -0,0 +1,64 @@
+use crate::{modern::types::chat::ChatComponent, tick::TICKS_PER_SECOND, world::Weather};
+
+use super::{argument, argument::Argument, literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	let duration = || Argument::Integer {
+		min: Some(0),
+		max: Some(1_000_000),
+	};
+	dispatcher.register(
+		literal("weather")
+			.requires("basalt.command.weather", 2)
+			.then(
+				literal("clear")
+					.executes(clear)
+					.then(argument("duration", duration()).executes(clear)),
+			)
+			.then(
+				literal("rain")
+					.executes(rain)
+					.then(argument("duration", duration()).executes(rain)),
+			)
+			.then(
+				literal("thunder")
+					.executes(thunder)
+					.then(argument("duration", duration()).executes(thunder)),
+			),
+	);
+}
+
+/// How long rain and thunder last if no duration is given, in ticks
+const DEFAULT_DURATION: i32 = 6000;
+
+fn clear(context: &CommandContext) -> Result<i32, CommandError> {
+	set(context, Weather::Clear, "clear")
+}
+
+fn rain(context: &CommandContext) -> Result<i32, CommandError> {
+	set(context, Weather::Rain, "rain")
+}
+
+fn thunder(context: &CommandContext) -> Result<i32, CommandError> {
+	set(context, Weather::Thunder, "rain & thunder")
+}
+
+/// Changes the weather in the sender's world, which the ticker then sends to the players in it. Clear
+/// weather lasts until it is changed again, so its duration is ignored.
+fn set(context: &CommandContext, weather: Weather, name: &str) -> Result<i32, CommandError> {
+	let ticks = match context.integer("duration") {
+		Some(seconds) => seconds * TICKS_PER_SECOND as i32,
+		None => DEFAULT_DURATION,
+	};
+	let (world, _, _) = context.sender.location(context.state);
+	if let Some(world) = context.state.world(&world) {
+		let ticks = if weather == Weather::Clear {
+			None
+		} else {
+			Some(ticks)
+		};
+		world.lock().unwrap().set_weather(weather, ticks);
+	}
+	context.success(ChatComponent::text(format!("Set the weather to {}", name)));
+	Ok(ticks)
+}
@@ -0,0 +1,100 @@
+use ::std::sync::atomic::Ordering;
+
+use crate::{lists::whitelist, modern::types::chat::ChatComponent};
+
+use super::{argument, argument::Argument, literal, CommandContext, CommandError, Dispatcher};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+	dispatcher.register(
+		literal("whitelist")
+			.requires("basalt.command.whitelist", 3)
+			.then(literal("on").executes(on))
+			.then(literal("off").executes(off))
+			.then(literal("list").executes(list))
+			.then(literal("add").then(argument("targets", Argument::GameProfile).executes(add)))
+			.then(
+				literal("remove").then(argument("targets", Argument::GameProfile).executes(remove)),
+			)
+			.then(literal("reload").executes(reload)),
+	);
+}
+
+fn on(context: &CommandContext) -> Result<i32, CommandError> {
+	if context
+		.state
+		.whitelist_enabled
+		.swap(true, Ordering::Relaxed)
+	{
+		return Err(CommandError::new("Whitelist is already turned on"));
+	}
+	context.success(ChatComponent::text("Whitelist is now turned on"));
+	Ok(1)
+}
+
+fn off(context: &CommandContext) -> Result<i32, CommandError> {
+	if !context
+		.state
+		.whitelist_enabled
+		.swap(false, Ordering::Relaxed)
+	{
+		return Err(CommandError::new("Whitelist is already turned off"));
+	}
+	context.success(ChatComponent::text("Whitelist is now turned off"));
+	Ok(1)
+}
+
+fn list(context: &CommandContext) -> Result<i32, CommandError> {
+	let names = whitelist::names(context.state);
+	if names.is_empty() {
+		context.reply(ChatComponent::text("There are no whitelisted players"));
+	} else {
+		context.reply(ChatComponent::text(format!(
+			"There are {} whitelisted players: {}",
+			names.len(),
+			names.join(", ")
+		)));
+	}
+	Ok(names.len() as i32)
+}
+
+fn add(context: &CommandContext) -> Result<i32, CommandError> {
+	let mut added = 0;
+	for profile in context.profiles("targets")? {
+		if whitelist::add(context.state, &profile)? {
+			added += 1;
+			context.success(ChatComponent::text(format!(
+				"Added {} to the whitelist",
+				profile.name
+			)));
+		}
+	}
+	if added == 0 {
+		return Err(CommandError::new("Player is already whitelisted"));
+	}
+	Ok(added)
+}
+
+fn remove(context: &CommandContext) -> Result<i32, CommandError> {
+	let mut removed = 0;
+	for profile in context.profiles("targets")? {
+		if whitelist::remove(context.state, &profile)? {
+			removed += 1;
+			context.success(ChatComponent::text(format!(
+				"Removed {} from the whitelist",
+				profile.name
+			)));
+		}
+	}
+	if removed == 0 {
+		return Err(CommandError::new("Player is not whitelisted"));
+	}
+	Ok(removed)
+}
+
+fn reload(context: &CommandContext) -> Result<i32, CommandError> {
+	if let Err(e) = context.state.whitelist.lock().unwrap().reload() {
+		return Err(CommandError::new(e.to_string()));
+	}
+	context.success(ChatComponent::text("Reloaded the whitelist"));
+	Ok(1)
+}
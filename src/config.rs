@@ -5,7 +5,12 @@ use ::std::{
 	path::Path,
 };
 
-#[derive(Clone, Deserialize)]
+use crate::world::{dimension::Dimension, Difficulty};
+
+/// Where the config is read from, relative to the directory the server is run in
+pub const CONFIG_PATH: &str = "basalt.toml";
+
+#[derive(Clone, Deserialize, PartialEq)]
 pub struct Authentication {
 	pub legacy: AuthenticationMethod,
 	pub microsoft: AuthenticationMethod,
@@ -13,43 +18,118 @@ pub struct Authentication {
 	pub yggdrasil: AuthenticationMethod,
 }
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, PartialEq)]
 pub struct AuthenticationMethod {
 	pub enabled: bool,
 	pub url: String,
 }
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, PartialEq)]
+pub struct Chat {
+	/// How chat messages are shown, where `{name}` is replaced with the player's name and `{message}`
+	/// with what they said
+	pub format: String,
+	/// Messages and commands a player can send in a burst before they are kicked for spamming, where 0
+	/// turns the limit off
+	pub spam_limit: u32,
+	/// Milliseconds it takes for each message to stop counting towards `spam_limit`
+	pub spam_interval: u64,
+}
+
+#[derive(Clone, Deserialize, PartialEq)]
 pub struct Config {
 	pub authentication: Authentication,
+	pub chat: Chat,
 	pub minecraft: Minecraft,
 	pub network: Network,
+	pub reload: Reload,
 	pub whitelist: Whitelist,
+	pub worlds: Vec<WorldConfig>,
 }
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, PartialEq)]
 pub struct Minecraft {
+	/// Directory laid out like the `data` directory of a data pack, whose recipes and tags are added to
+	/// or replace the ones bundled with the server
+	pub data_directory: String,
+	pub difficulty: Difficulty,
 	pub max_players: i32,
 	pub motd: String,
+	/// Distance from each world's spawn within which players can't break or place blocks, where 0
+	/// turns spawn protection off
+	pub spawn_protection: u32,
+	/// What players are kicked with when the server stops
+	pub stop_message: String,
+	pub view_distance: u8,
 }
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, PartialEq)]
 pub struct Network {
 	pub bind: String,
 	pub port: u16,
+	/// Seconds a client may go without sending anything before they are disconnected, which is checked
+	/// each time a keep alive is sent
+	pub timeout: u64,
+}
+
+#[derive(Clone, Deserialize, PartialEq)]
+pub struct Reload {
+	/// Whether the config is reloaded whenever the file changes, rather than only by `/reload` or
+	/// SIGHUP
+	pub watch: bool,
 }
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, PartialEq)]
 pub struct Whitelist {
 	pub enabled: bool,
+	/// Names or UUIDs of players who can join, as well as those in `whitelist.json`
 	pub list: Vec<String>,
+	/// What players who aren't whitelisted are kicked with
+	pub message: String,
+}
+
+#[derive(Clone, Deserialize, PartialEq)]
+pub struct WorldConfig {
+	pub name: String,
+	pub dimension: Dimension,
+	pub directory: String,
+	/// Either "flat", "hills" or "void"
+	pub generator: String,
+	/// Block states for the flat generator, from the bottom of the world upwards
+	pub layers: Option<Vec<u16>>,
+	/// Seed the terrain is generated from, which is 0 if not given
+	pub seed: Option<i64>,
+	pub spawn: [i32; 3],
 }
 
 impl Config {
-	pub async fn read<P: AsRef<Path>>(path: P) -> Result<Config> {
+	pub fn read<P: AsRef<Path>>(path: P) -> Result<Config> {
 		let mut config = File::open(path)?;
 		let mut buffer = String::new();
 		config.read_to_string(&mut buffer)?;
 		Ok(::toml::from_str(&buffer)?)
 	}
+
+	/// Puts back the settings in a reloaded config that can only change when the server restarts,
+	/// returning the keys that were changed
+	pub fn keep_restart_only(&self, reloaded: &mut Config) -> Vec<&'static str> {
+		let mut kept = Vec::new();
+		if reloaded.minecraft.data_directory != self.minecraft.data_directory {
+			reloaded.minecraft.data_directory = self.minecraft.data_directory.clone();
+			kept.push("minecraft.data_directory");
+		}
+		if reloaded.network.bind != self.network.bind {
+			reloaded.network.bind = self.network.bind.clone();
+			kept.push("network.bind");
+		}
+		if reloaded.network.port != self.network.port {
+			reloaded.network.port = self.network.port;
+			kept.push("network.port");
+		}
+		if reloaded.worlds != self.worlds {
+			reloaded.worlds = self.worlds.clone();
+			kept.push("worlds");
+		}
+		kept
+	}
 }
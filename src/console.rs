@@ -0,0 +1,390 @@
+use ::log::{warn, Record};
+use ::std::{
+	io::{self, BufRead, Read, Write},
+	panic,
+	process::{Command, Stdio},
+	str,
+	sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError},
+	thread,
+};
+
+use crate::{command::Sender, state::ServerState};
+
+/// Shown before the line being typed
+const PROMPT: &str = "> ";
+/// How many commands the console remembers
+const HISTORY_SIZE: usize = 100;
+
+/// Keys the line editor understands
+enum Key {
+	Char(char),
+	Backspace,
+	Delete,
+	Down,
+	End,
+	Enter,
+	Home,
+	/// Ctrl+C, which stops the server
+	Interrupt,
+	/// Ctrl+U, which clears the line
+	Kill,
+	Left,
+	Right,
+	Tab,
+	Up,
+	Unknown,
+}
+
+/// The line being typed, which log messages are printed above
+#[derive(Default)]
+struct Line {
+	chars: Vec<char>,
+	cursor: usize,
+	/// Whether the terminal is in raw mode and the line is being shown
+	editing: bool,
+	/// Settings to put the terminal back to when the server stops
+	terminal: Option<String>,
+}
+
+impl Line {
+	fn text(&self) -> String {
+		self.chars.iter().collect()
+	}
+
+	fn set(&mut self, text: &str) {
+		self.chars = text.chars().collect();
+		self.cursor = self.chars.len();
+	}
+
+	/// Redraws the line from the start of the row the cursor is on
+	fn draw<W: Write>(&self, out: &mut W) -> io::Result<()> {
+		write!(out, "\r\x1b[K{}{}", PROMPT, self.text())?;
+		if self.cursor < self.chars.len() {
+			write!(out, "\x1b[{}D", self.chars.len() - self.cursor)?;
+		}
+		Ok(())
+	}
+}
+
+/// The server console, which reads commands from stdin while log messages keep being printed
+#[derive(Clone, Default)]
+pub struct Console {
+	line: Arc<Mutex<Line>>,
+}
+
+impl Console {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Prints a formatted log message above the line being typed
+	pub fn log(&self, record: &Record) {
+		let line = self.line.lock().unwrap();
+		let stdout = io::stdout();
+		let mut out = stdout.lock();
+		let _ = if line.editing {
+			write!(out, "\r\x1b[K{}\n", record.args()).and_then(|_| line.draw(&mut out))
+		} else {
+			writeln!(out, "{}", record.args())
+		};
+		let _ = out.flush();
+	}
+
+	/// Starts reading commands from stdin on a thread of its own, since reading it blocks
+	pub fn spawn(&self, state: Arc<ServerState>) {
+		let console = self.clone();
+		let result = thread::Builder::new()
+			.name(String::from("console"))
+			.spawn(move || console.read(&state));
+		if let Err(e) = result {
+			warn!("Couldn't start the console: {}", e);
+		}
+	}
+
+	/// Puts the terminal back the way it was before the console took it over
+	pub fn restore(&self) {
+		restore(self.line.lock().unwrap_or_else(PoisonError::into_inner));
+	}
+
+	/// Makes sure the terminal is put back if the server panics, or if `main` returns before calling
+	/// `restore` while the returned guard is held
+	pub fn restore_on_exit(&self) -> RestoreGuard {
+		let console = self.clone();
+		let hook = panic::take_hook();
+		panic::set_hook(Box::new(move |info| {
+			// The thread that panicked may be holding the line, which can't be waited for
+			match console.line.try_lock() {
+				Ok(line) => restore(line),
+				Err(TryLockError::Poisoned(e)) => restore(e.into_inner()),
+				Err(TryLockError::WouldBlock) => {}
+			}
+			hook(info);
+		}));
+		RestoreGuard(self.clone())
+	}
+
+	fn read(&self, state: &Arc<ServerState>) {
+		if !self.enable_raw_mode() {
+			// Without a terminal to edit in, such as when commands are piped in, each line is run as
+			// it comes
+			let stdin = io::stdin();
+			for line in stdin.lock().lines() {
+				match line {
+					Ok(line) => execute(state, &line),
+					Err(e) => {
+						warn!("Couldn't read from the console: {}", e);
+						break;
+					}
+				}
+			}
+			return;
+		}
+		let stdin = io::stdin();
+		let mut input = stdin.lock().bytes().filter_map(|byte| byte.ok());
+		let mut history: Vec<String> = Vec::new();
+		// Position in the history being looked at, which is its length for a new line
+		let mut browsing = 0;
+		// What was typed before looking through the history, so it isn't lost coming back down
+		let mut draft = String::new();
+		while let Some(key) = read_key(&mut input) {
+			let mut command = None;
+			{
+				let mut line = self.line.lock().unwrap();
+				let stdout = io::stdout();
+				let mut out = stdout.lock();
+				match key {
+					Key::Char(c) => {
+						let cursor = line.cursor;
+						line.chars.insert(cursor, c);
+						line.cursor += 1;
+					}
+					Key::Backspace => {
+						if line.cursor > 0 {
+							line.cursor -= 1;
+							let cursor = line.cursor;
+							line.chars.remove(cursor);
+						}
+					}
+					Key::Delete => {
+						if line.cursor < line.chars.len() {
+							let cursor = line.cursor;
+							line.chars.remove(cursor);
+						}
+					}
+					Key::Left => line.cursor = line.cursor.saturating_sub(1),
+					Key::Right => line.cursor = (line.cursor + 1).min(line.chars.len()),
+					Key::Home => line.cursor = 0,
+					Key::End => line.cursor = line.chars.len(),
+					Key::Kill => line.set(""),
+					Key::Up => {
+						if browsing > 0 {
+							if browsing == history.len() {
+								draft = line.text();
+							}
+							browsing -= 1;
+							line.set(&history[browsing]);
+						}
+					}
+					Key::Down => {
+						if browsing < history.len() {
+							browsing += 1;
+							match history.get(browsing) {
+								Some(text) => line.set(text),
+								None => line.set(&draft),
+							}
+						}
+					}
+					Key::Tab => {
+						let text = line.text();
+						if let Some(options) = complete(state, &mut line) {
+							// Every option is listed when there is more than one to choose from
+							let _ = write!(out, "\r\x1b[K{}{}\n", PROMPT, text)
+								.and_then(|_| writeln!(out, "{}", options.join("  ")));
+						}
+					}
+					Key::Enter => {
+						let text = line.text();
+						let _ = write!(out, "\r\x1b[K{}{}\n", PROMPT, text);
+						line.set("");
+						if !text.trim().is_empty() {
+							if history.last() != Some(&text) {
+								history.push(text.clone());
+								if history.len() > HISTORY_SIZE {
+									history.remove(0);
+								}
+							}
+							command = Some(text);
+						}
+						browsing = history.len();
+						draft.clear();
+					}
+					Key::Interrupt => {
+						let _ = writeln!(out, "\r\x1b[K{}^C", PROMPT);
+						line.set("");
+						command = Some(String::from("stop"));
+					}
+					Key::Unknown => {}
+				}
+				let _ = line.draw(&mut out).and_then(|_| out.flush());
+			}
+			// The line is unlocked first so anything the command logs can be printed
+			if let Some(command) = command {
+				execute(state, &command);
+			}
+		}
+	}
+
+	/// Switches the terminal to reading a key at a time without echoing it, returning false if stdin
+	/// isn't a terminal or it can't be switched
+	///
+	/// This runs `stty`, so it only works on Unix-like systems. Elsewhere `stty` can't be found and
+	/// the console falls back to reading whole lines.
+	fn enable_raw_mode(&self) -> bool {
+		let terminal = match stty(&["-g"]) {
+			Some(terminal) => terminal,
+			None => return false,
+		};
+		// Ctrl+C is read as a key rather than sent as a signal, so it can stop the server cleanly
+		if stty(&["-icanon", "-echo", "-isig", "-ixon", "min", "1"]).is_none() {
+			return false;
+		}
+		let mut line = self.line.lock().unwrap();
+		line.terminal = Some(terminal.trim().to_owned());
+		line.editing = true;
+		let stdout = io::stdout();
+		let mut out = stdout.lock();
+		let _ = line.draw(&mut out).and_then(|_| out.flush());
+		true
+	}
+}
+
+/// Puts the terminal back the way it was when the guard is dropped
+pub struct RestoreGuard(Console);
+
+impl Drop for RestoreGuard {
+	fn drop(&mut self) {
+		self.0.restore();
+	}
+}
+
+/// Puts the terminal back to the settings saved when raw mode was turned on, if it was
+fn restore(mut line: MutexGuard<Line>) {
+	if let Some(terminal) = line.terminal.take() {
+		line.editing = false;
+		let stdout = io::stdout();
+		let mut out = stdout.lock();
+		let _ = write!(out, "\r\x1b[K").and_then(|_| out.flush());
+		let _ = stty(&[&terminal]);
+	}
+}
+
+/// Runs `stty` on the terminal connected to stdin, returning what it printed if it succeeded
+fn stty(args: &[&str]) -> Option<String> {
+	let output = Command::new("stty")
+		.args(args)
+		.stdin(Stdio::inherit())
+		.stderr(Stdio::null())
+		.output()
+		.ok()?;
+	if output.status.success() {
+		String::from_utf8(output.stdout).ok()
+	} else {
+		None
+	}
+}
+
+/// Runs a command typed into the console, which may start with a slash like in chat
+fn execute(state: &Arc<ServerState>, text: &str) {
+	let text = text.trim();
+	let command = text.strip_prefix('/').unwrap_or(text);
+	if !command.is_empty() {
+		state.commands.run(command, &Sender::Console, state);
+	}
+}
+
+/// Completes the line as far as all suggestions agree, returning them if there is more than one
+fn complete(state: &ServerState, line: &mut Line) -> Option<Vec<String>> {
+	let text = line.text();
+	let input = text.strip_prefix('/').unwrap_or(&text);
+	let offset = text.len() - input.len();
+	let (start, matches) = state.commands.suggest(input, &Sender::Console, state);
+	let first = matches.first()?;
+	let mut common = first.len();
+	for other in &matches[1..] {
+		let shared = first
+			.char_indices()
+			.zip(other.chars())
+			.take_while(|((_, a), b)| a == b)
+			.last()
+			.map_or(0, |((i, a), _)| i + a.len_utf8());
+		common = common.min(shared);
+	}
+	let mut completed = text[..offset + start].to_owned();
+	completed.push_str(&first[..common]);
+	if matches.len() == 1 {
+		completed.push(' ');
+	}
+	line.set(&completed);
+	if matches.len() > 1 {
+		Some(matches)
+	} else {
+		None
+	}
+}
+
+/// Reads the next key pressed, or None once stdin closes
+fn read_key<I: Iterator<Item = u8>>(input: &mut I) -> Option<Key> {
+	let byte = input.next()?;
+	Some(match byte {
+		0x01 => Key::Home,
+		0x03 => Key::Interrupt,
+		0x05 => Key::End,
+		0x08 | 0x7F => Key::Backspace,
+		b'\t' => Key::Tab,
+		b'\r' | b'\n' => Key::Enter,
+		0x15 => Key::Kill,
+		0x1B => read_escape(input)?,
+		0x00..=0x1F => Key::Unknown,
+		0x20..=0x7E => Key::Char(byte as char),
+		_ => {
+			// The rest of the bytes of a UTF-8 character
+			let length = match byte {
+				0xC0..=0xDF => 2,
+				0xE0..=0xEF => 3,
+				0xF0..=0xF7 => 4,
+				_ => return Some(Key::Unknown),
+			};
+			let mut bytes = vec![byte];
+			bytes.extend(input.take(length - 1));
+			match str::from_utf8(&bytes).ok().and_then(|c| c.chars().next()) {
+				Some(c) => Key::Char(c),
+				None => Key::Unknown,
+			}
+		}
+	})
+}
+
+/// Reads the rest of an escape sequence, which is how terminals send arrow keys and the like
+fn read_escape<I: Iterator<Item = u8>>(input: &mut I) -> Option<Key> {
+	let kind = input.next()?;
+	if kind != b'[' && kind != b'O' {
+		return Some(Key::Unknown);
+	}
+	let mut parameter = String::new();
+	loop {
+		let byte = input.next()?;
+		if (0x40..=0x7E).contains(&byte) {
+			return Some(match (byte, parameter.as_str()) {
+				(b'A', _) => Key::Up,
+				(b'B', _) => Key::Down,
+				(b'C', _) => Key::Right,
+				(b'D', _) => Key::Left,
+				(b'H', _) | (b'~', "1") | (b'~', "7") => Key::Home,
+				(b'F', _) | (b'~', "4") | (b'~', "8") => Key::End,
+				(b'~', "3") => Key::Delete,
+				_ => Key::Unknown,
+			});
+		}
+		parameter.push(byte as char);
+	}
+}
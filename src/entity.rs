@@ -0,0 +1,233 @@
+use ::openssl::{error::ErrorStack, rand::rand_bytes};
+use ::std::collections::{HashMap, HashSet};
+use ::uuid::Uuid;
+
+use crate::{
+	auth::GameProfile,
+	player::{Position, Rotation},
+};
+
+/// The kinds of entity the server knows how to show to clients
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EntityKind {
+	Item,
+	Player,
+}
+
+impl EntityKind {
+	/// ID of the entity type in 1.16.5
+	pub fn id(&self) -> i32 {
+		match self {
+			Self::Item => 37,
+			Self::Player => 106,
+		}
+	}
+
+	/// Name of the entity type, which selectors refer to it by
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::Item => "minecraft:item",
+			Self::Player => "minecraft:player",
+		}
+	}
+
+	/// Distance in blocks players can see this kind of entity from, before view distance is applied
+	pub fn tracking_range(&self) -> f64 {
+		match self {
+			Self::Item => 96.0,
+			Self::Player => 512.0,
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Entity {
+	pub id: i32,
+	pub uuid: Uuid,
+	pub kind: EntityKind,
+	pub position: Position,
+	pub rotation: Rotation,
+	pub on_ground: bool,
+	/// Set for players, whose name and skin other clients need to show them
+	pub profile: Option<GameProfile>,
+	/// Scoreboard tags, which selectors can pick entities out by
+	pub tags: HashSet<String>,
+	/// Position and rotation as last sent to the players tracking this entity
+	sent: (Position, Rotation),
+}
+
+impl Entity {
+	/// Creates an entity with a random UUID
+	pub fn new(
+		id: i32,
+		kind: EntityKind,
+		position: Position,
+		rotation: Rotation,
+	) -> Result<Self, ErrorStack> {
+		let mut bytes = [0u8; 16];
+		rand_bytes(&mut bytes)?;
+		// Mark the UUID as a version 4 (random) UUID
+		bytes[6] = (bytes[6] & 0x0F) | 0x40;
+		bytes[8] = (bytes[8] & 0x3F) | 0x80;
+		Ok(Self::with_uuid(
+			id,
+			Uuid::from_bytes(bytes),
+			kind,
+			position,
+			rotation,
+		))
+	}
+
+	pub fn player(id: i32, profile: GameProfile, position: Position, rotation: Rotation) -> Self {
+		let mut entity = Self::with_uuid(id, profile.uuid, EntityKind::Player, position, rotation);
+		entity.profile = Some(profile);
+		entity
+	}
+
+	fn with_uuid(
+		id: i32,
+		uuid: Uuid,
+		kind: EntityKind,
+		position: Position,
+		rotation: Rotation,
+	) -> Self {
+		Self {
+			id,
+			uuid,
+			kind,
+			position,
+			rotation,
+			on_ground: false,
+			profile: None,
+			tags: HashSet::new(),
+			sent: (position, rotation),
+		}
+	}
+}
+
+/// A change a player needs to make to the entities they can see
+#[derive(Clone, Debug)]
+pub enum EntityUpdate {
+	/// The entity came into range
+	Spawn(Entity),
+	/// The entity moved from where it was last sent, and may have turned as well
+	Move {
+		entity_id: i32,
+		from: Position,
+		to: Position,
+		rotation: Rotation,
+		on_ground: bool,
+	},
+	/// The entity turned without moving
+	Rotate {
+		entity_id: i32,
+		rotation: Rotation,
+		on_ground: bool,
+	},
+	/// The entities left range or were removed
+	Destroy(Vec<i32>),
+}
+
+/// Every entity in a world, along with which of them each player can see
+#[derive(Default)]
+pub struct EntityStore {
+	entities: HashMap<i32, Entity>,
+	/// The entities each player has been sent, keyed by the player's entity ID
+	visible: HashMap<i32, HashSet<i32>>,
+}
+
+impl EntityStore {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn get(&self, id: i32) -> Option<&Entity> {
+		self.entities.get(&id)
+	}
+
+	pub fn get_mut(&mut self, id: i32) -> Option<&mut Entity> {
+		self.entities.get_mut(&id)
+	}
+
+	pub fn insert(&mut self, entity: Entity) {
+		self.entities.insert(entity.id, entity);
+	}
+
+	/// Removes an entity, forgetting what it could see if it is a player so everything is spawned for
+	/// them again if they come back
+	pub fn remove(&mut self, id: i32) -> Option<Entity> {
+		self.visible.remove(&id);
+		self.entities.remove(&id)
+	}
+
+	pub fn values(&self) -> impl Iterator<Item = &Entity> {
+		self.entities.values()
+	}
+
+	/// Works out what each player needs to be told about the entities around them since the last
+	/// time this was called, where players can see no further than `view_distance` chunks
+	pub fn track(&mut self, view_distance: u8) -> HashMap<i32, Vec<EntityUpdate>> {
+		let limit = view_distance as f64 * 16.0;
+		let mut moved = HashMap::new();
+		for entity in self.entities.values_mut() {
+			let (position, rotation) = entity.sent;
+			if entity.position != position {
+				moved.insert(
+					entity.id,
+					EntityUpdate::Move {
+						entity_id: entity.id,
+						from: position,
+						to: entity.position,
+						rotation: entity.rotation,
+						on_ground: entity.on_ground,
+					},
+				);
+			} else if entity.rotation != rotation {
+				moved.insert(
+					entity.id,
+					EntityUpdate::Rotate {
+						entity_id: entity.id,
+						rotation: entity.rotation,
+						on_ground: entity.on_ground,
+					},
+				);
+			}
+			entity.sent = (entity.position, entity.rotation);
+		}
+		let entities = &self.entities;
+		self.visible.retain(|id, _| entities.contains_key(id));
+		let mut updates = HashMap::new();
+		for observer in entities.values().filter(|e| e.kind == EntityKind::Player) {
+			let in_range = |entity: &Entity| {
+				let range = entity.kind.tracking_range().min(limit);
+				entity.id != observer.id
+					&& (entity.position.x - observer.position.x).abs() <= range
+					&& (entity.position.z - observer.position.z).abs() <= range
+			};
+			let visible = self.visible.entry(observer.id).or_default();
+			let mut list = Vec::new();
+			let mut destroyed = Vec::new();
+			visible.retain(|id| match entities.get(id) {
+				Some(entity) if in_range(entity) => true,
+				_ => {
+					destroyed.push(*id);
+					false
+				}
+			});
+			if !destroyed.is_empty() {
+				list.push(EntityUpdate::Destroy(destroyed));
+			}
+			for entity in entities.values().filter(|e| in_range(e)) {
+				if visible.insert(entity.id) {
+					list.push(EntityUpdate::Spawn(entity.clone()));
+				} else if let Some(update) = moved.get(&entity.id) {
+					list.push(update.clone());
+				}
+			}
+			if !list.is_empty() {
+				updates.insert(observer.id, list);
+			}
+		}
+		updates
+	}
+}
@@ -0,0 +1,39 @@
+use ::flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use ::std::io::{Read, Result, Write};
+
+/// Wraps data in a gzip stream, such as the `.dat` files vanilla saves
+pub fn compress(data: &[u8]) -> Vec<u8> {
+	let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+	// Writing to a Vec can't fail
+	encoder.write_all(data).unwrap();
+	encoder.finish().unwrap()
+}
+
+/// Unpacks a gzip stream, failing if it is malformed or cut short
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+	let mut buffer = Vec::new();
+	GzDecoder::new(data).read_to_end(&mut buffer)?;
+	Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn round_trip() {
+		let data: Vec<u8> = (0..200_000u32)
+			.map(|i| ((i % 251) ^ (i / 7)) as u8)
+			.collect();
+		for data in &[&[][..], b"basalt", &data] {
+			assert_eq!(decompress(&compress(data)).unwrap(), *data);
+		}
+	}
+
+	#[test]
+	fn rejects_truncated_data() {
+		let compressed = compress(b"Hello, world! Hello, world! Hello, world!");
+		assert!(decompress(&compressed[..compressed.len() - 6]).is_err());
+		assert!(decompress(b"not gzip at all").is_err());
+	}
+}
@@ -0,0 +1,867 @@
+use ::std::ops::Range;
+
+use crate::{
+	item::max_stack,
+	recipe::RecipeRegistry,
+	types::{Hand, Slot},
+};
+
+/// Number of slots in the player's own window: the crafting output and grid, armor, the main
+/// inventory, the hotbar and the off hand
+pub const PLAYER_SLOTS: usize = 46;
+/// First slot of the main inventory in the player's own window, which is followed by the hotbar
+const MAIN: usize = 9;
+const MAIN_SIZE: usize = 27;
+const HOTBAR_SIZE: usize = 9;
+pub const OFFHAND: usize = 45;
+/// Window and slot used to set the item held by the cursor
+pub const CURSOR_WINDOW: u8 = 255;
+pub const CURSOR_SLOT: i16 = -1;
+/// Slot clicked when a client clicks outside of a window
+const OUTSIDE: i16 = -999;
+
+/// Whether two stacks are of the same item and can be merged
+fn stackable(a: &Slot, b: &Slot) -> bool {
+	a.item == b.item && a.nbt == b.nbt
+}
+
+/// Number vanilla saves the item in a slot of the player's window under, which counts the hotbar
+/// first, then the main inventory, then armor from 100 and the off hand at -106
+fn saved_slot(slot: usize) -> Option<i8> {
+	if (5..MAIN).contains(&slot) {
+		// Armor is saved from the boots up, but shown from the helmet down
+		Some(108 - slot as i8)
+	} else if (MAIN..MAIN + MAIN_SIZE).contains(&slot) {
+		Some(slot as i8)
+	} else if (MAIN + MAIN_SIZE..OFFHAND).contains(&slot) {
+		Some((slot - MAIN - MAIN_SIZE) as i8)
+	} else if slot == OFFHAND {
+		Some(-106)
+	} else {
+		None
+	}
+}
+
+/// The kinds of container block a window can be opened for
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ContainerKind {
+	Chest,
+	CraftingTable,
+	Dispenser,
+}
+
+impl ContainerKind {
+	/// Finds the kind of container a block is, if it is one
+	pub fn of_block(name: &str) -> Option<Self> {
+		match name {
+			"minecraft:chest" => Some(Self::Chest),
+			"minecraft:crafting_table" => Some(Self::CraftingTable),
+			"minecraft:dispenser" => Some(Self::Dispenser),
+			_ => None,
+		}
+	}
+
+	/// Whether the container's items stay in the world, rather than belonging to whoever is using it
+	pub fn is_shared(&self) -> bool {
+		*self != Self::CraftingTable
+	}
+
+	pub fn size(&self) -> usize {
+		match self {
+			Self::Chest => 27,
+			Self::CraftingTable => 10,
+			Self::Dispenser => 9,
+		}
+	}
+
+	/// Translation key of the window's title
+	pub fn title(&self) -> &'static str {
+		match self {
+			Self::Chest => "container.chest",
+			Self::CraftingTable => "container.crafting",
+			Self::Dispenser => "container.dispenser",
+		}
+	}
+
+	/// ID of the window type in 1.16.5
+	pub fn window_type(&self) -> i32 {
+		match self {
+			Self::Chest => 2,
+			Self::CraftingTable => 11,
+			Self::Dispenser => 6,
+		}
+	}
+}
+
+/// How the slots of a window are laid out
+struct Layout {
+	/// Slots that belong to the container, or to the crafting grid and armor in the player's own window
+	container: Range<usize>,
+	/// Slot crafted items come out of, which nothing can be put into
+	output: Option<usize>,
+	/// Slots of the crafting grid and how wide it is
+	grid: Option<(Range<usize>, usize)>,
+	/// First slot of the player's main inventory, which is followed by the hotbar
+	main: usize,
+	offhand: Option<usize>,
+	size: usize,
+}
+
+impl Layout {
+	fn player() -> Self {
+		Self {
+			container: 0..MAIN,
+			output: Some(0),
+			grid: Some((1..5, 2)),
+			main: MAIN,
+			offhand: Some(OFFHAND),
+			size: PLAYER_SLOTS,
+		}
+	}
+
+	fn container(kind: ContainerKind) -> Self {
+		let size = kind.size();
+		let crafting = kind == ContainerKind::CraftingTable;
+		Self {
+			container: 0..size,
+			output: if crafting { Some(0) } else { None },
+			grid: if crafting { Some((1..size, 3)) } else { None },
+			main: size,
+			offhand: None,
+			size: size + MAIN_SIZE + HOTBAR_SIZE,
+		}
+	}
+
+	fn main(&self) -> Range<usize> {
+		self.main..self.main + MAIN_SIZE
+	}
+
+	fn hotbar(&self) -> Range<usize> {
+		self.main + MAIN_SIZE..self.main + MAIN_SIZE + HOTBAR_SIZE
+	}
+
+	/// The main inventory and hotbar together
+	fn inventory(&self) -> Range<usize> {
+		self.main..self.main + MAIN_SIZE + HOTBAR_SIZE
+	}
+}
+
+/// A click in a window, as sent by the client
+#[derive(Clone, Copy, Debug)]
+pub struct Click {
+	pub slot: i16,
+	pub button: i8,
+	pub mode: i32,
+}
+
+/// How the items on the cursor are spread over the slots of a drag
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum DragKind {
+	/// Splits the items evenly, which is done with the left mouse button
+	Even,
+	/// Puts one item in each slot, which is done with the right mouse button
+	One,
+	/// Fills each slot with a full stack without using up the cursor, which only creative players can do
+	Clone,
+}
+
+/// A drag across several slots that hasn't been finished yet
+#[derive(Clone)]
+struct Drag {
+	kind: DragKind,
+	slots: Vec<usize>,
+}
+
+/// The outcome of a click
+pub struct ClickResult {
+	/// Whether the click was something the player could do and the client knew what it was clicking
+	/// on, without which nothing is changed
+	pub valid: bool,
+	/// What was in the clicked slot before the click, which is what the client expects it to be
+	pub clicked: Option<Slot>,
+	/// Window slots whose contents changed
+	pub changed: Vec<i16>,
+	pub cursor_changed: bool,
+}
+
+/// The items a player carries, laid out the way their own window shows them
+pub struct PlayerInventory {
+	pub slots: Vec<Option<Slot>>,
+	/// Items picked up with the cursor while a window is open
+	pub cursor: Option<Slot>,
+	drag: Option<Drag>,
+	/// Hotbar slot the player has selected
+	pub selected: usize,
+	/// The output and grid of the crafting table the player is using
+	pub table: Vec<Option<Slot>>,
+}
+
+impl PlayerInventory {
+	pub fn new() -> Self {
+		Self {
+			slots: vec![None; PLAYER_SLOTS],
+			cursor: None,
+			drag: None,
+			selected: 0,
+			table: vec![None; ContainerKind::CraftingTable.size()],
+		}
+	}
+
+	/// Slot in the player's window of the item held in a hand
+	pub fn held_slot(&self, hand: Hand) -> usize {
+		match hand {
+			Hand::Main => MAIN + MAIN_SIZE + self.selected,
+			Hand::Off => OFFHAND,
+		}
+	}
+
+	pub fn held(&self, hand: Hand) -> Option<&Slot> {
+		self.slots[self.held_slot(hand)].as_ref()
+	}
+
+	/// Uses up one of the items held in a hand
+	pub fn consume_held(&mut self, hand: Hand) {
+		let slot = self.held_slot(hand);
+		if let Some(stack) = &mut self.slots[slot] {
+			stack.count -= 1;
+			if stack.count <= 0 {
+				self.slots[slot] = None;
+			}
+		}
+	}
+
+	/// Sets a slot in the player's window directly, as creative players can, returning false if the
+	/// slot or item isn't valid
+	pub fn set(&mut self, slot: i16, item: Option<Slot>) -> bool {
+		if let Some(item) = &item {
+			if item.item <= 0 || item.count < 1 || item.count > max_stack(item.item) {
+				return false;
+			}
+		}
+		match self.slots.get_mut(slot as usize) {
+			// Nothing can be put into the crafting output
+			Some(current) if slot > 0 => {
+				*current = item;
+				true
+			}
+			_ => false,
+		}
+	}
+
+	/// Adds items to the main inventory and hotbar, returning whatever didn't fit
+	pub fn give(&mut self, stack: Slot) -> Option<Slot> {
+		let layout = Layout::player();
+		let mut stack = Some(stack);
+		move_into(&mut self.slots, &mut stack, layout.hotbar(), false);
+		move_into(&mut self.slots, &mut stack, layout.main(), false);
+		stack
+	}
+
+	/// Puts the cursor and crafting grid back into the inventory once the window they were used in is
+	/// closed, returning anything that didn't fit
+	pub fn close(&mut self) -> Vec<Slot> {
+		self.drag = None;
+		let mut items: Vec<Slot> = self.cursor.take().into_iter().collect();
+		self.slots[0] = None;
+		self.table[0] = None;
+		items.extend(self.slots[1..5].iter_mut().filter_map(Option::take));
+		items.extend(self.table[1..].iter_mut().filter_map(Option::take));
+		items
+			.into_iter()
+			.filter_map(|stack| self.give(stack))
+			.collect()
+	}
+
+	/// Every item in the inventory, along with the slot number vanilla saves it under
+	pub fn saved(&self) -> Vec<(i8, &Slot)> {
+		self.slots
+			.iter()
+			.enumerate()
+			.filter_map(|(slot, stack)| Some((saved_slot(slot)?, stack.as_ref()?)))
+			.collect()
+	}
+
+	/// Puts an item back in the slot vanilla saved it under, returning false if there is no such slot
+	pub fn restore(&mut self, saved: i8, stack: Slot) -> bool {
+		match (0..PLAYER_SLOTS).find(|&slot| saved_slot(slot) == Some(saved)) {
+			Some(slot) => {
+				self.slots[slot] = Some(stack);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Applies a click to the player's own window, or to a container window when given the kind and
+	/// contents of the container. `item` is what the client says was in the clicked slot.
+	pub fn click(
+		&mut self,
+		container: Option<(ContainerKind, &mut Vec<Option<Slot>>)>,
+		click: Click,
+		item: Option<&Slot>,
+		creative: bool,
+		recipes: &RecipeRegistry,
+	) -> ClickResult {
+		let (layout, mut slots) = match &container {
+			Some((kind, container)) => {
+				let mut slots = container.to_vec();
+				slots.extend_from_slice(&self.slots[MAIN..OFFHAND]);
+				(Layout::container(*kind), slots)
+			}
+			None => (Layout::player(), self.slots.clone()),
+		};
+		let before = slots.clone();
+		let clicked = if click.slot >= 0 {
+			before.get(click.slot as usize).cloned().flatten()
+		} else {
+			None
+		};
+		// Only the item in the clicked slot is known to the client for certain when it clicks
+		let predicted = click.mode != 0
+			|| item.map(|item| (item.item, item.count))
+				== clicked.as_ref().map(|item| (item.item, item.count));
+		// The click is worked out on copies, which are only kept if it turns out to be valid
+		let mut cursor = self.cursor.clone();
+		let mut drag = self.drag.clone();
+		let valid = predicted
+			&& match layout.output {
+				Some(output) if click.mode == 1 && click.slot == output as i16 => {
+					drag = None;
+					craft_all(&mut slots, &layout, recipes);
+					true
+				}
+				Some(output) => {
+					let result = slots[output].clone();
+					let valid = apply(&mut slots, &layout, &mut cursor, &mut drag, click, creative);
+					// Anything done to the output takes what was crafted
+					if result.is_some() && slots[output] != result {
+						consume_grid(&mut slots, &layout);
+					}
+					valid
+				}
+				None => apply(&mut slots, &layout, &mut cursor, &mut drag, click, creative),
+			};
+		if !valid {
+			self.drag = None;
+			return ClickResult {
+				valid,
+				clicked,
+				changed: Vec::new(),
+				cursor_changed: false,
+			};
+		}
+		update_output(&mut slots, &layout, recipes);
+		let changed = before
+			.iter()
+			.zip(&slots)
+			.enumerate()
+			.filter(|(_, (before, after))| before != after)
+			.map(|(slot, _)| slot as i16)
+			.collect();
+		match container {
+			Some((_, container)) => {
+				let size = container.len();
+				container.clone_from_slice(&slots[..size]);
+				self.slots[MAIN..OFFHAND].clone_from_slice(&slots[size..]);
+			}
+			None => self.slots = slots,
+		}
+		let cursor_changed = cursor != self.cursor;
+		self.cursor = cursor;
+		self.drag = drag;
+		ClickResult {
+			valid,
+			clicked,
+			changed,
+			cursor_changed,
+		}
+	}
+
+	/// Applies a click to the window of the crafting table the player is using, returning the new
+	/// contents of its grid along with the outcome
+	pub fn click_table(
+		&mut self,
+		click: Click,
+		item: Option<&Slot>,
+		creative: bool,
+		recipes: &RecipeRegistry,
+	) -> (ClickResult, Vec<Option<Slot>>) {
+		let mut table = ::std::mem::take(&mut self.table);
+		let result = self.click(
+			Some((ContainerKind::CraftingTable, &mut table)),
+			click,
+			item,
+			creative,
+			recipes,
+		);
+		self.table = table.clone();
+		(result, table)
+	}
+
+	/// Contents of a window as the client should see them
+	pub fn window(&self, container: Option<&[Option<Slot>]>) -> Vec<Option<Slot>> {
+		match container {
+			Some(container) => {
+				let mut slots = container.to_vec();
+				slots.extend_from_slice(&self.slots[MAIN..OFFHAND]);
+				slots
+			}
+			None => self.slots.clone(),
+		}
+	}
+}
+
+/// Carries out a click on the slots of a window, returning false if it wasn't possible
+fn apply(
+	slots: &mut [Option<Slot>],
+	layout: &Layout,
+	cursor: &mut Option<Slot>,
+	drag: &mut Option<Drag>,
+	click: Click,
+	creative: bool,
+) -> bool {
+	if click.mode == 5 {
+		return apply_drag(slots, layout, cursor, drag, click, creative);
+	}
+	*drag = None;
+	let index = match click.slot {
+		OUTSIDE => None,
+		slot if slot >= 0 && (slot as usize) < layout.size => Some(slot as usize),
+		_ => return false,
+	};
+	match (click.mode, click.button, index) {
+		// Thrown items are destroyed until there are item entities to drop them as
+		(0, 0, None) => *cursor = None,
+		(0, 1, None) => take_one(cursor),
+		(0, button, Some(index)) if button == 0 || button == 1 => {
+			if layout.output == Some(index) {
+				take_output(&mut slots[index], cursor);
+			} else if button == 0 {
+				left_click(&mut slots[index], cursor);
+			} else {
+				right_click(&mut slots[index], cursor);
+			}
+		}
+		(1, button, Some(index)) if button == 0 || button == 1 => quick_move(slots, layout, index),
+		(2, button, Some(index)) => {
+			let other = match button {
+				0..=8 => layout.hotbar().start + button as usize,
+				40 => match layout.offhand {
+					Some(offhand) => offhand,
+					None => return false,
+				},
+				_ => return false,
+			};
+			if layout.output == Some(index) && slots[other].is_some() {
+				return false;
+			}
+			slots.swap(index, other);
+		}
+		(3, 2, Some(index)) => {
+			if creative && cursor.is_none() {
+				*cursor = slots[index].clone().map(|stack| Slot {
+					count: max_stack(stack.item),
+					..stack
+				});
+			}
+		}
+		(4, _, None) => {}
+		(4, 0, Some(index)) => take_one(&mut slots[index]),
+		(4, 1, Some(index)) => slots[index] = None,
+		(6, 0, Some(_)) => collect(slots, layout, cursor),
+		_ => return false,
+	}
+	true
+}
+
+fn apply_drag(
+	slots: &mut [Option<Slot>],
+	layout: &Layout,
+	cursor: &mut Option<Slot>,
+	drag: &mut Option<Drag>,
+	click: Click,
+	creative: bool,
+) -> bool {
+	let kind = match click.button / 4 {
+		0 => DragKind::Even,
+		1 => DragKind::One,
+		2 if creative => DragKind::Clone,
+		_ => {
+			*drag = None;
+			return false;
+		}
+	};
+	let stack = match cursor {
+		Some(stack) => stack.clone(),
+		None => {
+			*drag = None;
+			return false;
+		}
+	};
+	match click.button % 4 {
+		0 if click.slot == OUTSIDE => {
+			*drag = Some(Drag {
+				kind,
+				slots: Vec::new(),
+			});
+			true
+		}
+		1 => {
+			let index = click.slot as usize;
+			let fits = click.slot >= 0
+				&& index < layout.size
+				&& layout.output != Some(index)
+				&& slots[index]
+					.as_ref()
+					.map_or(true, |slot| stackable(slot, &stack));
+			match drag {
+				Some(drag) if drag.kind == kind && fits => {
+					// Each slot needs at least one item, unless the items are being cloned
+					let room = kind == DragKind::Clone || drag.slots.len() < stack.count as usize;
+					if room && !drag.slots.contains(&index) {
+						drag.slots.push(index);
+					}
+					true
+				}
+				_ => {
+					*drag = None;
+					false
+				}
+			}
+		}
+		2 if click.slot == OUTSIDE => {
+			let drag = match drag.take() {
+				Some(drag) if drag.kind == kind => drag,
+				_ => return false,
+			};
+			let max = max_stack(stack.item) as i32;
+			let mut remaining = stack.count as i32;
+			let each = match kind {
+				DragKind::Even => remaining / drag.slots.len().max(1) as i32,
+				DragKind::One => 1,
+				DragKind::Clone => max,
+			};
+			for index in drag.slots {
+				// The window may have changed since the slot was dragged over, such as when someone
+				// else put an item into the same chest
+				let current = match &slots[index] {
+					Some(slot) if !stackable(slot, &stack) => continue,
+					Some(slot) => slot.count as i32,
+					None => 0,
+				};
+				let added = each.min(max - current);
+				if added <= 0 {
+					continue;
+				}
+				slots[index] = Some(Slot {
+					count: (current + added) as i8,
+					..stack.clone()
+				});
+				if kind != DragKind::Clone {
+					remaining -= added;
+				}
+			}
+			*cursor = if remaining > 0 {
+				Some(Slot {
+					count: remaining as i8,
+					..stack
+				})
+			} else {
+				None
+			};
+			true
+		}
+		_ => {
+			*drag = None;
+			false
+		}
+	}
+}
+
+/// Picks up, puts down, merges or swaps the whole stack
+fn left_click(slot: &mut Option<Slot>, cursor: &mut Option<Slot>) {
+	match (slot.as_mut(), cursor.as_mut()) {
+		(None, None) => {}
+		(Some(_), None) | (None, Some(_)) => ::std::mem::swap(slot, cursor),
+		(Some(stack), Some(held)) if stackable(stack, held) => {
+			let room = max_stack(stack.item) as i32 - stack.count as i32;
+			let moved = (held.count as i32).min(room).max(0) as i8;
+			stack.count += moved;
+			held.count -= moved;
+			if held.count <= 0 {
+				*cursor = None;
+			}
+		}
+		(Some(_), Some(_)) => ::std::mem::swap(slot, cursor),
+	}
+}
+
+/// Picks up half the stack, puts down a single item or swaps stacks that can't be merged
+fn right_click(slot: &mut Option<Slot>, cursor: &mut Option<Slot>) {
+	match (slot.as_mut(), cursor.as_mut()) {
+		(None, None) => {}
+		(Some(stack), None) => {
+			let taken = (stack.count + 1) / 2;
+			stack.count -= taken;
+			*cursor = Some(Slot {
+				count: taken,
+				..stack.clone()
+			});
+			if stack.count <= 0 {
+				*slot = None;
+			}
+		}
+		(None, Some(held)) => {
+			*slot = Some(Slot {
+				count: 1,
+				..held.clone()
+			});
+			take_one(cursor);
+		}
+		(Some(stack), Some(held)) if stackable(stack, held) => {
+			if stack.count < max_stack(stack.item) {
+				stack.count += 1;
+				take_one(cursor);
+			}
+		}
+		(Some(_), Some(_)) => ::std::mem::swap(slot, cursor),
+	}
+}
+
+/// Takes the items out of a crafting output, which can only be added to the cursor
+fn take_output(slot: &mut Option<Slot>, cursor: &mut Option<Slot>) {
+	match (slot.as_ref(), cursor.as_mut()) {
+		(Some(_), None) => *cursor = slot.take(),
+		(Some(stack), Some(held))
+			if stackable(stack, held)
+				&& held.count as i32 + stack.count as i32 <= max_stack(held.item) as i32 =>
+		{
+			held.count += stack.count;
+			*slot = None;
+		}
+		_ => {}
+	}
+}
+
+fn take_one(slot: &mut Option<Slot>) {
+	if let Some(stack) = slot {
+		stack.count -= 1;
+		if stack.count <= 0 {
+			*slot = None;
+		}
+	}
+}
+
+/// Moves a stack to the other part of the window, the way shift clicking does
+fn quick_move(slots: &mut [Option<Slot>], layout: &Layout, index: usize) {
+	let mut stack = slots[index].take();
+	if layout.container.contains(&index) || layout.offhand == Some(index) {
+		// Containers and crafting outputs fill the hotbar first, as they do in vanilla
+		let reverse = layout.output.is_none() || layout.output == Some(index);
+		move_into(slots, &mut stack, layout.inventory(), reverse);
+	} else if layout.output.is_none() {
+		move_into(slots, &mut stack, layout.container.clone(), false);
+	} else if layout.main().contains(&index) {
+		move_into(slots, &mut stack, layout.hotbar(), false);
+	} else {
+		move_into(slots, &mut stack, layout.main(), false);
+	}
+	// Whatever didn't fit stays where it was
+	if slots[index].is_none() {
+		slots[index] = stack;
+	}
+}
+
+/// Crafts as many of the output as fit in the inventory, the way shift clicking the output does
+fn craft_all(slots: &mut [Option<Slot>], layout: &Layout, recipes: &RecipeRegistry) {
+	let output = match layout.output {
+		Some(output) => output,
+		None => return,
+	};
+	while let Some(result) = slots[output].clone() {
+		let mut crafted = slots.to_vec();
+		let mut stack = crafted[output].take();
+		move_into(&mut crafted, &mut stack, layout.inventory(), true);
+		if stack.is_some() {
+			break;
+		}
+		consume_grid(&mut crafted, layout);
+		update_output(&mut crafted, layout, recipes);
+		slots.clone_from_slice(&crafted);
+		// Crafting stops once the grid makes something else
+		if slots[output].as_ref().map(|stack| stack.item) != Some(result.item) {
+			break;
+		}
+	}
+}
+
+/// Uses up one of each item in the crafting grid once what they made has been taken
+fn consume_grid(slots: &mut [Option<Slot>], layout: &Layout) {
+	if let Some((grid, _)) = &layout.grid {
+		for index in grid.clone() {
+			take_one(&mut slots[index]);
+		}
+	}
+}
+
+/// Puts whatever the crafting grid makes in the output
+fn update_output(slots: &mut [Option<Slot>], layout: &Layout, recipes: &RecipeRegistry) {
+	if let (Some(output), Some((grid, width))) = (layout.output, &layout.grid) {
+		slots[output] = recipes.craft(&slots[grid.clone()], *width);
+	}
+}
+
+/// Merges a stack into a range of slots, filling stacks of the same item before empty slots
+fn move_into(
+	slots: &mut [Option<Slot>],
+	stack: &mut Option<Slot>,
+	range: Range<usize>,
+	reverse: bool,
+) {
+	let order: Vec<usize> = if reverse {
+		range.rev().collect()
+	} else {
+		range.collect()
+	};
+	for index in &order {
+		let moving = match stack.as_mut() {
+			Some(moving) => moving,
+			None => return,
+		};
+		if let Some(existing) = &mut slots[*index] {
+			if stackable(existing, moving) {
+				let room = max_stack(existing.item) as i32 - existing.count as i32;
+				let moved = (moving.count as i32).min(room).max(0) as i8;
+				existing.count += moved;
+				moving.count -= moved;
+				if moving.count <= 0 {
+					*stack = None;
+				}
+			}
+		}
+	}
+	if let Some(index) = order.into_iter().find(|index| slots[*index].is_none()) {
+		slots[index] = stack.take();
+	}
+}
+
+/// Gathers items of the same kind as the cursor into it, the way double clicking does
+fn collect(slots: &mut [Option<Slot>], layout: &Layout, cursor: &mut Option<Slot>) {
+	let held = match cursor {
+		Some(held) => held,
+		None => return,
+	};
+	let max = max_stack(held.item) as i32;
+	// Stacks that aren't full are taken from first, so full stacks are only broken up if needed
+	for full in &[false, true] {
+		for (index, slot) in slots.iter_mut().enumerate() {
+			if held.count as i32 >= max || layout.output == Some(index) {
+				continue;
+			}
+			if let Some(stack) = slot {
+				if stackable(stack, held) && (stack.count as i32 >= max) == *full {
+					let moved = (stack.count as i32).min(max - held.count as i32) as i8;
+					held.count += moved;
+					stack.count -= moved;
+					if stack.count <= 0 {
+						*slot = None;
+					}
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::item::ItemRegistry;
+
+	fn stack(item: i32, count: i8) -> Option<Slot> {
+		Some(Slot {
+			item,
+			count,
+			nbt: None,
+		})
+	}
+
+	fn click(slot: i16, button: i8, mode: i32) -> Click {
+		Click { slot, button, mode }
+	}
+
+	fn recipes() -> RecipeRegistry {
+		RecipeRegistry::load("", &ItemRegistry::new()).unwrap()
+	}
+
+	#[test]
+	fn rejected_clicks_change_nothing() {
+		let recipes = recipes();
+		let mut inventory = PlayerInventory::new();
+		inventory.slots[MAIN] = stack(1, 10);
+		// The client thinks the slot holds something else, so it can't know what it picked up
+		let result = inventory.click(None, click(MAIN as i16, 0, 0), None, false, &recipes);
+		assert!(!result.valid);
+		assert!(result.changed.is_empty());
+		assert_eq!(inventory.slots[MAIN], stack(1, 10));
+		assert_eq!(inventory.cursor, None);
+		// There is no hotbar slot 41 to swap with
+		let result = inventory.click(None, click(MAIN as i16, 41, 2), None, false, &recipes);
+		assert!(!result.valid);
+		assert_eq!(inventory.slots[MAIN], stack(1, 10));
+		inventory.cursor = stack(1, 4);
+		let result = inventory.click(
+			None,
+			click(MAIN as i16, 0, 0),
+			stack(1, 10).as_ref(),
+			false,
+			&recipes,
+		);
+		assert!(result.valid);
+		assert_eq!(result.changed, [MAIN as i16]);
+		assert_eq!(inventory.slots[MAIN], stack(1, 14));
+		assert_eq!(inventory.cursor, None);
+	}
+
+	#[test]
+	fn crafted_items_never_overflow_the_cursor() {
+		let mut output = stack(1, 100);
+		let mut cursor = stack(1, 100);
+		take_output(&mut output, &mut cursor);
+		assert_eq!(output, stack(1, 100));
+		assert_eq!(cursor, stack(1, 100));
+		let mut output = stack(1, 4);
+		take_output(&mut output, &mut cursor);
+		assert_eq!(cursor, stack(1, 100));
+		let mut cursor = stack(1, 60);
+		take_output(&mut output, &mut cursor);
+		assert_eq!(output, None);
+		assert_eq!(cursor, stack(1, 64));
+	}
+
+	#[test]
+	fn drag_skips_slots_filled_while_dragging() {
+		let layout = Layout::container(ContainerKind::Chest);
+		let mut slots = vec![None; layout.size];
+		let mut cursor = stack(1, 10);
+		let mut drag = None;
+		let mut drag_click = |slots: &mut Vec<Option<Slot>>, slot, button| {
+			apply(
+				slots,
+				&layout,
+				&mut cursor,
+				&mut drag,
+				click(slot, button, 5),
+				false,
+			)
+		};
+		assert!(drag_click(&mut slots, OUTSIDE, 0));
+		assert!(drag_click(&mut slots, 0, 1));
+		assert!(drag_click(&mut slots, 1, 1));
+		// Someone else puts a different item into one of the dragged over slots
+		slots[1] = stack(2, 3);
+		assert!(drag_click(&mut slots, OUTSIDE, 2));
+		assert_eq!(slots[0], stack(1, 5));
+		assert_eq!(slots[1], stack(2, 3));
+		assert_eq!(cursor, stack(1, 5));
+	}
+}
@@ -0,0 +1,94 @@
+use ::std::collections::HashMap;
+
+use crate::world::registry::WORLD_PROTOCOL;
+
+/// A single item as listed in a vanilla registries report
+#[derive(Clone, Copy, Debug)]
+pub struct Item {
+	pub id: i32,
+	pub name: &'static str,
+}
+
+include!(concat!(env!("OUT_DIR"), "/items.rs"));
+
+/// Items stored in worlds and inventories, generated from the vanilla data reports in `data`
+pub struct ItemRegistry {
+	ids: HashMap<&'static str, i32>,
+}
+
+impl ItemRegistry {
+	pub fn new() -> Self {
+		Self {
+			ids: items().iter().map(|item| (item.name, item.id)).collect(),
+		}
+	}
+
+	pub fn id(&self, name: &str) -> Option<i32> {
+		self.ids.get(name).copied()
+	}
+
+	pub fn name(&self, id: i32) -> Option<&'static str> {
+		name(id)
+	}
+
+	pub fn names(&self) -> impl Iterator<Item = &'static str> {
+		items().iter().map(|item| item.name)
+	}
+}
+
+/// The items of the version worlds are stored with, ordered by ID
+fn items() -> &'static [Item] {
+	ITEMS
+		.iter()
+		.find(|(protocol, _)| *protocol == WORLD_PROTOCOL)
+		.map(|(_, items)| *items)
+		.unwrap_or_default()
+}
+
+fn name(id: i32) -> Option<&'static str> {
+	let items = items();
+	items
+		.binary_search_by_key(&id, |item| item.id)
+		.ok()
+		.map(|index| items[index].name)
+}
+
+/// Largest stack an item can form, following the rules vanilla gives each kind of item
+pub fn max_stack(item: i32) -> i8 {
+	let name = match name(item) {
+		Some(name) => name.trim_start_matches("minecraft:"),
+		None => return 64,
+	};
+	const UNSTACKABLE: &[&str] = &[
+		"_sword",
+		"_shovel",
+		"_pickaxe",
+		"_axe",
+		"_hoe",
+		"_helmet",
+		"_chestplate",
+		"_leggings",
+		"_boots",
+		"_horse_armor",
+		"_boat",
+		"_minecart",
+		"_bed",
+		"_stew",
+		"_soup",
+		"_shulker_box",
+		"_on_a_stick",
+		"_potion",
+	];
+	const SIXTEENS: &[&str] = &["_sign", "_banner"];
+	match name {
+		"bow" | "crossbow" | "trident" | "shield" | "elytra" | "fishing_rod"
+		| "flint_and_steel" | "shears" | "saddle" | "minecart" | "potion" | "shulker_box"
+		| "cake" | "enchanted_book" | "writable_book" | "written_book" | "totem_of_undying"
+		| "knowledge_book" | "debug_stick" => 1,
+		"ender_pearl" | "snowball" | "egg" | "bucket" | "honey_bottle" | "armor_stand" => 16,
+		_ if name.ends_with("_bucket") || name.starts_with("music_disc_") => 1,
+		_ if UNSTACKABLE.iter().any(|suffix| name.ends_with(suffix)) => 1,
+		_ if SIXTEENS.iter().any(|suffix| name.ends_with(suffix)) => 16,
+		_ => 64,
+	}
+}
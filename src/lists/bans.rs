@@ -0,0 +1,144 @@
+use ::chrono::{DateTime, FixedOffset, Local};
+use ::serde::{Deserialize, Serialize};
+use ::std::{io, net::IpAddr};
+
+use crate::{auth::GameProfile, state::ServerState};
+
+use super::matches;
+
+/// How vanilla writes the dates in ban lists
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+/// What vanilla writes in place of a date for bans that never expire
+const FOREVER: &str = "forever";
+
+/// Why and until when someone is banned, as stored in both ban lists
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Ban {
+	pub created: String,
+	/// Who made the ban, which is `Server` for the console
+	pub source: String,
+	pub expires: String,
+	pub reason: String,
+}
+
+impl Ban {
+	/// Creates a ban starting now, which lasts forever if no expiry is given
+	pub fn new(source: String, reason: String, expires: Option<DateTime<Local>>) -> Self {
+		Self {
+			created: Local::now().format(DATE_FORMAT).to_string(),
+			source,
+			expires: match expires {
+				Some(expires) => expires.format(DATE_FORMAT).to_string(),
+				None => String::from(FOREVER),
+			},
+			reason,
+		}
+	}
+
+	/// When the ban runs out, or None if it never does
+	pub fn expiry(&self) -> Option<DateTime<FixedOffset>> {
+		DateTime::parse_from_str(&self.expires, DATE_FORMAT).ok()
+	}
+
+	pub fn is_expired(&self) -> bool {
+		match self.expiry() {
+			Some(expiry) => expiry < Local::now(),
+			None => false,
+		}
+	}
+
+	/// What a banned player is kicked with
+	pub fn message(&self, banned: &str) -> String {
+		let mut message = format!("{}\nReason: {}", banned, self.reason);
+		if let Some(expiry) = self.expiry() {
+			message.push_str(&format!(
+				"\nYour ban will be removed on {}",
+				expiry.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S")
+			));
+		}
+		message
+	}
+}
+
+/// A player listed in `banned-players.json`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PlayerBan {
+	pub uuid: String,
+	pub name: String,
+	#[serde(flatten)]
+	pub ban: Ban,
+}
+
+/// An address listed in `banned-ips.json`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IpBan {
+	pub ip: String,
+	#[serde(flatten)]
+	pub ban: Ban,
+}
+
+/// The ban keeping a player from joining, if they have one that hasn't expired
+pub fn player_ban(state: &ServerState, profile: &GameProfile) -> Option<Ban> {
+	let bans = state.banned_players.lock().unwrap();
+	bans.entries()
+		.iter()
+		.find(|entry| {
+			(matches(profile, &entry.uuid) || matches(profile, &entry.name))
+				&& !entry.ban.is_expired()
+		})
+		.map(|entry| entry.ban.clone())
+}
+
+/// The ban keeping an address from joining, if it has one that hasn't expired
+pub fn ip_ban(state: &ServerState, address: IpAddr) -> Option<Ban> {
+	let bans = state.banned_ips.lock().unwrap();
+	bans.entries()
+		.iter()
+		.find(|entry| entry.ip.parse::<IpAddr>().ok() == Some(address) && !entry.ban.is_expired())
+		.map(|entry| entry.ban.clone())
+}
+
+/// Bans a player, returning false if they already were
+pub fn ban_player(state: &ServerState, profile: &GameProfile, ban: Ban) -> io::Result<bool> {
+	if player_ban(state, profile).is_some() {
+		return Ok(false);
+	}
+	let mut bans = state.banned_players.lock().unwrap();
+	// Whatever expired ban the player still has is replaced
+	bans.remove(|entry| matches(profile, &entry.uuid) || matches(profile, &entry.name))?;
+	bans.add(PlayerBan {
+		uuid: profile.uuid.to_string(),
+		name: profile.name.clone(),
+		ban,
+	})?;
+	Ok(true)
+}
+
+/// Lifts a player's ban, returning false if they weren't banned
+pub fn pardon_player(state: &ServerState, profile: &GameProfile) -> io::Result<bool> {
+	let mut bans = state.banned_players.lock().unwrap();
+	let removed =
+		bans.remove(|entry| matches(profile, &entry.uuid) || matches(profile, &entry.name))?;
+	Ok(removed > 0)
+}
+
+/// Bans an address, returning false if it already was
+pub fn ban_ip(state: &ServerState, address: IpAddr, ban: Ban) -> io::Result<bool> {
+	if ip_ban(state, address).is_some() {
+		return Ok(false);
+	}
+	let mut bans = state.banned_ips.lock().unwrap();
+	bans.remove(|entry| entry.ip.parse::<IpAddr>().ok() == Some(address))?;
+	bans.add(IpBan {
+		ip: address.to_string(),
+		ban,
+	})?;
+	Ok(true)
+}
+
+/// Lifts an address's ban, returning false if it wasn't banned
+pub fn pardon_ip(state: &ServerState, address: IpAddr) -> io::Result<bool> {
+	let mut bans = state.banned_ips.lock().unwrap();
+	let removed = bans.remove(|entry| entry.ip.parse::<IpAddr>().ok() == Some(address))?;
+	Ok(removed > 0)
+}
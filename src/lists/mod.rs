@@ -0,0 +1,268 @@
+use ::log::{info, warn};
+use ::serde::{de::DeserializeOwned, Serialize};
+use ::std::{
+	error::Error,
+	fs,
+	io::{self, ErrorKind},
+	net::IpAddr,
+	path::PathBuf,
+	sync::atomic::Ordering,
+	time::SystemTime,
+};
+
+use ::uuid::Uuid;
+
+use crate::{auth::GameProfile, state::ServerState, types::BasaltError};
+
+mod bans;
+mod ops;
+pub mod whitelist;
+
+pub use bans::{ban_ip, ban_player, pardon_ip, pardon_player, Ban, IpBan, PlayerBan};
+pub use ops::{bypasses_player_limit, deop, op, op_level, Operator};
+pub use whitelist::WhitelistEntry;
+
+/// Entries kept in a JSON file in vanilla's format. Lookups only use the entries in memory, and the
+/// file is read again by `refresh` if it changed, so admins can edit it while the server is running.
+pub struct JsonList<T> {
+	path: PathBuf,
+	entries: Vec<T>,
+	/// When the file had last been modified as of the last time it was read
+	modified: Option<SystemTime>,
+}
+
+impl<T: DeserializeOwned + Serialize> JsonList<T> {
+	/// Reads a list, creating an empty file for it if there isn't one yet
+	pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+		let mut list = Self {
+			path: PathBuf::from(path),
+			entries: Vec::new(),
+			modified: None,
+		};
+		match fs::metadata(&list.path) {
+			Ok(_) => list.reload()?,
+			Err(e) if e.kind() == ErrorKind::NotFound => list.save()?,
+			Err(e) => return Err(Box::new(e)),
+		}
+		Ok(list)
+	}
+
+	pub fn entries(&self) -> &[T] {
+		&self.entries
+	}
+
+	/// Adds an entry and writes the list out
+	pub fn add(&mut self, entry: T) -> io::Result<()> {
+		self.refresh();
+		self.entries.push(entry);
+		self.save()
+	}
+
+	/// Removes the entries matching a predicate and writes the list out if any were, returning how
+	/// many there were
+	pub fn remove<F: FnMut(&T) -> bool>(&mut self, mut predicate: F) -> io::Result<usize> {
+		self.refresh();
+		let before = self.entries.len();
+		self.entries.retain(|entry| !predicate(entry));
+		let removed = before - self.entries.len();
+		if removed > 0 {
+			self.save()?;
+		}
+		Ok(removed)
+	}
+
+	/// Reads the file again, keeping the current entries if it can't be read
+	pub fn reload(&mut self) -> Result<(), Box<dyn Error>> {
+		let modified = fs::metadata(&self.path)?.modified().ok();
+		let contents = fs::read_to_string(&self.path)?;
+		self.entries = ::serde_json::from_str(&contents).map_err(|e| {
+			BasaltError::new(format!("Couldn't read {}: {}", self.path.display(), e))
+		})?;
+		self.modified = modified;
+		Ok(())
+	}
+
+	/// Reads the file again if it was changed since it was last read, returning whether it was
+	pub fn refresh(&mut self) -> bool {
+		let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+		if modified.is_none() || modified == self.modified {
+			return false;
+		}
+		match self.reload() {
+			Ok(()) => {
+				info!("Reloaded {}", self.path.display());
+				true
+			}
+			Err(e) => {
+				// Don't complain about the same broken file again until it changes
+				self.modified = modified;
+				warn!("{}", e);
+				false
+			}
+		}
+	}
+
+	pub fn save(&mut self) -> io::Result<()> {
+		let contents = ::serde_json::to_string_pretty(&self.entries)?;
+		fs::write(&self.path, contents)?;
+		self.modified = fs::metadata(&self.path)?.modified().ok();
+		Ok(())
+	}
+}
+
+/// The error logging in ends with when a player isn't allowed to join
+pub fn refused(profile: &GameProfile, reason: &str) -> io::Error {
+	io::Error::new(
+		ErrorKind::PermissionDenied,
+		BasaltError::new(format!("{} was refused: {}", profile.name, reason)),
+	)
+}
+
+/// Whether a name or UUID refers to the player, ignoring case the way vanilla does for names
+fn matches(profile: &GameProfile, name_or_uuid: &str) -> bool {
+	match Uuid::parse_str(name_or_uuid) {
+		Ok(uuid) => uuid == profile.uuid,
+		Err(_) => name_or_uuid.eq_ignore_ascii_case(&profile.name),
+	}
+}
+
+/// Decides whether a connection from an address may go on to log in, giving the message it is
+/// disconnected with if not
+pub fn check_address(state: &ServerState, address: IpAddr) -> Result<(), String> {
+	match bans::ip_ban(state, address) {
+		Some(ban) => Err(ban.message("Your IP address is banned from this server.")),
+		None => Ok(()),
+	}
+}
+
+/// Decides whether a player may join, giving the message they are kicked with if not
+pub fn check_login(state: &ServerState, profile: &GameProfile) -> Result<(), String> {
+	// Nobody's name or UUID can be trusted until players are authenticated, so nobody is let in while
+	// the config says they have to be
+	if state.config().authentication.required {
+		return Err(String::from(
+			"This server requires authentication, which isn't supported yet",
+		));
+	}
+	if let Some(ban) = bans::player_ban(state, profile) {
+		return Err(ban.message("You are banned from this server."));
+	}
+	if state.whitelist_enabled.load(Ordering::Relaxed) && !whitelist::allows(state, profile) {
+		return Err(state.config().whitelist.message.clone());
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::chrono::{Duration, Local};
+
+	/// A path in an empty temporary directory for a list to be kept at
+	fn path(name: &str) -> String {
+		let dir = ::std::env::temp_dir().join(format!("basalt-lists-{}", name));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		dir.join("list.json").to_string_lossy().into_owned()
+	}
+
+	fn profile(name: &str) -> GameProfile {
+		GameProfile::offline(String::from(name)).unwrap()
+	}
+
+	fn entry(name: &str) -> WhitelistEntry {
+		WhitelistEntry {
+			uuid: profile(name).uuid.to_string(),
+			name: String::from(name),
+		}
+	}
+
+	#[test]
+	fn missing_list_is_created_empty() {
+		let path = path("missing");
+		let list: JsonList<WhitelistEntry> = JsonList::load(&path).unwrap();
+		assert!(list.entries().is_empty());
+		let saved: Vec<WhitelistEntry> =
+			::serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+		assert!(saved.is_empty());
+	}
+
+	#[test]
+	fn changes_are_saved() {
+		let path = path("saved");
+		let mut list = JsonList::load(&path).unwrap();
+		list.add(entry("Alice")).unwrap();
+		list.add(entry("Bob")).unwrap();
+		assert_eq!(list.remove(|entry| entry.name == "Alice").unwrap(), 1);
+		assert_eq!(list.remove(|entry| entry.name == "Carol").unwrap(), 0);
+		let list: JsonList<WhitelistEntry> = JsonList::load(&path).unwrap();
+		let names: Vec<_> = list.entries().iter().map(|entry| &entry.name).collect();
+		assert_eq!(names, ["Bob"]);
+	}
+
+	#[test]
+	fn edited_file_is_read_again() {
+		let path = path("edited");
+		let mut list: JsonList<WhitelistEntry> = JsonList::load(&path).unwrap();
+		assert!(!list.refresh());
+		let contents = ::serde_json::to_string(&[entry("Alice")]).unwrap();
+		fs::write(&path, contents).unwrap();
+		assert!(list.refresh());
+		assert!(!list.refresh());
+		assert_eq!(list.entries().len(), 1);
+		// A broken file leaves the entries as they were
+		fs::write(&path, "[{").unwrap();
+		assert!(!list.refresh());
+		assert_eq!(list.entries().len(), 1);
+	}
+
+	#[test]
+	fn players_match_by_name_or_uuid() {
+		let alice = profile("Alice");
+		assert!(matches(&alice, "alice"));
+		assert!(matches(&alice, "ALICE"));
+		assert!(matches(&alice, &alice.uuid.to_string()));
+		assert!(matches(&alice, &alice.uuid.to_simple().to_string()));
+		assert!(!matches(&alice, "Bob"));
+		assert!(!matches(&alice, &profile("Bob").uuid.to_string()));
+	}
+
+	#[test]
+	fn bans_expire() {
+		let reason = || String::from("Griefing");
+		let forever = Ban::new(String::from("Server"), reason(), None);
+		assert_eq!(forever.expires, "forever");
+		assert!(!forever.is_expired());
+		let over = Ban::new(
+			String::from("Server"),
+			reason(),
+			Some(Local::now() - Duration::hours(1)),
+		);
+		assert!(over.is_expired());
+		let ongoing = Ban::new(
+			String::from("Server"),
+			reason(),
+			Some(Local::now() + Duration::hours(1)),
+		);
+		assert!(!ongoing.is_expired());
+	}
+
+	#[test]
+	fn ban_messages_give_the_reason_and_expiry() {
+		let forever = Ban::new(String::from("Server"), String::from("Griefing"), None);
+		assert_eq!(forever.message("Banned"), "Banned\nReason: Griefing");
+		let expires = Local::now() + Duration::days(1);
+		let ongoing = Ban::new(
+			String::from("Server"),
+			String::from("Griefing"),
+			Some(expires),
+		);
+		assert_eq!(
+			ongoing.message("Banned"),
+			format!(
+				"Banned\nReason: Griefing\nYour ban will be removed on {}",
+				expires.format("%Y-%m-%d %H:%M:%S")
+			)
+		);
+	}
+}
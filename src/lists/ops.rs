@@ -0,0 +1,59 @@
+use ::serde::{Deserialize, Serialize};
+use ::std::io;
+
+use crate::{auth::GameProfile, state::ServerState};
+
+use super::matches;
+
+/// Highest operator level, which can use every command
+const MAX_OP_LEVEL: u8 = 4;
+
+/// A player listed in `ops.json`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Operator {
+	pub uuid: String,
+	pub name: String,
+	/// From 1, which lets the player build in spawn protection, to 4, which lets them use every command
+	pub level: u8,
+	/// Whether the player can join even if the server is full
+	pub bypasses_player_limit: bool,
+}
+
+fn find(state: &ServerState, profile: &GameProfile) -> Option<Operator> {
+	let ops = state.ops.lock().unwrap();
+	ops.entries()
+		.iter()
+		.find(|op| matches(profile, &op.uuid) || matches(profile, &op.name))
+		.cloned()
+}
+
+/// The player's operator level, which is 0 if they aren't an operator
+pub fn op_level(state: &ServerState, profile: &GameProfile) -> u8 {
+	find(state, profile).map_or(0, |op| op.level.min(MAX_OP_LEVEL))
+}
+
+pub fn bypasses_player_limit(state: &ServerState, profile: &GameProfile) -> bool {
+	find(state, profile).map_or(false, |op| op.bypasses_player_limit)
+}
+
+/// Makes a player an operator with the highest level, returning false if they already were one
+pub fn op(state: &ServerState, profile: &GameProfile) -> io::Result<bool> {
+	if find(state, profile).is_some() {
+		return Ok(false);
+	}
+	state.ops.lock().unwrap().add(Operator {
+		uuid: profile.uuid.to_string(),
+		name: profile.name.clone(),
+		level: MAX_OP_LEVEL,
+		bypasses_player_limit: false,
+	})?;
+	Ok(true)
+}
+
+/// Takes a player's operator level away, returning false if they weren't an operator
+pub fn deop(state: &ServerState, profile: &GameProfile) -> io::Result<bool> {
+	let mut ops = state.ops.lock().unwrap();
+	let removed = ops.remove(|op| matches(profile, &op.uuid) || matches(profile, &op.name))?;
+	Ok(removed > 0)
+}
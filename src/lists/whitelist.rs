@@ -0,0 +1,59 @@
+use ::serde::{Deserialize, Serialize};
+use ::std::io;
+
+use crate::{auth::GameProfile, state::ServerState};
+
+use super::matches;
+
+/// A player listed in `whitelist.json`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WhitelistEntry {
+	pub uuid: String,
+	pub name: String,
+}
+
+/// Whether a player is on the whitelist in the config or in `whitelist.json`
+pub fn allows(state: &ServerState, profile: &GameProfile) -> bool {
+	if state
+		.config()
+		.whitelist
+		.list
+		.iter()
+		.any(|entry| matches(profile, entry))
+	{
+		return true;
+	}
+	let whitelist = state.whitelist.lock().unwrap();
+	whitelist
+		.entries()
+		.iter()
+		.any(|entry| matches(profile, &entry.uuid) || matches(profile, &entry.name))
+}
+
+/// Adds a player to `whitelist.json`, returning false if they were already whitelisted
+pub fn add(state: &ServerState, profile: &GameProfile) -> io::Result<bool> {
+	if allows(state, profile) {
+		return Ok(false);
+	}
+	state.whitelist.lock().unwrap().add(WhitelistEntry {
+		uuid: profile.uuid.to_string(),
+		name: profile.name.clone(),
+	})?;
+	Ok(true)
+}
+
+/// Removes a player from `whitelist.json`, returning false if they weren't in it
+pub fn remove(state: &ServerState, profile: &GameProfile) -> io::Result<bool> {
+	let mut whitelist = state.whitelist.lock().unwrap();
+	let removed =
+		whitelist.remove(|entry| matches(profile, &entry.uuid) || matches(profile, &entry.name))?;
+	Ok(removed > 0)
+}
+
+/// Everyone on the whitelist, by name unless the config lists them by UUID
+pub fn names(state: &ServerState) -> Vec<String> {
+	let mut names = state.config().whitelist.list.clone();
+	let whitelist = state.whitelist.lock().unwrap();
+	names.extend(whitelist.entries().iter().map(|entry| entry.name.clone()));
+	names
+}
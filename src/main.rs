@@ -1,23 +1,53 @@
 use ::chrono::Local;
-use ::fern::Dispatch;
-use ::log::LevelFilter;
-use ::std::io;
+use ::fern::{Dispatch, Output};
+use ::log::{error, info, warn, LevelFilter};
+use ::std::{fs, io, process, sync::Arc, time::Duration};
+#[cfg(unix)]
+use ::tokio::signal::unix::{signal, Signal, SignalKind};
+use ::tokio::{
+	task::{self, JoinHandle},
+	time,
+};
 
 mod auth;
 mod classic;
+mod command;
 mod config;
 mod connection;
+mod console;
+mod entity;
+mod gzip;
+mod inventory;
+mod item;
+mod lists;
 mod modern;
+mod nbt;
+mod permissions;
+mod player;
+mod recipe;
 mod server;
+mod state;
 mod status;
+mod tick;
 mod types;
 mod util;
+mod world;
 
-use config::Config;
+use config::{Config, CONFIG_PATH};
+use console::Console;
 use server::Server;
+use state::ServerState;
+
+/// How long players are given to save and leave when the server stops
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often the config file is checked for changes while it is being watched
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
 
 #[::tokio::main]
 async fn main() {
+	let console = Console::new();
+	let _terminal = console.restore_on_exit();
+	let output = console.clone();
 	Dispatch::new()
 		.format(|out, msg, record| {
 			out.finish(format_args!(
@@ -29,11 +59,167 @@ async fn main() {
 			))
 		})
 		.level(LevelFilter::Debug)
-		.chain(io::stdout())
+		.chain(Output::call(move |record| output.log(record)))
 		.apply()
 		.unwrap();
-	let config = Config::read("basalt.toml").await.unwrap();
-	let mut server = Server::new(&config).await.unwrap();
-	// ...
-	server.listen(&config).await.unwrap();
+	let config = Config::read(CONFIG_PATH).unwrap();
+	let (tick, receiver) = tick::channel();
+	let state = Arc::new(ServerState::new(config, tick).unwrap());
+	let ticker = tick::spawn(state.clone(), receiver);
+	let mut server = Server::new(state.clone()).await.unwrap();
+	console.spawn(state.clone());
+	::tokio::spawn(handle_signals(state.clone(), console.clone()));
+	#[cfg(unix)]
+	::tokio::spawn(handle_hangups(state.clone()));
+	::tokio::spawn(watch_config(state.clone()));
+	let mut status = 0;
+	::tokio::select! {
+		result = server.listen() => {
+			if let Err(e) = result {
+				error!("Couldn't accept connections: {}", e);
+				status = 1;
+			}
+			state.stop();
+		}
+		_ = state.stopped() => {}
+	}
+	// Closing the listener turns away anyone else who tries to connect
+	drop(server);
+	if !stop(&state, ticker).await {
+		status = 1;
+	}
+	console.restore();
+	process::exit(status);
+}
+
+/// Stops the server when the process is interrupted or terminated, or exits straight away if that
+/// happens again while it is stopping
+async fn handle_signals(state: Arc<ServerState>, console: Console) {
+	if let Err(e) = stop_on_signals(&state, &console).await {
+		warn!("Couldn't listen for signals: {}", e);
+	}
+}
+
+async fn stop_on_signals(state: &ServerState, console: &Console) -> io::Result<()> {
+	let mut signals = Signals::new()?;
+	let signal = signals.recv().await?;
+	info!("Received {}, stopping the server", signal);
+	state.stop();
+	let signal = signals.recv().await?;
+	warn!(
+		"Received {} again, exiting without waiting for the server to stop",
+		signal
+	);
+	console.restore();
+	process::exit(1)
+}
+
+/// Reloads the config whenever the process gets SIGHUP
+#[cfg(unix)]
+async fn handle_hangups(state: Arc<ServerState>) {
+	let mut hangup = match signal(SignalKind::hangup()) {
+		Ok(hangup) => hangup,
+		Err(e) => {
+			warn!("Couldn't listen for SIGHUP: {}", e);
+			return;
+		}
+	};
+	while hangup.recv().await.is_some() {
+		info!("Received SIGHUP, reloading the config");
+		reload(&state);
+	}
+}
+
+/// Reloads the config when its file changes, for as long as the config asks for that
+async fn watch_config(state: Arc<ServerState>) {
+	let modified = || fs::metadata(CONFIG_PATH).and_then(|m| m.modified()).ok();
+	let mut last = modified();
+	let mut interval = time::interval(WATCH_INTERVAL);
+	loop {
+		interval.tick().await;
+		let current = modified();
+		if current == last {
+			continue;
+		}
+		last = current;
+		if state.config().reload.watch {
+			info!("{} changed, reloading it", CONFIG_PATH);
+			reload(&state);
+		}
+	}
+}
+
+fn reload(state: &ServerState) {
+	if let Err(e) = state.reload_config() {
+		error!("Couldn't reload {}: {}", CONFIG_PATH, e);
+	}
+}
+
+/// Listens for SIGINT and SIGTERM
+#[cfg(unix)]
+struct Signals {
+	interrupt: Signal,
+	terminate: Signal,
+}
+
+#[cfg(unix)]
+impl Signals {
+	fn new() -> io::Result<Self> {
+		Ok(Self {
+			interrupt: signal(SignalKind::interrupt())?,
+			terminate: signal(SignalKind::terminate())?,
+		})
+	}
+
+	/// Waits for the next signal, returning which one it was
+	async fn recv(&mut self) -> io::Result<&'static str> {
+		Ok(::tokio::select! {
+			_ = self.interrupt.recv() => "SIGINT",
+			_ = self.terminate.recv() => "SIGTERM",
+		})
+	}
+}
+
+/// Listens for Ctrl+C, which is the only signal other platforms have
+#[cfg(not(unix))]
+struct Signals;
+
+#[cfg(not(unix))]
+impl Signals {
+	fn new() -> io::Result<Self> {
+		Ok(Self)
+	}
+
+	async fn recv(&mut self) -> io::Result<&'static str> {
+		::tokio::signal::ctrl_c().await?;
+		Ok("Ctrl+C")
+	}
+}
+
+/// Kicks everyone and waits for their data to be saved, then finishes the last tick and saves every
+/// world. Returns whether everything was saved.
+async fn stop(state: &ServerState, ticker: JoinHandle<()>) -> bool {
+	for player in state.players.read().unwrap().values() {
+		player.disconnect(state.config().minecraft.stop_message.clone());
+	}
+	let saved = time::timeout(STOP_TIMEOUT, async {
+		while state.online() > 0 {
+			time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.is_ok();
+	if !saved {
+		warn!(
+			"{} players didn't leave in time, so their data may not have been saved",
+			state.online()
+		);
+	}
+	state.tick.stop();
+	let ticked = ticker.await.is_ok();
+	// Nothing can change the worlds once the tick loop and players are gone
+	info!("Saving worlds");
+	let worlds = task::block_in_place(|| state.save_worlds()).is_empty();
+	info!("Server stopped");
+	saved && ticked && worlds
 }
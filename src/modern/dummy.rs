@@ -1,10 +1,27 @@
 use ::async_trait::async_trait;
-use ::std::io::Result;
-use ::tokio::net::TcpStream;
+use ::std::{
+	collections::HashSet,
+	io::{Error, ErrorKind, Result},
+	sync::Arc,
+};
+use ::tokio::{io::AsyncWriteExt, net::TcpStream};
+
+use ::uuid::Uuid;
 
 use crate::{
-	config::Config,
-	modern::{ModernVersion, ProtocolHandler},
+	auth::GameProfile,
+	command::CommandNode,
+	entity::EntityKind,
+	inventory::ContainerKind,
+	modern::{
+		types::{chat::ChatComponent, ModernEncodable, VarInt},
+		ModernVersion, ProtocolHandler,
+	},
+	player::{Gamemode, Position, Rotation, Session},
+	state::ServerState,
+	types::{BasaltError, DigStatus, Events, MessagePosition, Slot},
+	util,
+	world::{chunk::ChunkPos, BlockPos, Difficulty, Weather},
 };
 
 pub struct DummyHandler {
@@ -13,18 +30,248 @@ pub struct DummyHandler {
 
 #[async_trait]
 impl ModernVersion for DummyHandler {
-	async fn new(conn: TcpStream, _config: &Config) -> Box<Self> {
+	async fn new(conn: TcpStream, _state: Arc<ServerState>) -> Box<Self> {
 		Box::new(Self { conn })
 	}
 }
 
+/// Only ever used to tell the client its version isn't supported, so everything else fails
 #[async_trait]
 impl ProtocolHandler for DummyHandler {
+	async fn acknowledge_dig(
+		&mut self,
+		_position: BlockPos,
+		_state: u16,
+		_status: DigStatus,
+		_successful: bool,
+	) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn add_to_player_list(
+		&mut self,
+		_profile: &GameProfile,
+		_gamemode: Gamemode,
+		_ping: i32,
+	) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn block_break_animation(
+		&mut self,
+		_entity_id: i32,
+		_position: BlockPos,
+		_stage: i8,
+	) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn block_change(&mut self, _position: BlockPos, _state: u16) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn block_changes(
+		&mut self,
+		_blocks: &[(BlockPos, u16)],
+		_light: &HashSet<ChunkPos>,
+	) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn close_window(&mut self, _window: u8) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn death_screen(&mut self, _entity_id: i32, _message: &ChatComponent) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn declare_commands(&mut self, _root: &CommandNode) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn despawn_entities(&mut self, _entity_ids: &[i32]) -> Result<()> {
+		Err(unsupported())
+	}
+
 	async fn disconnect(&mut self, reason: String) -> Result<()> {
-		todo!()
+		let mut buffer = Vec::new();
+		VarInt::from(0x00).write(&mut buffer)?;
+		ChatComponent::text(reason).to_json().write(&mut buffer)?;
+		self.conn.write_all(&util::prepend_length(buffer)).await
+	}
+
+	async fn entity_teleport(
+		&mut self,
+		_entity_id: i32,
+		_position: Position,
+		_rotation: Rotation,
+		_on_ground: bool,
+	) -> Result<()> {
+		Err(unsupported())
 	}
 
 	fn is_dummy(&self) -> bool {
 		true
 	}
+
+	async fn join(&mut self, _entity_id: i32, _session: &Session) -> Result<Events> {
+		Err(unsupported())
+	}
+
+	async fn keep_alive(&mut self, _id: i64) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn login(&mut self) -> Result<GameProfile> {
+		Err(unsupported())
+	}
+
+	async fn move_entity(
+		&mut self,
+		_entity_id: i32,
+		_from: Position,
+		_to: Position,
+		_rotation: Rotation,
+		_on_ground: bool,
+	) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn open_window(
+		&mut self,
+		_window: u8,
+		_kind: ContainerKind,
+		_title: &ChatComponent,
+	) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn remove_from_player_list(&mut self, _uuid: Uuid) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn respawn(&mut self, _session: &Session) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn rotate_entity(
+		&mut self,
+		_entity_id: i32,
+		_rotation: Rotation,
+		_on_ground: bool,
+	) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn send_message(
+		&mut self,
+		_message: &ChatComponent,
+		_position: MessagePosition,
+		_sender: Option<Uuid>,
+	) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn set_difficulty(&mut self, _difficulty: Difficulty) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn set_gamemode(&mut self, _gamemode: Gamemode) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn set_inventory(&mut self, _window: u8, _items: &[Option<Slot>]) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn set_op_level(&mut self, _entity_id: i32, _level: u8) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn set_slot(&mut self, _window: u8, _slot: i16, _item: Option<&Slot>) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn set_weather(&mut self, _weather: Weather) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn spawn_entity(
+		&mut self,
+		_entity_id: i32,
+		_uuid: Uuid,
+		_kind: EntityKind,
+		_position: Position,
+		_rotation: Rotation,
+	) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn spawn_player(
+		&mut self,
+		_entity_id: i32,
+		_profile: &GameProfile,
+		_position: Position,
+		_rotation: Rotation,
+	) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn tab_complete(
+		&mut self,
+		_id: i32,
+		_start: usize,
+		_length: usize,
+		_matches: &[String],
+	) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn teleport(&mut self, _position: Position, _rotation: Rotation) -> Result<Option<i32>> {
+		Err(unsupported())
+	}
+
+	async fn title(
+		&mut self,
+		_title: &ChatComponent,
+		_subtitle: Option<&ChatComponent>,
+		_fade_in: i32,
+		_stay: i32,
+		_fade_out: i32,
+	) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn update_health(&mut self, _session: &Session) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn update_latency(&mut self, _uuid: Uuid, _ping: i32) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn update_time(&mut self, _age: i64, _time: i64) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn update_view(&mut self, _position: Position) -> Result<()> {
+		Err(unsupported())
+	}
+
+	async fn window_confirmation(
+		&mut self,
+		_window: u8,
+		_action: i16,
+		_accepted: bool,
+	) -> Result<()> {
+		Err(unsupported())
+	}
+}
+
+fn unsupported() -> Error {
+	Error::new(
+		ErrorKind::Other,
+		BasaltError::new(String::from("Unsupported protocol version")),
+	)
 }
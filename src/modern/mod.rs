@@ -1,7 +1,8 @@
 use ::async_trait::async_trait;
+use ::std::sync::Arc;
 use ::tokio::net::TcpStream;
 
-use crate::{config::Config, types::ProtocolHandler};
+use crate::{state::ServerState, types::ProtocolHandler};
 
 mod dummy;
 pub mod types;
@@ -13,13 +14,17 @@ pub use v754::V754;
 
 #[async_trait]
 pub trait ModernVersion {
-	async fn new(conn: TcpStream, config: &Config) -> Box<Self>;
+	async fn new(conn: TcpStream, state: Arc<ServerState>) -> Box<Self>;
 }
 
-pub async fn handler(conn: TcpStream, config: &Config, version: i32) -> Box<dyn ProtocolHandler> {
+pub async fn handler(
+	conn: TcpStream,
+	state: Arc<ServerState>,
+	version: i32,
+) -> Box<dyn ProtocolHandler> {
 	match version {
-		754 => V754::new(conn, config).await,
-		_ => DummyHandler::new(conn, config).await,
+		754 => V754::new(conn, state).await,
+		_ => DummyHandler::new(conn, state).await,
 	}
 }
 
@@ -6,7 +6,11 @@ use ::std::{
 use ::tokio::io::{AsyncReadExt, AsyncWriteExt};
 use ::uuid::Uuid;
 
-use crate::types::BasaltError;
+use crate::{
+	nbt::Nbt,
+	types::{BasaltError, Slot},
+	world::BlockPos,
+};
 
 pub mod chat;
 
@@ -22,6 +26,41 @@ pub trait ModernEncodable {
 	fn write<W: Write>(&self, stream: &mut W) -> Result<()>;
 }
 
+/// Packs a block position into a long as x (26 bits), z (26 bits) and y (12 bits)
+#[async_trait]
+impl ModernEncodable for BlockPos {
+	async fn async_read<R: AsyncReadExt + Send + Unpin>(stream: &mut R) -> Result<Self> {
+		Ok(unpack_position(stream.read_i64().await?))
+	}
+
+	async fn async_write<W: AsyncWriteExt + Send + Unpin>(&self, stream: &mut W) -> Result<()> {
+		stream.write_i64(pack_position(self)).await?;
+		Ok(())
+	}
+
+	fn read<R: Read>(stream: &mut R) -> Result<Self> {
+		Ok(unpack_position(i64::read(stream)?))
+	}
+
+	fn write<W: Write>(&self, stream: &mut W) -> Result<()> {
+		pack_position(self).write(stream)
+	}
+}
+
+fn pack_position(position: &BlockPos) -> i64 {
+	((position.x as i64 & 0x3FFFFFF) << 38)
+		| ((position.z as i64 & 0x3FFFFFF) << 12)
+		| (position.y as i64 & 0xFFF)
+}
+
+fn unpack_position(value: i64) -> BlockPos {
+	BlockPos::new(
+		(value >> 38) as i32,
+		(value << 52 >> 52) as i32,
+		(value << 26 >> 38) as i32,
+	)
+}
+
 #[async_trait]
 impl ModernEncodable for bool {
 	async fn async_read<R: AsyncReadExt + Send + Unpin>(stream: &mut R) -> Result<Self> {
@@ -321,6 +360,63 @@ impl ModernEncodable for Uuid {
 	}
 }
 
+/// An inventory slot, which is either empty or holds a stack of items
+#[async_trait]
+impl ModernEncodable for Option<Slot> {
+	async fn async_read<R: AsyncReadExt + Send + Unpin>(stream: &mut R) -> Result<Self> {
+		if !bool::async_read(stream).await? {
+			return Ok(None);
+		}
+		let item = VarInt::async_read(stream).await?.raw;
+		let count = i8::async_read(stream).await?;
+		let tag = u8::async_read(stream).await?;
+		let nbt = if tag == 0 {
+			None
+		} else {
+			Some(Nbt::async_read_after_id(tag, stream).await?)
+		};
+		Ok(Some(Slot { item, count, nbt }))
+	}
+
+	async fn async_write<W: AsyncWriteExt + Send + Unpin>(&self, stream: &mut W) -> Result<()> {
+		let mut buffer = Vec::new();
+		self.write(&mut buffer)?;
+		stream.write_all(&buffer).await?;
+		Ok(())
+	}
+
+	fn read<R: Read>(stream: &mut R) -> Result<Self> {
+		if !bool::read(stream)? {
+			return Ok(None);
+		}
+		let item = VarInt::read(stream)?.raw;
+		let count = i8::read(stream)?;
+		let tag = u8::read(stream)?;
+		let nbt = if tag == 0 {
+			None
+		} else {
+			Some(Nbt::read_after_id(tag, stream)?)
+		};
+		Ok(Some(Slot { item, count, nbt }))
+	}
+
+	fn write<W: Write>(&self, stream: &mut W) -> Result<()> {
+		match self {
+			Some(slot) => {
+				true.write(stream)?;
+				VarInt::from(slot.item).write(stream)?;
+				slot.count.write(stream)?;
+				match &slot.nbt {
+					Some(nbt) => nbt.write(stream)?,
+					None => 0u8.write(stream)?,
+				}
+			}
+			None => false.write(stream)?,
+		}
+		Ok(())
+	}
+}
+
 pub struct VarInt {
 	pub raw: i32,
 }
@@ -362,7 +458,7 @@ impl ModernEncodable for VarInt {
 	}
 
 	async fn async_write<W: AsyncWriteExt + Send + Unpin>(&self, stream: &mut W) -> Result<()> {
-		let mut value = self.raw;
+		let mut value = self.raw as u32;
 		while {
 			let mut temp = (value & 0x7F) as u8;
 			value >>= 7;
@@ -398,7 +494,7 @@ impl ModernEncodable for VarInt {
 	}
 
 	fn write<W: Write>(&self, stream: &mut W) -> Result<()> {
-		let mut value = self.raw;
+		let mut value = self.raw as u32;
 		while {
 			let mut temp = (value & 0x7F) as u8;
 			value >>= 7;
@@ -453,7 +549,7 @@ impl ModernEncodable for VarLong {
 	}
 
 	async fn async_write<W: AsyncWriteExt + Send + Unpin>(&self, stream: &mut W) -> Result<()> {
-		let mut value = self.raw;
+		let mut value = self.raw as u64;
 		while {
 			let mut temp = (value & 0x7F) as u8;
 			value >>= 7;
@@ -489,7 +585,7 @@ impl ModernEncodable for VarLong {
 	}
 
 	fn write<W: Write>(&self, stream: &mut W) -> Result<()> {
-		let mut value = self.raw;
+		let mut value = self.raw as u64;
 		while {
 			let mut temp = (value & 0x7F) as u8;
 			value >>= 7;
@@ -1,9 +1,11 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::convert::TryFrom;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 enum ScoreValue {}
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 enum ChatComponentType {
 	/// Stores a string of text
@@ -11,6 +13,7 @@ enum ChatComponentType {
 	/// Gets translated to the local client's language (translation key)
 	Translation {
 		translate: String,
+		#[serde(skip_serializing_if = "Option::is_none")]
 		with: Option<Vec<ChatComponent>>,
 	},
 	/// Gets translated into the client's local keybind for an action (translated key)
@@ -22,12 +25,12 @@ enum ChatComponentType {
 		value: Option<ScoreValue>,
 	},
 	/// Displays the results of an entity selector and should **not** be sent to clients
-	Selector, // TODO: Figure out what type populates this field.
+	Selector { selector: String },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "action", rename_all = "snake_case")]
-enum ClickEvent {
+pub enum ClickEvent {
 	/// Opens the given URL in the client's browser
 	OpenUrl { value: String },
 	/// Runs given command *or* makes the user say the given text
@@ -36,9 +39,11 @@ enum ClickEvent {
 	SuggestCommand { value: String },
 	/// Changes the page in a written book to the given page
 	ChangePage { value: u64 },
+	/// Copies the given text to the client's clipboard
+	CopyToClipboard { value: String },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 enum HoverEventShowText {
 	/// Represents the string varient of the value key
 	String(String),
@@ -46,7 +51,7 @@ enum HoverEventShowText {
 	Component(Box<ChatComponent>),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "action", rename_all = "snake_case")]
 enum HoverEvent {
 	/// Shows text on hover
@@ -57,7 +62,7 @@ enum HoverEvent {
 	ShowEntity { value: String },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct ComponentModifiers {
 	/// Makes text bold
@@ -92,10 +97,214 @@ struct ComponentModifiers {
 	extra: Option<Vec<ChatComponent>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ChatComponent {
+/// A chat component, which can be read from JSON as plain text in quotes or a list of components
+/// that are shown one after the other as well as an object
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Value")]
+pub struct ChatComponent {
 	#[serde(flatten)]
 	component: ChatComponentType,
 	#[serde(flatten)]
 	modifiers: ComponentModifiers,
 }
+
+/// A chat component written as a JSON object
+#[derive(Deserialize)]
+struct ComponentObject {
+	#[serde(flatten)]
+	component: ChatComponentType,
+	#[serde(flatten)]
+	modifiers: ComponentModifiers,
+}
+
+impl TryFrom<Value> for ChatComponent {
+	type Error = String;
+
+	fn try_from(value: Value) -> Result<Self, String> {
+		match value {
+			Value::String(text) => Ok(Self::text(text)),
+			Value::Array(values) => {
+				let mut components = values.into_iter().map(Self::try_from);
+				let first = components
+					.next()
+					.unwrap_or_else(|| Err(String::from("The list of components is empty")))?;
+				components.try_fold(first, |component, extra| Ok(component.append(extra?)))
+			}
+			value => {
+				let object: ComponentObject =
+					::serde_json::from_value(value).map_err(|e| e.to_string())?;
+				Ok(Self {
+					component: object.component,
+					modifiers: object.modifiers,
+				})
+			}
+		}
+	}
+}
+
+impl ChatComponent {
+	/// Creates a component containing plain text
+	pub fn text<S: Into<String>>(text: S) -> Self {
+		Self {
+			component: ChatComponentType::String { text: text.into() },
+			modifiers: ComponentModifiers::default(),
+		}
+	}
+
+	/// Creates a component the client translates into its own language
+	pub fn translate<S: Into<String>>(key: S, with: Vec<ChatComponent>) -> Self {
+		Self {
+			component: ChatComponentType::Translation {
+				translate: key.into(),
+				with: if with.is_empty() { None } else { Some(with) },
+			},
+			modifiers: ComponentModifiers::default(),
+		}
+	}
+
+	/// Replaces every selector in the component with what `resolve` makes of it, keeping its style
+	pub fn resolve_selectors<F: Fn(&str) -> ChatComponent>(mut self, resolve: &F) -> Self {
+		if let Some(extra) = self.modifiers.extra.take() {
+			self.modifiers.extra = Some(
+				extra
+					.into_iter()
+					.map(|component| component.resolve_selectors(resolve))
+					.collect(),
+			);
+		}
+		match &mut self.component {
+			ChatComponentType::Selector { selector } => {
+				let resolved = resolve(selector);
+				self.component = ChatComponentType::String {
+					text: String::new(),
+				};
+				self.modifiers
+					.extra
+					.get_or_insert_with(Vec::new)
+					.insert(0, resolved);
+			}
+			ChatComponentType::Translation {
+				with: Some(with), ..
+			} => {
+				*with = with
+					.drain(..)
+					.map(|argument| argument.resolve_selectors(resolve))
+					.collect();
+			}
+			_ => {}
+		}
+		self
+	}
+
+	/// Appends a sibling component
+	pub fn append(mut self, extra: ChatComponent) -> Self {
+		self.modifiers
+			.extra
+			.get_or_insert_with(Vec::new)
+			.push(extra);
+		self
+	}
+
+	pub fn click(mut self, event: ClickEvent) -> Self {
+		self.modifiers.click_event = Some(event);
+		self
+	}
+
+	pub fn color<S: Into<String>>(mut self, color: S) -> Self {
+		self.modifiers.color = Some(color.into());
+		self
+	}
+
+	pub fn italic(mut self) -> Self {
+		self.modifiers.italic = Some(true);
+		self
+	}
+
+	/// Reads a component written as JSON
+	pub fn from_json(json: &str) -> Result<Self, String> {
+		::serde_json::from_str(json).map_err(|e| e.to_string())
+	}
+
+	/// Serializes the component for sending to modern clients
+	pub fn to_json(&self) -> String {
+		::serde_json::to_string(self).unwrap()
+	}
+
+	/// Flattens the component into plain text for clients that don't understand components, leaving
+	/// translation keys untranslated
+	pub fn to_plain(&self) -> String {
+		let mut result = match &self.component {
+			ChatComponentType::String { text } => text.clone(),
+			ChatComponentType::Translation { translate, with } => {
+				let mut result = translate.clone();
+				for argument in with.iter().flatten() {
+					result.push(' ');
+					result.push_str(&argument.to_plain());
+				}
+				result
+			}
+			ChatComponentType::Keybind { keybind } => keybind.clone(),
+			ChatComponentType::Score { name, .. } => name.clone(),
+			ChatComponentType::Selector { selector } => selector.clone(),
+		};
+		for extra in self.modifiers.extra.iter().flatten() {
+			result.push_str(&extra.to_plain());
+		}
+		result
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reads_components_from_json() {
+		assert_eq!(
+			ChatComponent::from_json(r#""hello""#),
+			Ok(ChatComponent::text("hello"))
+		);
+		assert_eq!(
+			ChatComponent::from_json(r#"{"text": "hello", "color": "red"}"#),
+			Ok(ChatComponent::text("hello").color("red"))
+		);
+		assert_eq!(
+			ChatComponent::from_json(r#"["a", {"text": "b", "italic": true}]"#),
+			Ok(ChatComponent::text("a").append(ChatComponent::text("b").italic()))
+		);
+		assert!(ChatComponent::from_json("[]").is_err());
+		assert!(ChatComponent::from_json("{").is_err());
+	}
+
+	#[test]
+	fn selectors_are_replaced_everywhere() {
+		let component = ChatComponent::from_json(
+			r#"{
+				"translate": "chat.type.announcement",
+				"with": [{"selector": "@s"}, "hi"],
+				"extra": [{"selector": "@a", "color": "red"}]
+			}"#,
+		)
+		.unwrap();
+		let resolved = component.resolve_selectors(&|selector| {
+			ChatComponent::text(if selector == "@s" {
+				"Alice"
+			} else {
+				"Alice, Bob"
+			})
+		});
+		let name = |text| ChatComponent::text("").append(ChatComponent::text(text));
+		assert_eq!(
+			resolved,
+			ChatComponent::translate(
+				"chat.type.announcement",
+				vec![name("Alice"), ChatComponent::text("hi")]
+			)
+			.append(name("Alice, Bob").color("red"))
+		);
+		assert_eq!(
+			resolved.to_plain(),
+			"chat.type.announcement Alice hiAlice, Bob"
+		);
+	}
+}
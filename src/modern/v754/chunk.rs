@@ -0,0 +1,132 @@
+use ::std::io::{Result, Write};
+
+use crate::{
+	compound,
+	modern::types::{ModernEncodable, VarInt},
+	nbt::{Nbt, Tag},
+	world::{
+		chunk::{Chunk, ChunkSection, SECTION_VOLUME},
+		light::NIBBLE_ARRAY_SIZE,
+	},
+};
+
+/// Bits per entry of the global block state palette in 1.16.5
+const GLOBAL_PALETTE_BITS: usize = 15;
+
+pub fn encode_chunk(chunk: &Chunk) -> Result<Vec<u8>> {
+	let mut buffer = Vec::new();
+	VarInt::from(0x20).write(&mut buffer)?;
+	chunk.pos.x.write(&mut buffer)?;
+	chunk.pos.z.write(&mut buffer)?;
+	true.write(&mut buffer)?;
+	let mut mask = 0;
+	let mut data = Vec::new();
+	for (y, section) in chunk.sections() {
+		mask |= 1 << y;
+		write_section(section, &mut data)?;
+	}
+	VarInt::from(mask).write(&mut buffer)?;
+	let mut heights = Vec::with_capacity(256);
+	for z in 0..16 {
+		for x in 0..16 {
+			heights.push(chunk.height(x, z) as u64);
+		}
+	}
+	Nbt::new(compound! {
+		"MOTION_BLOCKING" => Tag::LongArray(pack(&heights, 9)),
+	})
+	.write(&mut buffer)?;
+	VarInt::from(chunk.biomes().len() as i32).write(&mut buffer)?;
+	for biome in chunk.biomes() {
+		VarInt::from(*biome).write(&mut buffer)?;
+	}
+	VarInt::from(data.len() as i32).write(&mut buffer)?;
+	buffer.extend_from_slice(&data);
+	// Block entities
+	VarInt::from(0).write(&mut buffer)?;
+	Ok(buffer)
+}
+
+pub fn encode_light(chunk: &Chunk) -> Result<Vec<u8>> {
+	let mut buffer = Vec::new();
+	VarInt::from(0x23).write(&mut buffer)?;
+	VarInt::from(chunk.pos.x).write(&mut buffer)?;
+	VarInt::from(chunk.pos.z).write(&mut buffer)?;
+	true.write(&mut buffer)?;
+	let mut masks = Vec::new();
+	for arrays in &[&chunk.light.sky, &chunk.light.block] {
+		let mut mask = 0;
+		let mut empty = 0;
+		for (i, array) in arrays.iter().enumerate() {
+			if array.is_empty() {
+				empty |= 1 << i;
+			} else {
+				mask |= 1 << i;
+			}
+		}
+		masks.push((mask, empty));
+	}
+	VarInt::from(masks[0].0).write(&mut buffer)?;
+	VarInt::from(masks[1].0).write(&mut buffer)?;
+	VarInt::from(masks[0].1).write(&mut buffer)?;
+	VarInt::from(masks[1].1).write(&mut buffer)?;
+	for arrays in &[&chunk.light.sky, &chunk.light.block] {
+		for array in arrays.iter().filter(|a| !a.is_empty()) {
+			VarInt::from(NIBBLE_ARRAY_SIZE as i32).write(&mut buffer)?;
+			buffer.extend_from_slice(array.as_bytes());
+		}
+	}
+	Ok(buffer)
+}
+
+/// Packs values into longs without letting any value span two longs, as 1.16 expects
+fn pack(values: &[u64], bits: usize) -> Vec<i64> {
+	let per_long = 64 / bits;
+	let mask = (1u64 << bits) - 1;
+	let mut result = vec![0i64; (values.len() + per_long - 1) / per_long];
+	for (i, value) in values.iter().enumerate() {
+		let shift = (i % per_long) * bits;
+		result[i / per_long] |= ((value & mask) << shift) as i64;
+	}
+	result
+}
+
+fn write_section<W: Write>(section: &ChunkSection, stream: &mut W) -> Result<()> {
+	let mut palette: Vec<u16> = Vec::new();
+	for state in section.blocks() {
+		if !palette.contains(state) {
+			palette.push(*state);
+			if palette.len() > 256 {
+				break;
+			}
+		}
+	}
+	let mut bits = 4;
+	while (1 << bits) < palette.len() {
+		bits += 1;
+	}
+	section.block_count().write(stream)?;
+	let values: Vec<u64> = if bits > 8 {
+		bits = GLOBAL_PALETTE_BITS;
+		(bits as u8).write(stream)?;
+		section.blocks().iter().map(|s| *s as u64).collect()
+	} else {
+		(bits as u8).write(stream)?;
+		VarInt::from(palette.len() as i32).write(stream)?;
+		for state in &palette {
+			VarInt::from(*state as i32).write(stream)?;
+		}
+		section
+			.blocks()
+			.iter()
+			.map(|s| palette.iter().position(|p| p == s).unwrap() as u64)
+			.collect()
+	};
+	debug_assert_eq!(values.len(), SECTION_VOLUME);
+	let longs = pack(&values, bits);
+	VarInt::from(longs.len() as i32).write(stream)?;
+	for long in longs {
+		long.write(stream)?;
+	}
+	Ok(())
+}
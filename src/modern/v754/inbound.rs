@@ -0,0 +1,203 @@
+use ::log::debug;
+use ::std::io::{Cursor, Error, ErrorKind, Result};
+use ::tokio::{io::AsyncReadExt, net::tcp::OwnedReadHalf, sync::mpsc::UnboundedSender};
+
+use crate::{
+	modern::types::{ModernEncodable, VarInt},
+	player::{Position, Rotation},
+	types::{BasaltError, ChatMode, DigStatus, Event, Hand, Slot},
+	world::BlockPos,
+};
+
+/// Largest packet the vanilla client will send when uncompressed
+const MAX_PACKET_SIZE: i32 = 2097151;
+
+/// Reads a whole packet, returning its ID and a cursor over the rest of it
+pub async fn read_packet<R: AsyncReadExt + Send + Unpin>(
+	conn: &mut R,
+) -> Result<(i32, Cursor<Vec<u8>>)> {
+	let length = VarInt::async_read(conn).await?.raw;
+	if length <= 0 || length > MAX_PACKET_SIZE {
+		return Err(Error::new(
+			ErrorKind::InvalidData,
+			BasaltError::new(format!("Invalid packet length {}", length)),
+		));
+	}
+	let mut buffer = vec![0u8; length as usize];
+	conn.read_exact(&mut buffer).await?;
+	let mut packet = Cursor::new(buffer);
+	let id = VarInt::read(&mut packet)?.raw;
+	Ok((id, packet))
+}
+
+/// Reads packets until the connection closes, forwarding them as events
+pub async fn run(mut conn: OwnedReadHalf, events: UnboundedSender<Event>) {
+	loop {
+		let (id, mut packet) = match read_packet(&mut conn).await {
+			Ok(packet) => packet,
+			Err(e) => {
+				debug!("Stopped reading from client: {}", e);
+				break;
+			}
+		};
+		match decode(id, &mut packet) {
+			Ok(Some(event)) => {
+				if events.send(event).is_err() {
+					break;
+				}
+			}
+			Ok(None) => {}
+			Err(e) => {
+				debug!("Malformed packet {:#04X} from client: {}", id, e);
+				break;
+			}
+		}
+	}
+}
+
+fn read_hand(packet: &mut Cursor<Vec<u8>>) -> Result<Hand> {
+	Ok(if VarInt::read(packet)?.raw == 1 {
+		Hand::Off
+	} else {
+		Hand::Main
+	})
+}
+
+fn read_position(packet: &mut Cursor<Vec<u8>>) -> Result<Position> {
+	let x = f64::read(packet)?;
+	let y = f64::read(packet)?;
+	let z = f64::read(packet)?;
+	Ok(Position::new(x, y, z))
+}
+
+fn read_rotation(packet: &mut Cursor<Vec<u8>>) -> Result<Rotation> {
+	let yaw = f32::read(packet)?;
+	let pitch = f32::read(packet)?;
+	Ok(Rotation { yaw, pitch })
+}
+
+/// Turns a serverbound play packet into an event, ignoring packets nothing cares about yet
+fn decode(id: i32, packet: &mut Cursor<Vec<u8>>) -> Result<Option<Event>> {
+	Ok(Some(match id {
+		0x00 => Event::TeleportConfirm(VarInt::read(packet)?.raw),
+		0x03 => Event::Chat(String::read(packet)?),
+		// Client Status, where the only other action asks for statistics
+		0x04 => match VarInt::read(packet)?.raw {
+			0 => Event::Respawn,
+			_ => return Ok(None),
+		},
+		0x05 => {
+			// Locale, which isn't used since chat is only sent in one language
+			String::read(packet)?;
+			let view_distance = i8::read(packet)?;
+			let chat_mode = match VarInt::read(packet)?.raw {
+				0 => ChatMode::Full,
+				1 => ChatMode::CommandsOnly,
+				_ => ChatMode::Hidden,
+			};
+			Event::ClientSettings {
+				view_distance,
+				chat_mode,
+			}
+		}
+		0x06 => {
+			let id = VarInt::read(packet)?.raw;
+			let text = String::read(packet)?;
+			Event::TabComplete { id, text }
+		}
+		0x09 => {
+			let window = u8::read(packet)?;
+			let slot = i16::read(packet)?;
+			let button = i8::read(packet)?;
+			let action = i16::read(packet)?;
+			let mode = VarInt::read(packet)?.raw;
+			let item = Option::<Slot>::read(packet)?;
+			Event::ClickWindow {
+				window,
+				slot,
+				button,
+				action,
+				mode,
+				item,
+			}
+		}
+		0x0A => Event::CloseWindow(u8::read(packet)?),
+		0x0E => {
+			let entity_id = VarInt::read(packet)?.raw;
+			let kind = VarInt::read(packet)?.raw;
+			Event::Interact {
+				entity_id,
+				attack: kind == 1,
+			}
+		}
+		0x10 => Event::KeepAlive(i64::read(packet)?),
+		0x12 => {
+			let position = read_position(packet)?;
+			Event::Move {
+				position: Some(position),
+				rotation: None,
+				on_ground: bool::read(packet)?,
+			}
+		}
+		0x13 => {
+			let position = read_position(packet)?;
+			let rotation = read_rotation(packet)?;
+			Event::Move {
+				position: Some(position),
+				rotation: Some(rotation),
+				on_ground: bool::read(packet)?,
+			}
+		}
+		0x14 => {
+			let rotation = read_rotation(packet)?;
+			Event::Move {
+				position: None,
+				rotation: Some(rotation),
+				on_ground: bool::read(packet)?,
+			}
+		}
+		0x15 => Event::Move {
+			position: None,
+			rotation: None,
+			on_ground: bool::read(packet)?,
+		},
+		0x1B => {
+			let status = match VarInt::read(packet)?.raw {
+				0 => DigStatus::Started,
+				1 => DigStatus::Cancelled,
+				2 => DigStatus::Finished,
+				3 => DigStatus::DropStack,
+				4 => DigStatus::DropItem,
+				5 => DigStatus::ReleaseItem,
+				_ => DigStatus::SwapHands,
+			};
+			let position = BlockPos::read(packet)?;
+			let face = i8::read(packet)? as u8;
+			Event::Dig {
+				status,
+				position,
+				face,
+			}
+		}
+		0x25 => Event::HeldItemChange(i16::read(packet)?),
+		0x28 => {
+			let slot = i16::read(packet)?;
+			let item = Option::<Slot>::read(packet)?;
+			Event::CreativeInventory { slot, item }
+		}
+		0x2C => Event::Swing(read_hand(packet)?),
+		0x2E => {
+			let hand = read_hand(packet)?;
+			let position = BlockPos::read(packet)?;
+			let face = VarInt::read(packet)?.raw as u8;
+			Event::Place {
+				position,
+				face: Some(face),
+				hand,
+				block: None,
+			}
+		}
+		0x2F => Event::UseItem(read_hand(packet)?),
+		_ => return Ok(None),
+	}))
+}
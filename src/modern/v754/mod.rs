@@ -0,0 +1,1030 @@
+use ::async_trait::async_trait;
+use ::std::{
+	collections::{HashMap, HashSet},
+	io::{Error, ErrorKind, Result},
+	sync::Arc,
+};
+use ::tokio::{
+	self,
+	io::AsyncWriteExt,
+	net::{
+		tcp::{OwnedReadHalf, OwnedWriteHalf},
+		TcpStream,
+	},
+	sync::mpsc,
+};
+use ::uuid::Uuid;
+
+use crate::{
+	auth::GameProfile,
+	command::{
+		argument::{Argument, StringKind},
+		CommandNode, NodeKind,
+	},
+	entity::EntityKind,
+	inventory::ContainerKind,
+	lists,
+	modern::{
+		types::{chat::ChatComponent, ModernEncodable, VarInt, VarLong},
+		ModernVersion,
+	},
+	nbt::Nbt,
+	player::{Experience, Gamemode, Position, Rotation, Session},
+	recipe::RecipeKind,
+	state::ServerState,
+	types::{
+		BasaltError, DigStatus, Events, MessagePosition, ProtocolHandler, ProtocolState, Slot,
+	},
+	util,
+	world::{chunk::ChunkPos, dimension, tracker::ChunkTracker, BlockPos, Difficulty, Weather},
+};
+
+mod chunk;
+mod inbound;
+
+use chunk::{encode_chunk, encode_light};
+
+pub struct V754 {
+	chunks: ChunkTracker,
+	conn: OwnedWriteHalf,
+	/// Read half of the connection, until it is handed to the task that reads events
+	reader: Option<OwnedReadHalf>,
+	state: Arc<ServerState>,
+	protocol: ProtocolState,
+	/// ID of the last teleport sent to the client
+	teleport_id: i32,
+	/// Name of the world the player is in
+	world: String,
+}
+
+impl V754 {
+	fn reader(&mut self) -> Result<&mut OwnedReadHalf> {
+		self.reader.as_mut().ok_or_else(|| {
+			Error::new(
+				ErrorKind::Other,
+				BasaltError::new(String::from("Connection is already being read from")),
+			)
+		})
+	}
+
+	async fn send(&mut self, buffer: Vec<u8>) -> Result<()> {
+		self.conn.write_all(&util::prepend_length(buffer)).await
+	}
+
+	async fn send_all(&mut self, buffers: Vec<Vec<u8>>) -> Result<()> {
+		for buffer in buffers {
+			self.send(buffer).await?;
+		}
+		Ok(())
+	}
+
+	pub async fn join_game(&mut self, entity_id: i32, gamemode: Gamemode) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x24).write(&mut buffer)?;
+		entity_id.write(&mut buffer)?;
+		false.write(&mut buffer)?;
+		gamemode.id().write(&mut buffer)?;
+		(-1i8).write(&mut buffer)?;
+		let mut names: Vec<String> = self
+			.state
+			.worlds
+			.values()
+			.map(|w| w.lock().unwrap().identifier())
+			.collect();
+		names.sort();
+		VarInt::from(names.len() as i32).write(&mut buffer)?;
+		for name in names {
+			name.write(&mut buffer)?;
+		}
+		Nbt::new(dimension::codec()).write(&mut buffer)?;
+		{
+			let world = self.state.worlds[&self.world].lock().unwrap();
+			Nbt::new(world.dimension.element()).write(&mut buffer)?;
+			world.identifier().write(&mut buffer)?;
+			// Hashed seed
+			0i64.write(&mut buffer)?;
+			VarInt::from(self.state.config().minecraft.max_players).write(&mut buffer)?;
+			VarInt::from(self.chunks.view_distance()).write(&mut buffer)?;
+			// Reduced debug info and respawn screen
+			false.write(&mut buffer)?;
+			true.write(&mut buffer)?;
+			// Debug world
+			false.write(&mut buffer)?;
+			world.is_flat().write(&mut buffer)?;
+		}
+		self.send(buffer).await
+	}
+
+	pub async fn set_experience(&mut self, experience: Experience) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x48).write(&mut buffer)?;
+		experience.progress.write(&mut buffer)?;
+		VarInt::from(experience.level).write(&mut buffer)?;
+		VarInt::from(experience.total).write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	/// Tells the client every recipe the server knows, so its recipe book can show them
+	pub async fn declare_recipes(&mut self) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x5A).write(&mut buffer)?;
+		let recipes = self.state.recipes.recipes();
+		VarInt::from(recipes.len() as i32).write(&mut buffer)?;
+		for recipe in recipes {
+			String::from(recipe.kind.name()).write(&mut buffer)?;
+			recipe.id.write(&mut buffer)?;
+			match &recipe.kind {
+				RecipeKind::Shaped {
+					width,
+					height,
+					ingredients,
+				} => {
+					VarInt::from(*width as i32).write(&mut buffer)?;
+					VarInt::from(*height as i32).write(&mut buffer)?;
+					recipe.group.write(&mut buffer)?;
+					for ingredient in ingredients {
+						write_ingredient(ingredient, &mut buffer)?;
+					}
+					Some(recipe.result.clone()).write(&mut buffer)?;
+				}
+				RecipeKind::Shapeless { ingredients } => {
+					recipe.group.write(&mut buffer)?;
+					VarInt::from(ingredients.len() as i32).write(&mut buffer)?;
+					for ingredient in ingredients {
+						write_ingredient(ingredient, &mut buffer)?;
+					}
+					Some(recipe.result.clone()).write(&mut buffer)?;
+				}
+				RecipeKind::Smelting {
+					ingredient,
+					experience,
+					cooking_time,
+				} => {
+					recipe.group.write(&mut buffer)?;
+					write_ingredient(ingredient, &mut buffer)?;
+					Some(recipe.result.clone()).write(&mut buffer)?;
+					experience.write(&mut buffer)?;
+					VarInt::from(*cooking_time).write(&mut buffer)?;
+				}
+			}
+		}
+		self.send(buffer).await
+	}
+
+	/// Moves the player into another world, leaving them to be repositioned afterwards
+	pub async fn change_world(&mut self, name: &str, gamemode: Gamemode) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x39).write(&mut buffer)?;
+		match self.state.world(name) {
+			Some(world) => {
+				let world = world.lock().unwrap();
+				Nbt::new(world.dimension.element()).write(&mut buffer)?;
+				world.identifier().write(&mut buffer)?;
+				0i64.write(&mut buffer)?;
+				gamemode.id().write(&mut buffer)?;
+				gamemode.id().write(&mut buffer)?;
+				false.write(&mut buffer)?;
+				world.is_flat().write(&mut buffer)?;
+				// Keep entity metadata such as health
+				true.write(&mut buffer)?;
+			}
+			None => {
+				return Err(Error::new(
+					ErrorKind::NotFound,
+					BasaltError::new(format!("No world named \"{}\"", name)),
+				))
+			}
+		}
+		self.send(buffer).await?;
+		self.world = String::from(name);
+		self.chunks.clear();
+		Ok(())
+	}
+
+	/// Changes the game state, which covers everything from gamemodes to the weather
+	pub async fn change_game_state(&mut self, reason: u8, value: f32) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x1D).write(&mut buffer)?;
+		reason.write(&mut buffer)?;
+		value.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	pub async fn send_unload_chunk(&mut self, pos: ChunkPos) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x1C).write(&mut buffer)?;
+		pos.x.write(&mut buffer)?;
+		pos.z.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	pub async fn send_view_position(&mut self, pos: ChunkPos) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x40).write(&mut buffer)?;
+		VarInt::from(pos.x).write(&mut buffer)?;
+		VarInt::from(pos.z).write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	/// Sends whichever chunks the rate limit allows from those still waiting
+	pub async fn flush_chunks(&mut self) -> Result<()> {
+		let pending = self.chunks.poll();
+		if pending.is_empty() {
+			return Ok(());
+		}
+		let reader = self.state.worlds[&self.world]
+			.lock()
+			.unwrap()
+			.reader(&pending);
+		if let Some(reader) = reader {
+			// Reading the region files can block for a while, so it happens off the async threads
+			// and without the world locked
+			let read = tokio::task::spawn_blocking(move || reader.read())
+				.await
+				.map_err(|e| Error::new(ErrorKind::Other, e))?;
+			self.state.worlds[&self.world]
+				.lock()
+				.unwrap()
+				.add_read_chunks(read);
+		}
+		let mut packets = Vec::with_capacity(pending.len() * 2);
+		{
+			let mut world = self.state.worlds[&self.world].lock().unwrap();
+			for pos in pending {
+				let chunk = world.chunk(pos);
+				packets.push(encode_light(chunk)?);
+				packets.push(encode_chunk(chunk)?);
+			}
+		}
+		self.send_all(packets).await
+	}
+
+	/// Resends light for any of the given chunks that this client has loaded
+	pub async fn resend_light(&mut self, changed: &HashSet<ChunkPos>) -> Result<()> {
+		let mut packets = Vec::new();
+		{
+			let mut world = self.state.worlds[&self.world].lock().unwrap();
+			for pos in changed.iter().filter(|pos| self.chunks.is_loaded(pos)) {
+				packets.push(encode_light(world.chunk(*pos))?);
+			}
+		}
+		self.send_all(packets).await
+	}
+
+	/// Moves the player's view to a new position, loading and unloading chunks as needed
+	pub async fn move_view(&mut self, x: f64, z: f64) -> Result<()> {
+		let center = ChunkPos::from_position(x, z);
+		let update = self.chunks.move_to(center);
+		if update.center_changed {
+			self.send_view_position(center).await?;
+		}
+		for pos in update.unload {
+			self.send_unload_chunk(pos).await?;
+		}
+		self.flush_chunks().await
+	}
+}
+
+#[async_trait]
+impl ModernVersion for V754 {
+	async fn new(conn: TcpStream, state: Arc<ServerState>) -> Box<Self> {
+		let (reader, conn) = conn.into_split();
+		Box::new(Self {
+			chunks: ChunkTracker::new(state.config().minecraft.view_distance),
+			conn,
+			reader: Some(reader),
+			protocol: ProtocolState::Login,
+			teleport_id: 0,
+			world: state.default_world.clone(),
+			state,
+		})
+	}
+}
+
+#[async_trait]
+impl ProtocolHandler for V754 {
+	async fn acknowledge_dig(
+		&mut self,
+		position: BlockPos,
+		state: u16,
+		status: DigStatus,
+		successful: bool,
+	) -> Result<()> {
+		let status = match status {
+			DigStatus::Started => 0,
+			DigStatus::Cancelled => 1,
+			DigStatus::Finished => 2,
+			// The client only waits to hear back about digging
+			_ => return Ok(()),
+		};
+		let mut buffer = Vec::new();
+		VarInt::from(0x07).write(&mut buffer)?;
+		position.write(&mut buffer)?;
+		VarInt::from(state as i32).write(&mut buffer)?;
+		VarInt::from(status).write(&mut buffer)?;
+		successful.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn add_to_player_list(
+		&mut self,
+		profile: &GameProfile,
+		gamemode: Gamemode,
+		ping: i32,
+	) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x32).write(&mut buffer)?;
+		VarInt::from(0).write(&mut buffer)?;
+		VarInt::from(1).write(&mut buffer)?;
+		profile.uuid.write(&mut buffer)?;
+		profile.name.write(&mut buffer)?;
+		VarInt::from(profile.properties.len() as i32).write(&mut buffer)?;
+		for property in &profile.properties {
+			property.name.write(&mut buffer)?;
+			property.value.write(&mut buffer)?;
+			property.signature.is_some().write(&mut buffer)?;
+			if let Some(signature) = &property.signature {
+				signature.write(&mut buffer)?;
+			}
+		}
+		VarInt::from(gamemode.id() as i32).write(&mut buffer)?;
+		VarInt::from(ping).write(&mut buffer)?;
+		// No display name
+		false.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn block_break_animation(
+		&mut self,
+		entity_id: i32,
+		position: BlockPos,
+		stage: i8,
+	) -> Result<()> {
+		if !self
+			.chunks
+			.is_loaded(&ChunkPos::from_block(position.x, position.z))
+		{
+			return Ok(());
+		}
+		let mut buffer = Vec::new();
+		VarInt::from(0x08).write(&mut buffer)?;
+		VarInt::from(entity_id).write(&mut buffer)?;
+		position.write(&mut buffer)?;
+		stage.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn block_change(&mut self, position: BlockPos, state: u16) -> Result<()> {
+		if !self
+			.chunks
+			.is_loaded(&ChunkPos::from_block(position.x, position.z))
+		{
+			return Ok(());
+		}
+		let mut buffer = Vec::new();
+		VarInt::from(0x0B).write(&mut buffer)?;
+		position.write(&mut buffer)?;
+		VarInt::from(state as i32).write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn block_changes(
+		&mut self,
+		blocks: &[(BlockPos, u16)],
+		light: &HashSet<ChunkPos>,
+	) -> Result<()> {
+		let mut sections: HashMap<(i32, i32, i32), Vec<(BlockPos, u16)>> = HashMap::new();
+		for (position, state) in blocks {
+			if self
+				.chunks
+				.is_loaded(&ChunkPos::from_block(position.x, position.z))
+			{
+				sections
+					.entry((position.x >> 4, position.y >> 4, position.z >> 4))
+					.or_default()
+					.push((*position, *state));
+			}
+		}
+		let mut packets = Vec::with_capacity(sections.len());
+		for ((x, y, z), changes) in sections {
+			let mut buffer = Vec::new();
+			if let [(position, state)] = changes[..] {
+				VarInt::from(0x0B).write(&mut buffer)?;
+				position.write(&mut buffer)?;
+				VarInt::from(state as i32).write(&mut buffer)?;
+			} else {
+				VarInt::from(0x3B).write(&mut buffer)?;
+				let section = ((x as i64 & 0x3FFFFF) << 42)
+					| ((z as i64 & 0x3FFFFF) << 20)
+					| (y as i64 & 0xFFFFF);
+				section.write(&mut buffer)?;
+				// Light is sent separately
+				false.write(&mut buffer)?;
+				VarInt::from(changes.len() as i32).write(&mut buffer)?;
+				for (position, state) in changes {
+					let local =
+						((position.x & 15) << 8) | ((position.z & 15) << 4) | (position.y & 15);
+					VarLong::from(((state as i64) << 12) | local as i64).write(&mut buffer)?;
+				}
+			}
+			packets.push(buffer);
+		}
+		self.send_all(packets).await?;
+		self.resend_light(light).await
+	}
+
+	async fn close_window(&mut self, window: u8) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x12).write(&mut buffer)?;
+		window.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn death_screen(&mut self, entity_id: i32, message: &ChatComponent) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x31).write(&mut buffer)?;
+		// Combat Event for an entity dying, where nothing killed the player
+		VarInt::from(2).write(&mut buffer)?;
+		VarInt::from(entity_id).write(&mut buffer)?;
+		(-1i32).write(&mut buffer)?;
+		message.to_json().write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn declare_commands(&mut self, root: &CommandNode) -> Result<()> {
+		// Nodes are listed breadth first, so the children of each node follow on from those of the
+		// node before it
+		let mut nodes = vec![root];
+		let mut i = 0;
+		while i < nodes.len() {
+			let children = &nodes[i].children;
+			nodes.extend(children.iter());
+			i += 1;
+		}
+		let mut buffer = Vec::new();
+		VarInt::from(0x10).write(&mut buffer)?;
+		VarInt::from(nodes.len() as i32).write(&mut buffer)?;
+		let mut next = 1;
+		for node in &nodes {
+			let asks_server = match &node.kind {
+				NodeKind::Argument { argument, .. } => argument.asks_server(),
+				_ => false,
+			};
+			let mut flags: u8 = match node.kind {
+				NodeKind::Root => 0,
+				NodeKind::Literal(_) => 1,
+				NodeKind::Argument { .. } => 2,
+			};
+			if node.executor.is_some() {
+				flags |= 0x04;
+			}
+			if asks_server {
+				flags |= 0x10;
+			}
+			flags.write(&mut buffer)?;
+			VarInt::from(node.children.len() as i32).write(&mut buffer)?;
+			for child in next..next + node.children.len() {
+				VarInt::from(child as i32).write(&mut buffer)?;
+			}
+			next += node.children.len();
+			match &node.kind {
+				NodeKind::Root => {}
+				NodeKind::Literal(name) => name.write(&mut buffer)?,
+				NodeKind::Argument { name, argument } => {
+					name.write(&mut buffer)?;
+					write_argument(argument, &mut buffer)?;
+					if asks_server {
+						String::from("minecraft:ask_server").write(&mut buffer)?;
+					}
+				}
+			}
+		}
+		// Index of the root node
+		VarInt::from(0).write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn despawn_entities(&mut self, entity_ids: &[i32]) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x36).write(&mut buffer)?;
+		VarInt::from(entity_ids.len() as i32).write(&mut buffer)?;
+		for id in entity_ids {
+			VarInt::from(*id).write(&mut buffer)?;
+		}
+		self.send(buffer).await
+	}
+
+	async fn disconnect(&mut self, reason: String) -> Result<()> {
+		let mut buffer = Vec::new();
+		match self.protocol {
+			ProtocolState::Login => {
+				VarInt::from(0x00).write(&mut buffer)?;
+			}
+			ProtocolState::Play => {
+				VarInt::from(0x19).write(&mut buffer)?;
+			}
+		}
+		ChatComponent::text(reason).to_json().write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn entity_teleport(
+		&mut self,
+		entity_id: i32,
+		position: Position,
+		rotation: Rotation,
+		on_ground: bool,
+	) -> Result<()> {
+		let mut teleport = Vec::new();
+		VarInt::from(0x56).write(&mut teleport)?;
+		VarInt::from(entity_id).write(&mut teleport)?;
+		position.x.write(&mut teleport)?;
+		position.y.write(&mut teleport)?;
+		position.z.write(&mut teleport)?;
+		angle(rotation.yaw).write(&mut teleport)?;
+		angle(rotation.pitch).write(&mut teleport)?;
+		on_ground.write(&mut teleport)?;
+		let head = head_look(entity_id, rotation)?;
+		self.send_all(vec![teleport, head]).await
+	}
+
+	async fn flush(&mut self) -> Result<()> {
+		self.flush_chunks().await
+	}
+
+	async fn join(&mut self, entity_id: i32, session: &Session) -> Result<Events> {
+		if session.world != self.world {
+			self.world = session.world.clone();
+			self.chunks.clear();
+		}
+		self.join_game(entity_id, session.gamemode).await?;
+		self.declare_recipes().await?;
+		self.move_view(session.position.x, session.position.z)
+			.await?;
+		self.teleport(session.position, session.rotation).await?;
+		self.update_health(session).await?;
+		self.set_experience(session.experience).await?;
+		let reader = self.reader.take().ok_or_else(|| {
+			Error::new(
+				ErrorKind::Other,
+				BasaltError::new(String::from("Player has already joined")),
+			)
+		})?;
+		let (sender, events) = mpsc::unbounded_channel();
+		tokio::spawn(inbound::run(reader, sender));
+		Ok(events)
+	}
+
+	async fn keep_alive(&mut self, id: i64) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x1F).write(&mut buffer)?;
+		id.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn login(&mut self) -> Result<GameProfile> {
+		let (id, mut packet) = inbound::read_packet(self.reader()?).await?;
+		if id != 0x00 {
+			return Err(Error::new(
+				ErrorKind::InvalidData,
+				BasaltError::new(format!("Expected Login Start but got packet {:#04X}", id)),
+			));
+		}
+		let name = String::read(&mut packet)?;
+		// TODO: Encrypt the connection and authenticate the player when authentication is required
+		let profile = GameProfile::offline(name).map_err(|e| Error::new(ErrorKind::Other, e))?;
+		if let Err(reason) = lists::check_login(&self.state, &profile) {
+			self.disconnect(reason.clone()).await?;
+			return Err(lists::refused(&profile, &reason));
+		}
+		let mut buffer = Vec::new();
+		VarInt::from(0x02).write(&mut buffer)?;
+		profile.uuid.write(&mut buffer)?;
+		profile.name.write(&mut buffer)?;
+		self.send(buffer).await?;
+		self.protocol = ProtocolState::Play;
+		Ok(profile)
+	}
+
+	async fn move_entity(
+		&mut self,
+		entity_id: i32,
+		from: Position,
+		to: Position,
+		rotation: Rotation,
+		on_ground: bool,
+	) -> Result<()> {
+		let delta = |from: f64, to: f64| fixed(to) - fixed(from);
+		let (dx, dy, dz) = (
+			delta(from.x, to.x),
+			delta(from.y, to.y),
+			delta(from.z, to.z),
+		);
+		let limit = i16::MAX as i64;
+		// Relative moves can only cover 8 blocks at once
+		if dx.abs() > limit || dy.abs() > limit || dz.abs() > limit {
+			return self
+				.entity_teleport(entity_id, to, rotation, on_ground)
+				.await;
+		}
+		let mut buffer = Vec::new();
+		VarInt::from(0x28).write(&mut buffer)?;
+		VarInt::from(entity_id).write(&mut buffer)?;
+		(dx as i16).write(&mut buffer)?;
+		(dy as i16).write(&mut buffer)?;
+		(dz as i16).write(&mut buffer)?;
+		angle(rotation.yaw).write(&mut buffer)?;
+		angle(rotation.pitch).write(&mut buffer)?;
+		on_ground.write(&mut buffer)?;
+		let head = head_look(entity_id, rotation)?;
+		self.send_all(vec![buffer, head]).await
+	}
+
+	async fn open_window(
+		&mut self,
+		window: u8,
+		kind: ContainerKind,
+		title: &ChatComponent,
+	) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x2D).write(&mut buffer)?;
+		VarInt::from(window as i32).write(&mut buffer)?;
+		VarInt::from(kind.window_type()).write(&mut buffer)?;
+		title.to_json().write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn remove_from_player_list(&mut self, uuid: Uuid) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x32).write(&mut buffer)?;
+		VarInt::from(4).write(&mut buffer)?;
+		VarInt::from(1).write(&mut buffer)?;
+		uuid.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn respawn(&mut self, session: &Session) -> Result<()> {
+		self.change_world(&session.world, session.gamemode).await?;
+		self.move_view(session.position.x, session.position.z)
+			.await?;
+		self.update_health(session).await?;
+		self.set_experience(session.experience).await
+	}
+
+	async fn rotate_entity(
+		&mut self,
+		entity_id: i32,
+		rotation: Rotation,
+		on_ground: bool,
+	) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x29).write(&mut buffer)?;
+		VarInt::from(entity_id).write(&mut buffer)?;
+		angle(rotation.yaw).write(&mut buffer)?;
+		angle(rotation.pitch).write(&mut buffer)?;
+		on_ground.write(&mut buffer)?;
+		let head = head_look(entity_id, rotation)?;
+		self.send_all(vec![buffer, head]).await
+	}
+
+	async fn send_message(
+		&mut self,
+		message: &ChatComponent,
+		position: MessagePosition,
+		sender: Option<Uuid>,
+	) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x0E).write(&mut buffer)?;
+		message.to_json().write(&mut buffer)?;
+		let position: u8 = match position {
+			MessagePosition::Chat => 0,
+			MessagePosition::System => 1,
+			MessagePosition::ActionBar => 2,
+		};
+		position.write(&mut buffer)?;
+		sender.unwrap_or_else(Uuid::nil).write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn set_difficulty(&mut self, difficulty: Difficulty) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x0D).write(&mut buffer)?;
+		difficulty.id().write(&mut buffer)?;
+		// Whether the difficulty is locked, which only matters in singleplayer
+		false.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn set_gamemode(&mut self, gamemode: Gamemode) -> Result<()> {
+		self.change_game_state(3, gamemode.id() as f32).await?;
+		let mut flags: u8 = 0;
+		if matches!(gamemode, Gamemode::Creative | Gamemode::Spectator) {
+			// Invulnerable
+			flags |= 0x01;
+		}
+		if gamemode == Gamemode::Spectator {
+			// Flying
+			flags |= 0x02;
+		}
+		if gamemode.can_fly() {
+			flags |= 0x04;
+		}
+		if gamemode == Gamemode::Creative {
+			// Breaks blocks instantly
+			flags |= 0x08;
+		}
+		let mut buffer = Vec::new();
+		VarInt::from(0x30).write(&mut buffer)?;
+		flags.write(&mut buffer)?;
+		// Flying speed and field of view modifier
+		0.05f32.write(&mut buffer)?;
+		0.1f32.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn set_inventory(&mut self, window: u8, items: &[Option<Slot>]) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x13).write(&mut buffer)?;
+		window.write(&mut buffer)?;
+		(items.len() as i16).write(&mut buffer)?;
+		for item in items {
+			item.write(&mut buffer)?;
+		}
+		self.send(buffer).await
+	}
+
+	async fn set_op_level(&mut self, entity_id: i32, level: u8) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x1A).write(&mut buffer)?;
+		entity_id.write(&mut buffer)?;
+		// Entity statuses 24 to 28 set the operator level from 0 to 4
+		(24 + level as i8).write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn set_slot(&mut self, window: u8, slot: i16, item: Option<&Slot>) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x15).write(&mut buffer)?;
+		(window as i8).write(&mut buffer)?;
+		slot.write(&mut buffer)?;
+		item.cloned().write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn set_view_distance(&mut self, view_distance: u8) -> Result<()> {
+		let old = self.chunks.view_distance();
+		let unload = self.chunks.set_view_distance(view_distance);
+		if self.chunks.view_distance() != old {
+			let mut buffer = Vec::new();
+			VarInt::from(0x41).write(&mut buffer)?;
+			VarInt::from(self.chunks.view_distance()).write(&mut buffer)?;
+			self.send(buffer).await?;
+		}
+		for pos in unload {
+			self.send_unload_chunk(pos).await?;
+		}
+		self.flush_chunks().await
+	}
+
+	async fn set_weather(&mut self, weather: Weather) -> Result<()> {
+		let (reason, rain, thunder) = match weather {
+			Weather::Clear => (1, 0.0, 0.0),
+			Weather::Rain => (2, 1.0, 0.0),
+			Weather::Thunder => (2, 1.0, 1.0),
+		};
+		self.change_game_state(reason, 0.0).await?;
+		self.change_game_state(7, rain).await?;
+		self.change_game_state(8, thunder).await
+	}
+
+	async fn spawn_entity(
+		&mut self,
+		entity_id: i32,
+		uuid: Uuid,
+		kind: EntityKind,
+		position: Position,
+		rotation: Rotation,
+	) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x00).write(&mut buffer)?;
+		VarInt::from(entity_id).write(&mut buffer)?;
+		uuid.write(&mut buffer)?;
+		VarInt::from(kind.id()).write(&mut buffer)?;
+		position.x.write(&mut buffer)?;
+		position.y.write(&mut buffer)?;
+		position.z.write(&mut buffer)?;
+		angle(rotation.pitch).write(&mut buffer)?;
+		angle(rotation.yaw).write(&mut buffer)?;
+		// Data, whose meaning depends on the kind of entity, followed by velocity
+		0i32.write(&mut buffer)?;
+		0i16.write(&mut buffer)?;
+		0i16.write(&mut buffer)?;
+		0i16.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn spawn_player(
+		&mut self,
+		entity_id: i32,
+		profile: &GameProfile,
+		position: Position,
+		rotation: Rotation,
+	) -> Result<()> {
+		let mut spawn = Vec::new();
+		VarInt::from(0x04).write(&mut spawn)?;
+		VarInt::from(entity_id).write(&mut spawn)?;
+		profile.uuid.write(&mut spawn)?;
+		position.x.write(&mut spawn)?;
+		position.y.write(&mut spawn)?;
+		position.z.write(&mut spawn)?;
+		angle(rotation.yaw).write(&mut spawn)?;
+		angle(rotation.pitch).write(&mut spawn)?;
+		self.send(spawn).await
+	}
+
+	async fn tab_complete(
+		&mut self,
+		id: i32,
+		start: usize,
+		length: usize,
+		matches: &[String],
+	) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x0F).write(&mut buffer)?;
+		VarInt::from(id).write(&mut buffer)?;
+		VarInt::from(start as i32).write(&mut buffer)?;
+		VarInt::from(length as i32).write(&mut buffer)?;
+		VarInt::from(matches.len() as i32).write(&mut buffer)?;
+		for suggestion in matches {
+			suggestion.write(&mut buffer)?;
+			// No tooltip
+			false.write(&mut buffer)?;
+		}
+		self.send(buffer).await
+	}
+
+	async fn teleport(&mut self, position: Position, rotation: Rotation) -> Result<Option<i32>> {
+		self.teleport_id += 1;
+		let mut buffer = Vec::new();
+		VarInt::from(0x34).write(&mut buffer)?;
+		position.x.write(&mut buffer)?;
+		position.y.write(&mut buffer)?;
+		position.z.write(&mut buffer)?;
+		rotation.yaw.write(&mut buffer)?;
+		rotation.pitch.write(&mut buffer)?;
+		// Every field is absolute
+		0i8.write(&mut buffer)?;
+		VarInt::from(self.teleport_id).write(&mut buffer)?;
+		self.send(buffer).await?;
+		Ok(Some(self.teleport_id))
+	}
+
+	async fn title(
+		&mut self,
+		title: &ChatComponent,
+		subtitle: Option<&ChatComponent>,
+		fade_in: i32,
+		stay: i32,
+		fade_out: i32,
+	) -> Result<()> {
+		let mut packets = Vec::new();
+		let mut times = Vec::new();
+		VarInt::from(0x4F).write(&mut times)?;
+		VarInt::from(3).write(&mut times)?;
+		fade_in.write(&mut times)?;
+		stay.write(&mut times)?;
+		fade_out.write(&mut times)?;
+		packets.push(times);
+		if let Some(subtitle) = subtitle {
+			let mut buffer = Vec::new();
+			VarInt::from(0x4F).write(&mut buffer)?;
+			VarInt::from(1).write(&mut buffer)?;
+			subtitle.to_json().write(&mut buffer)?;
+			packets.push(buffer);
+		}
+		// Setting the title is what makes it show up, so it goes last
+		let mut buffer = Vec::new();
+		VarInt::from(0x4F).write(&mut buffer)?;
+		VarInt::from(0).write(&mut buffer)?;
+		title.to_json().write(&mut buffer)?;
+		packets.push(buffer);
+		self.send_all(packets).await
+	}
+
+	async fn update_health(&mut self, session: &Session) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x49).write(&mut buffer)?;
+		session.health.write(&mut buffer)?;
+		VarInt::from(session.food).write(&mut buffer)?;
+		session.saturation.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn update_latency(&mut self, uuid: Uuid, ping: i32) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x32).write(&mut buffer)?;
+		VarInt::from(2).write(&mut buffer)?;
+		VarInt::from(1).write(&mut buffer)?;
+		uuid.write(&mut buffer)?;
+		VarInt::from(ping).write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn update_time(&mut self, age: i64, time: i64) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x4E).write(&mut buffer)?;
+		age.write(&mut buffer)?;
+		time.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+
+	async fn update_view(&mut self, position: Position) -> Result<()> {
+		self.move_view(position.x, position.z).await
+	}
+
+	async fn window_confirmation(&mut self, window: u8, action: i16, accepted: bool) -> Result<()> {
+		let mut buffer = Vec::new();
+		VarInt::from(0x11).write(&mut buffer)?;
+		(window as i8).write(&mut buffer)?;
+		action.write(&mut buffer)?;
+		accepted.write(&mut buffer)?;
+		self.send(buffer).await
+	}
+}
+
+/// Writes the items that can be used for an ingredient as a list of single items
+fn write_ingredient(ingredient: &[i32], buffer: &mut Vec<u8>) -> Result<()> {
+	VarInt::from(ingredient.len() as i32).write(buffer)?;
+	for item in ingredient {
+		Some(Slot {
+			item: *item,
+			count: 1,
+			nbt: None,
+		})
+		.write(buffer)?;
+	}
+	Ok(())
+}
+
+/// Converts degrees into the 256ths of a turn used by entity packets
+fn angle(degrees: f32) -> u8 {
+	(degrees / 360.0 * 256.0) as i32 as u8
+}
+
+/// Builds a Head Look packet, as the head turns separately from the body
+fn head_look(entity_id: i32, rotation: Rotation) -> Result<Vec<u8>> {
+	let mut buffer = Vec::new();
+	VarInt::from(0x3A).write(&mut buffer)?;
+	VarInt::from(entity_id).write(&mut buffer)?;
+	angle(rotation.yaw).write(&mut buffer)?;
+	Ok(buffer)
+}
+
+/// Converts a coordinate into the 4096ths of a block relative moves are measured in
+fn fixed(value: f64) -> i64 {
+	(value * 4096.0).floor() as i64
+}
+
+/// Writes the parser brigadier uses for an argument, followed by its properties
+fn write_argument(argument: &Argument, buffer: &mut Vec<u8>) -> Result<()> {
+	match argument {
+		Argument::Integer { min, max } => {
+			String::from("brigadier:integer").write(buffer)?;
+			let flags = min.map_or(0, |_| 0x01) | max.map_or(0, |_| 0x02);
+			(flags as u8).write(buffer)?;
+			if let Some(min) = min {
+				min.write(buffer)?;
+			}
+			if let Some(max) = max {
+				max.write(buffer)?;
+			}
+			Ok(())
+		}
+		Argument::String(kind) => {
+			String::from("brigadier:string").write(buffer)?;
+			VarInt::from(match kind {
+				StringKind::Word => 0,
+				StringKind::Greedy => 2,
+			})
+			.write(buffer)
+		}
+		Argument::Entity { single, players } => {
+			String::from("minecraft:entity").write(buffer)?;
+			let mut flags: u8 = 0;
+			if *single {
+				flags |= 0x01;
+			}
+			if *players {
+				flags |= 0x02;
+			}
+			flags.write(buffer)
+		}
+		Argument::GameProfile => String::from("minecraft:game_profile").write(buffer),
+		Argument::BlockPos => String::from("minecraft:block_pos").write(buffer),
+		Argument::Vec3 => String::from("minecraft:vec3").write(buffer),
+		Argument::Message => String::from("minecraft:message").write(buffer),
+		Argument::Component => String::from("minecraft:component").write(buffer),
+		Argument::Item => String::from("minecraft:item_stack").write(buffer),
+		Argument::Time => String::from("minecraft:time").write(buffer),
+	}
+}
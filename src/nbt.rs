@@ -0,0 +1,355 @@
+use ::async_trait::async_trait;
+use ::std::{
+	collections::HashMap,
+	future::Future,
+	io::{Error, ErrorKind, Read, Result, Write},
+	pin::Pin,
+};
+use ::tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::{modern::types::ModernEncodable, types::BasaltError};
+
+const TAG_END: u8 = 0;
+const TAG_BYTE: u8 = 1;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_LONG: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_DOUBLE: u8 = 6;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+const TAG_INT_ARRAY: u8 = 11;
+const TAG_LONG_ARRAY: u8 = 12;
+
+/// A single NBT value
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tag {
+	Byte(i8),
+	Short(i16),
+	Int(i32),
+	Long(i64),
+	Float(f32),
+	Double(f64),
+	ByteArray(Vec<i8>),
+	String(String),
+	/// Every element of a list must share the same tag type
+	List(Vec<Tag>),
+	Compound(HashMap<String, Tag>),
+	IntArray(Vec<i32>),
+	LongArray(Vec<i64>),
+}
+
+impl Tag {
+	pub fn id(&self) -> u8 {
+		match self {
+			Self::Byte(_) => TAG_BYTE,
+			Self::Short(_) => TAG_SHORT,
+			Self::Int(_) => TAG_INT,
+			Self::Long(_) => TAG_LONG,
+			Self::Float(_) => TAG_FLOAT,
+			Self::Double(_) => TAG_DOUBLE,
+			Self::ByteArray(_) => TAG_BYTE_ARRAY,
+			Self::String(_) => TAG_STRING,
+			Self::List(_) => TAG_LIST,
+			Self::Compound(_) => TAG_COMPOUND,
+			Self::IntArray(_) => TAG_INT_ARRAY,
+			Self::LongArray(_) => TAG_LONG_ARRAY,
+		}
+	}
+
+	fn read_payload<R: Read>(id: u8, stream: &mut R) -> Result<Self> {
+		Ok(match id {
+			TAG_BYTE => Self::Byte(i8::read(stream)?),
+			TAG_SHORT => Self::Short(i16::read(stream)?),
+			TAG_INT => Self::Int(i32::read(stream)?),
+			TAG_LONG => Self::Long(i64::read(stream)?),
+			TAG_FLOAT => Self::Float(f32::read(stream)?),
+			TAG_DOUBLE => Self::Double(f64::read(stream)?),
+			TAG_BYTE_ARRAY => {
+				let length = read_length(stream)?;
+				let mut values = Vec::with_capacity(length);
+				for _ in 0..length {
+					values.push(i8::read(stream)?);
+				}
+				Self::ByteArray(values)
+			}
+			TAG_STRING => Self::String(read_string(stream)?),
+			TAG_LIST => {
+				let element = u8::read(stream)?;
+				let length = read_length(stream)?;
+				let mut values = Vec::with_capacity(length);
+				for _ in 0..length {
+					values.push(Self::read_payload(element, stream)?);
+				}
+				Self::List(values)
+			}
+			TAG_COMPOUND => {
+				let mut values = HashMap::new();
+				loop {
+					let id = u8::read(stream)?;
+					if id == TAG_END {
+						break;
+					}
+					let name = read_string(stream)?;
+					values.insert(name, Self::read_payload(id, stream)?);
+				}
+				Self::Compound(values)
+			}
+			TAG_INT_ARRAY => {
+				let length = read_length(stream)?;
+				let mut values = Vec::with_capacity(length);
+				for _ in 0..length {
+					values.push(i32::read(stream)?);
+				}
+				Self::IntArray(values)
+			}
+			TAG_LONG_ARRAY => {
+				let length = read_length(stream)?;
+				let mut values = Vec::with_capacity(length);
+				for _ in 0..length {
+					values.push(i64::read(stream)?);
+				}
+				Self::LongArray(values)
+			}
+			_ => return Err(invalid_tag(id)),
+		})
+	}
+
+	fn read_payload_async<'a, R: AsyncReadExt + Send + Unpin>(
+		id: u8,
+		stream: &'a mut R,
+	) -> Pin<Box<dyn Future<Output = Result<Self>> + Send + 'a>> {
+		Box::pin(async move {
+			Ok(match id {
+				TAG_BYTE => Self::Byte(stream.read_i8().await?),
+				TAG_SHORT => Self::Short(stream.read_i16().await?),
+				TAG_INT => Self::Int(stream.read_i32().await?),
+				TAG_LONG => Self::Long(stream.read_i64().await?),
+				TAG_FLOAT => Self::Float(f32::async_read(stream).await?),
+				TAG_DOUBLE => Self::Double(f64::async_read(stream).await?),
+				TAG_BYTE_ARRAY => {
+					let length = check_length(stream.read_i32().await?)?;
+					let mut values = Vec::with_capacity(length);
+					for _ in 0..length {
+						values.push(stream.read_i8().await?);
+					}
+					Self::ByteArray(values)
+				}
+				TAG_STRING => Self::String(read_string_async(stream).await?),
+				TAG_LIST => {
+					let element = stream.read_u8().await?;
+					let length = check_length(stream.read_i32().await?)?;
+					let mut values = Vec::with_capacity(length);
+					for _ in 0..length {
+						values.push(Self::read_payload_async(element, stream).await?);
+					}
+					Self::List(values)
+				}
+				TAG_COMPOUND => {
+					let mut values = HashMap::new();
+					loop {
+						let id = stream.read_u8().await?;
+						if id == TAG_END {
+							break;
+						}
+						let name = read_string_async(stream).await?;
+						values.insert(name, Self::read_payload_async(id, stream).await?);
+					}
+					Self::Compound(values)
+				}
+				TAG_INT_ARRAY => {
+					let length = check_length(stream.read_i32().await?)?;
+					let mut values = Vec::with_capacity(length);
+					for _ in 0..length {
+						values.push(stream.read_i32().await?);
+					}
+					Self::IntArray(values)
+				}
+				TAG_LONG_ARRAY => {
+					let length = check_length(stream.read_i32().await?)?;
+					let mut values = Vec::with_capacity(length);
+					for _ in 0..length {
+						values.push(stream.read_i64().await?);
+					}
+					Self::LongArray(values)
+				}
+				_ => return Err(invalid_tag(id)),
+			})
+		})
+	}
+
+	fn write_payload<W: Write>(&self, stream: &mut W) -> Result<()> {
+		match self {
+			Self::Byte(v) => v.write(stream)?,
+			Self::Short(v) => v.write(stream)?,
+			Self::Int(v) => v.write(stream)?,
+			Self::Long(v) => v.write(stream)?,
+			Self::Float(v) => v.write(stream)?,
+			Self::Double(v) => v.write(stream)?,
+			Self::ByteArray(values) => {
+				(values.len() as i32).write(stream)?;
+				for v in values {
+					v.write(stream)?;
+				}
+			}
+			Self::String(v) => write_string(v, stream)?,
+			Self::List(values) => {
+				let element = values.first().map(|v| v.id()).unwrap_or(TAG_END);
+				element.write(stream)?;
+				(values.len() as i32).write(stream)?;
+				for v in values {
+					if v.id() != element {
+						return Err(Error::new(
+							ErrorKind::InvalidData,
+							BasaltError::new(String::from("NBT list elements differ in type")),
+						));
+					}
+					v.write_payload(stream)?;
+				}
+			}
+			Self::Compound(values) => {
+				for (name, v) in values {
+					v.id().write(stream)?;
+					write_string(name, stream)?;
+					v.write_payload(stream)?;
+				}
+				TAG_END.write(stream)?;
+			}
+			Self::IntArray(values) => {
+				(values.len() as i32).write(stream)?;
+				for v in values {
+					v.write(stream)?;
+				}
+			}
+			Self::LongArray(values) => {
+				(values.len() as i32).write(stream)?;
+				for v in values {
+					v.write(stream)?;
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Looks up a child of a compound tag
+	pub fn get(&self, name: &str) -> Option<&Tag> {
+		match self {
+			Self::Compound(values) => values.get(name),
+			_ => None,
+		}
+	}
+}
+
+/// Builds a compound tag from a list of name/value pairs
+#[macro_export]
+macro_rules! compound {
+	($($name:expr => $value:expr),* $(,)?) => {{
+		#[allow(unused_mut)]
+		let mut values = ::std::collections::HashMap::new();
+		$(values.insert(::std::string::String::from($name), $value);)*
+		$crate::nbt::Tag::Compound(values)
+	}};
+}
+
+/// A named root tag, as found on the wire and in `.dat` files
+#[derive(Clone, Debug, PartialEq)]
+pub struct Nbt {
+	pub name: String,
+	pub root: Tag,
+}
+
+impl Nbt {
+	pub fn new(root: Tag) -> Self {
+		Self {
+			name: String::new(),
+			root,
+		}
+	}
+
+	/// Reads a named tag whose type has already been read, as happens when a tag is optional
+	pub async fn async_read_after_id<R: AsyncReadExt + Send + Unpin>(
+		id: u8,
+		stream: &mut R,
+	) -> Result<Self> {
+		let name = read_string_async(stream).await?;
+		let root = Tag::read_payload_async(id, stream).await?;
+		Ok(Self { name, root })
+	}
+
+	/// Reads a named tag whose type has already been read, as happens when a tag is optional
+	pub fn read_after_id<R: Read>(id: u8, stream: &mut R) -> Result<Self> {
+		let name = read_string(stream)?;
+		let root = Tag::read_payload(id, stream)?;
+		Ok(Self { name, root })
+	}
+}
+
+#[async_trait]
+impl ModernEncodable for Nbt {
+	async fn async_read<R: AsyncReadExt + Send + Unpin>(stream: &mut R) -> Result<Self> {
+		let id = stream.read_u8().await?;
+		Self::async_read_after_id(id, stream).await
+	}
+
+	async fn async_write<W: AsyncWriteExt + Send + Unpin>(&self, stream: &mut W) -> Result<()> {
+		let mut buffer = Vec::new();
+		self.write(&mut buffer)?;
+		stream.write_all(&buffer).await?;
+		Ok(())
+	}
+
+	fn read<R: Read>(stream: &mut R) -> Result<Self> {
+		let id = u8::read(stream)?;
+		Self::read_after_id(id, stream)
+	}
+
+	fn write<W: Write>(&self, stream: &mut W) -> Result<()> {
+		self.root.id().write(stream)?;
+		write_string(&self.name, stream)?;
+		self.root.write_payload(stream)
+	}
+}
+
+fn check_length(length: i32) -> Result<usize> {
+	if length < 0 {
+		Err(Error::new(
+			ErrorKind::InvalidData,
+			BasaltError::new(format!("Negative NBT length {}", length)),
+		))
+	} else {
+		Ok(length as usize)
+	}
+}
+
+fn invalid_tag(id: u8) -> Error {
+	Error::new(
+		ErrorKind::InvalidData,
+		BasaltError::new(format!("Unknown NBT tag {}", id)),
+	)
+}
+
+fn read_length<R: Read>(stream: &mut R) -> Result<usize> {
+	check_length(i32::read(stream)?)
+}
+
+fn read_string<R: Read>(stream: &mut R) -> Result<String> {
+	let length = u16::read(stream)?;
+	let mut buffer = vec![0u8; length as usize];
+	stream.read_exact(&mut buffer)?;
+	String::from_utf8(buffer).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+async fn read_string_async<R: AsyncReadExt + Send + Unpin>(stream: &mut R) -> Result<String> {
+	let length = stream.read_u16().await?;
+	let mut buffer = vec![0u8; length as usize];
+	stream.read_exact(&mut buffer).await?;
+	String::from_utf8(buffer).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+fn write_string<W: Write>(value: &str, stream: &mut W) -> Result<()> {
+	(value.len() as u16).write(stream)?;
+	stream.write_all(value.as_bytes())?;
+	Ok(())
+}
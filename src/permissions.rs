@@ -0,0 +1,326 @@
+use ::log::{info, warn};
+use ::serde::Deserialize;
+use ::std::{collections::HashMap, error::Error, fs, io::ErrorKind, time::SystemTime};
+use ::uuid::Uuid;
+
+use crate::{auth::GameProfile, lists, state::ServerState, types::BasaltError};
+
+/// Group everyone is in, whether or not they are listed
+const DEFAULT_GROUP: &str = "default";
+/// How deep groups can inherit from each other, which stops groups that inherit from themselves from
+/// being followed forever
+const MAX_INHERITANCE_DEPTH: usize = 16;
+
+/// Permissions given to a group or player
+///
+/// Nodes are dotted names such as `basalt.command.kick`. A node ending in `.*` covers every node
+/// under it, `*` covers everything, and a node starting with `-` takes a permission away instead.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PermissionSet {
+	/// Groups whose permissions are inherited, in order of priority
+	pub groups: Vec<String>,
+	pub permissions: Vec<String>,
+	/// Permissions that only apply in a world, which take priority over the others, keyed by the
+	/// world's name
+	pub worlds: HashMap<String, Vec<String>>,
+}
+
+/// Permission groups and the players in them, as set up in `permissions.toml`
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Permissions {
+	pub groups: HashMap<String, PermissionSet>,
+	/// Players keyed by name or UUID
+	pub players: HashMap<String, PermissionSet>,
+	#[serde(skip)]
+	path: String,
+	/// When the file had last been modified as of the last time it was read
+	#[serde(skip)]
+	modified: Option<SystemTime>,
+}
+
+impl Permissions {
+	/// Reads the permissions file, where a missing file gives nobody any permissions
+	pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+		let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+		let mut permissions: Self = match fs::read_to_string(path) {
+			Ok(contents) => ::toml::from_str(&contents)
+				.map_err(|e| BasaltError::new(format!("Couldn't read {}: {}", path, e)))?,
+			Err(e) if e.kind() == ErrorKind::NotFound => Self::default(),
+			Err(e) => return Err(Box::new(e)),
+		};
+		permissions.path = String::from(path);
+		permissions.modified = modified;
+		Ok(permissions)
+	}
+
+	/// Reads the file again if it was changed since it was last read, keeping the current
+	/// permissions if it can't be read, and returns whether anything was reloaded
+	pub fn refresh(&mut self) -> bool {
+		let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+		if modified.is_none() || modified == self.modified {
+			return false;
+		}
+		match Self::load(&self.path) {
+			Ok(permissions) => {
+				info!("Reloaded {}", self.path);
+				*self = permissions;
+				true
+			}
+			Err(e) => {
+				// Don't complain about the same broken file again until it changes
+				self.modified = modified;
+				warn!("{}", e);
+				false
+			}
+		}
+	}
+
+	/// Whether a player is given or denied a permission in a world, or None if nothing mentions it
+	///
+	/// The player's own permissions come first, then those of their groups and finally those of the
+	/// default group.
+	pub fn lookup(&self, profile: &GameProfile, world: &str, node: &str) -> Option<bool> {
+		let player = self
+			.players
+			.iter()
+			.find(|(key, _)| match Uuid::parse_str(key) {
+				Ok(uuid) => uuid == profile.uuid,
+				Err(_) => key.eq_ignore_ascii_case(&profile.name),
+			});
+		if let Some((_, set)) = player {
+			if let Some(granted) = self.resolve(set, world, node, 0) {
+				return Some(granted);
+			}
+		}
+		let set = self.groups.get(DEFAULT_GROUP)?;
+		self.resolve(set, world, node, 0)
+	}
+
+	fn resolve(&self, set: &PermissionSet, world: &str, node: &str, depth: usize) -> Option<bool> {
+		if depth > MAX_INHERITANCE_DEPTH {
+			return None;
+		}
+		if let Some(granted) = set.worlds.get(world).and_then(|nodes| best(nodes, node)) {
+			return Some(granted);
+		}
+		if let Some(granted) = best(&set.permissions, node) {
+			return Some(granted);
+		}
+		set.groups
+			.iter()
+			.filter_map(|group| self.groups.get(group))
+			.find_map(|group| self.resolve(group, world, node, depth + 1))
+	}
+}
+
+/// How closely a listed node covers a permission, where higher is closer, or None if it doesn't
+fn specificity(pattern: &str, node: &str) -> Option<usize> {
+	if pattern == node {
+		Some(usize::MAX)
+	} else if pattern == "*" {
+		Some(0)
+	} else {
+		let prefix = pattern.strip_suffix(".*")?;
+		match node.strip_prefix(prefix) {
+			Some(rest) if rest.starts_with('.') => Some(prefix.len() + 1),
+			_ => None,
+		}
+	}
+}
+
+/// Decides a permission from the closest of a list of nodes, where taking it away wins a tie
+fn best(nodes: &[String], node: &str) -> Option<bool> {
+	nodes
+		.iter()
+		.filter_map(|entry| {
+			let (pattern, granted) = match entry.strip_prefix('-') {
+				Some(pattern) => (pattern, false),
+				None => (entry.as_str(), true),
+			};
+			Some((specificity(pattern, node)?, !granted))
+		})
+		.max()
+		.map(|(_, denied)| !denied)
+}
+
+/// Whether a player has a permission in a world, which falls back on them being an operator of at
+/// least the given level if their permissions don't mention it
+pub fn check(
+	state: &ServerState,
+	profile: &GameProfile,
+	world: &str,
+	node: &str,
+	level: u8,
+) -> bool {
+	let granted = state
+		.permissions
+		.read()
+		.unwrap()
+		.lookup(profile, world, node);
+	match granted {
+		Some(granted) => granted,
+		None => lists::op_level(state, profile) >= level,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn nodes(nodes: &[&str]) -> Vec<String> {
+		nodes.iter().map(|node| String::from(*node)).collect()
+	}
+
+	fn profile(name: &str) -> GameProfile {
+		GameProfile::offline(String::from(name)).unwrap()
+	}
+
+	fn permissions(toml: &str) -> Permissions {
+		::toml::from_str(toml).unwrap()
+	}
+
+	#[test]
+	fn closer_nodes_are_more_specific() {
+		let node = "basalt.command.kick";
+		assert_eq!(specificity("basalt.command.kick", node), Some(usize::MAX));
+		assert_eq!(specificity("*", node), Some(0));
+		assert!(specificity("basalt.command.*", node) > specificity("basalt.*", node));
+		assert!(specificity("basalt.*", node) > specificity("*", node));
+		assert_eq!(specificity("basalt.command.ban", node), None);
+		assert_eq!(specificity("basalt.comm.*", node), None);
+		assert_eq!(specificity("basalt.command.kick.*", node), None);
+	}
+
+	#[test]
+	fn closest_node_decides() {
+		let node = "basalt.command.kick";
+		assert_eq!(best(&nodes(&["*", "-basalt.command.*"]), node), Some(false));
+		assert_eq!(
+			best(&nodes(&["-*", "basalt.command.kick"]), node),
+			Some(true)
+		);
+		assert_eq!(best(&nodes(&["basalt.*", "-basalt.*"]), node), Some(false));
+		assert_eq!(best(&nodes(&["basalt.command.ban"]), node), None);
+		assert_eq!(best(&[], node), None);
+	}
+
+	#[test]
+	fn players_are_found_by_name_or_uuid() {
+		let alice = profile("Alice");
+		let bob = profile("Bob");
+		let permissions = permissions(&format!(
+			r#"
+			[players.alice]
+			permissions = ["basalt.command.kick"]
+			[players."{}"]
+			permissions = ["basalt.command.ban"]
+			"#,
+			bob.uuid
+		));
+		assert_eq!(
+			permissions.lookup(&alice, "world", "basalt.command.kick"),
+			Some(true)
+		);
+		assert_eq!(
+			permissions.lookup(&bob, "world", "basalt.command.ban"),
+			Some(true)
+		);
+		assert_eq!(
+			permissions.lookup(&bob, "world", "basalt.command.kick"),
+			None
+		);
+	}
+
+	#[test]
+	fn groups_are_inherited_in_order() {
+		let alice = profile("Alice");
+		let permissions = permissions(
+			r#"
+			[groups.default]
+			permissions = ["basalt.command.list"]
+			[groups.mod]
+			groups = ["helper"]
+			permissions = ["basalt.command.kick"]
+			[groups.helper]
+			permissions = ["-basalt.command.kick", "basalt.command.tp"]
+			[players.Alice]
+			groups = ["mod"]
+			"#,
+		);
+		let lookup = |node| permissions.lookup(&alice, "world", node);
+		assert_eq!(lookup("basalt.command.kick"), Some(true));
+		assert_eq!(lookup("basalt.command.tp"), Some(true));
+		assert_eq!(lookup("basalt.command.list"), Some(true));
+		assert_eq!(lookup("basalt.command.ban"), None);
+	}
+
+	#[test]
+	fn world_permissions_come_first() {
+		let alice = profile("Alice");
+		let permissions = permissions(
+			r#"
+			[players.Alice]
+			permissions = ["basalt.command.gamemode"]
+			worlds.creative = ["-basalt.command.gamemode"]
+			"#,
+		);
+		assert_eq!(
+			permissions.lookup(&alice, "world", "basalt.command.gamemode"),
+			Some(true)
+		);
+		assert_eq!(
+			permissions.lookup(&alice, "creative", "basalt.command.gamemode"),
+			Some(false)
+		);
+	}
+
+	#[test]
+	fn groups_inheriting_themselves_end() {
+		let alice = profile("Alice");
+		let permissions = permissions(
+			r#"
+			[groups.a]
+			groups = ["b"]
+			[groups.b]
+			groups = ["a"]
+			[players.Alice]
+			groups = ["a"]
+			"#,
+		);
+		assert_eq!(
+			permissions.lookup(&alice, "world", "basalt.command.kick"),
+			None
+		);
+	}
+
+	#[test]
+	fn edited_file_is_read_again() {
+		let dir =
+			::std::env::temp_dir().join(format!("basalt-permissions-{}", ::std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("permissions.toml");
+		let path = path.to_str().unwrap();
+		let _ = fs::remove_file(path);
+		let alice = profile("Alice");
+		let mut permissions = Permissions::load(path).unwrap();
+		assert!(!permissions.refresh());
+		assert_eq!(
+			permissions.lookup(&alice, "world", "basalt.command.kick"),
+			None
+		);
+		fs::write(
+			path,
+			"[players.Alice]\npermissions = [\"basalt.command.kick\"]\n",
+		)
+		.unwrap();
+		assert!(permissions.refresh());
+		assert!(!permissions.refresh());
+		assert_eq!(
+			permissions.lookup(&alice, "world", "basalt.command.kick"),
+			Some(true)
+		);
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
@@ -0,0 +1,358 @@
+use ::log::debug;
+use ::std::io::Result;
+use ::tokio::time::Instant;
+
+use crate::{
+	entity::EntityKind,
+	inventory::ContainerKind,
+	player::{Gamemode, Player, PlayerMessage, Session},
+	tick::TICK_DURATION,
+	types::{DigStatus, Hand},
+	world::{
+		block::{self, Tool},
+		collision::{self, BoundingBox},
+		BlockPos,
+	},
+};
+
+/// Furthest the center of a block can be from a player's eyes for them to break it
+const DIG_REACH: f64 = 6.0;
+/// Furthest the center of a block can be from a player's eyes for them to place against it
+pub(super) const PLACE_REACH: f64 = 8.0;
+/// Height of a player's eyes above their feet, as reach is measured in vanilla
+const EYE_HEIGHT: f64 = 1.5;
+/// Fraction of a block's break time it may be broken in, to allow for latency as vanilla does
+const DIG_LEEWAY: f64 = 0.7;
+/// Distance other players can see a block being broken from
+const ANIMATION_RANGE: f64 = 32.0;
+const PLAYER_WIDTH: f64 = 0.6;
+const PLAYER_HEIGHT: f64 = 1.8;
+
+/// A block a player has started breaking but not finished
+pub struct Digging {
+	position: BlockPos,
+	state: u16,
+	started: Instant,
+	/// Ticks the block takes to break
+	ticks: u32,
+	/// Stage of the break animation last shown to other players
+	stage: i8,
+}
+
+impl Player {
+	pub(super) async fn handle_dig(&mut self, status: DigStatus, position: BlockPos) -> Result<()> {
+		match status {
+			DigStatus::Started => self.start_digging(position).await,
+			// Classic clients only say when a block is gone
+			DigStatus::Finished if self.handler.breaks_instantly() => {
+				self.start_digging(position).await
+			}
+			DigStatus::Finished => self.finish_digging(position).await,
+			DigStatus::Cancelled => {
+				self.stop_digging();
+				let state = self.get_block(position);
+				self.handler
+					.acknowledge_dig(position, state, status, true)
+					.await
+			}
+			_ => Ok(()),
+		}
+	}
+
+	async fn start_digging(&mut self, position: BlockPos) -> Result<()> {
+		self.stop_digging();
+		let session = self.session();
+		let state = self.get_block(position);
+		let ticks = if !self.can_dig(&session, position, state) {
+			None
+		} else if session.gamemode == Gamemode::Creative {
+			Some(0)
+		} else {
+			match block::break_ticks(state, self.held_tool(), session.on_ground) {
+				Some(_) if self.handler.breaks_instantly() => Some(0),
+				ticks => ticks,
+			}
+		};
+		match ticks {
+			Some(0) => self.break_block(position),
+			Some(ticks) => {
+				self.digging = Some(Digging {
+					position,
+					state,
+					started: Instant::now(),
+					ticks,
+					stage: -1,
+				})
+			}
+			None => {
+				return self
+					.handler
+					.acknowledge_dig(position, state, DigStatus::Started, false)
+					.await
+			}
+		}
+		self.handler
+			.acknowledge_dig(position, state, DigStatus::Started, true)
+			.await
+	}
+
+	async fn finish_digging(&mut self, position: BlockPos) -> Result<()> {
+		let session = self.session();
+		let state = self.get_block(position);
+		let digging = match self.digging.take() {
+			Some(digging) if digging.position == position => digging,
+			other => {
+				self.digging = other;
+				debug!(
+					"{} finished breaking a block they never started breaking",
+					self.profile.name
+				);
+				return self
+					.handler
+					.acknowledge_dig(position, state, DigStatus::Finished, false)
+					.await;
+			}
+		};
+		self.show_progress(position, -1);
+		let ticks = digging.started.elapsed().as_secs_f64() / TICK_DURATION.as_secs_f64();
+		let successful = state == digging.state
+			&& ticks >= digging.ticks as f64 * DIG_LEEWAY
+			&& self.can_dig(&session, position, state);
+		if successful {
+			self.break_block(position);
+		} else {
+			debug!(
+				"{} wasn't allowed to break the block at ({}, {}, {}) after {:.1} of {} ticks",
+				self.profile.name, position.x, position.y, position.z, ticks, digging.ticks
+			);
+		}
+		self.handler
+			.acknowledge_dig(position, state, DigStatus::Finished, successful)
+			.await
+	}
+
+	/// Forgets about the block the player was breaking, if there was one
+	pub(super) fn stop_digging(&mut self) {
+		if let Some(digging) = self.digging.take() {
+			self.show_progress(digging.position, -1);
+		}
+	}
+
+	/// Shows other players how far along breaking a block the player is, if it has changed
+	pub(super) fn update_digging(&mut self) {
+		let (position, stage) = match &mut self.digging {
+			Some(digging) => {
+				let elapsed = digging.started.elapsed().as_secs_f64() / TICK_DURATION.as_secs_f64();
+				let stage = (elapsed * 10.0 / digging.ticks as f64).min(9.0) as i8;
+				if stage == digging.stage {
+					return;
+				}
+				digging.stage = stage;
+				(digging.position, stage)
+			}
+			None => return,
+		};
+		self.show_progress(position, stage);
+	}
+
+	fn show_progress(&self, position: BlockPos, stage: i8) {
+		let session = self.session();
+		let (x, y, z) = center(position);
+		for player in self.state.players.read().unwrap().values() {
+			if player.entity_id == self.entity_id {
+				continue;
+			}
+			let other = player.session();
+			let (dx, dy, dz) = (
+				other.position.x - x,
+				other.position.y - y,
+				other.position.z - z,
+			);
+			if other.world == session.world
+				&& dx * dx + dy * dy + dz * dz <= ANIMATION_RANGE * ANIMATION_RANGE
+			{
+				player.send(PlayerMessage::BlockBreakAnimation {
+					entity_id: self.entity_id,
+					position,
+					stage,
+				});
+			}
+		}
+	}
+
+	/// Places the block the player is holding, or the block a classic client says it placed
+	pub(super) async fn handle_place(
+		&mut self,
+		position: BlockPos,
+		face: Option<u8>,
+		hand: Hand,
+		block: Option<u16>,
+	) -> Result<()> {
+		let (target, state) = match (face, block) {
+			(None, Some(state)) => (position, state),
+			(Some(face), None) => {
+				if let Some(kind) = self.container_at(position) {
+					return self.use_container(position, kind).await;
+				}
+				match self.held_block(hand) {
+					Some(state) => (position.offset(face), state),
+					// Clicking a block without holding one doesn't place anything
+					None => return Ok(()),
+				}
+			}
+			_ => return Ok(()),
+		};
+		let session = self.session();
+		let allowed = state != 0
+			&& (0..256).contains(&target.y)
+			&& self.can_build(&session, target)
+			&& in_reach(&session, position, PLACE_REACH);
+		let current = match self.state.world(&session.world) {
+			Some(world) => {
+				let mut world = world.lock().unwrap();
+				let current = world.get_block(target.x, target.y, target.z);
+				let bounds = BoundingBox {
+					min: (target.x as f64, target.y as f64, target.z as f64),
+					max: (
+						target.x as f64 + 1.0,
+						target.y as f64 + 1.0,
+						target.z as f64 + 1.0,
+					),
+				};
+				let obstructed = collision::is_solid(state)
+					&& world.entities.values().any(|entity| {
+						entity.kind == EntityKind::Player
+							&& BoundingBox::standing(
+								entity.position.x,
+								entity.position.y,
+								entity.position.z,
+								PLAYER_WIDTH,
+								PLAYER_HEIGHT,
+							)
+							.intersects(&bounds)
+					});
+				if allowed && block::is_replaceable(current) && !obstructed {
+					world.set_block(target.x, target.y, target.z, state);
+					drop(world);
+					// Classic clients have as many of every block as they like
+					if face.is_some() && session.gamemode != Gamemode::Creative {
+						self.inventory.consume_held(hand);
+					}
+					return Ok(());
+				}
+				current
+			}
+			None => return Ok(()),
+		};
+		debug!(
+			"{} wasn't allowed to place a block at ({}, {}, {})",
+			self.profile.name, target.x, target.y, target.z
+		);
+		// The client already shows the block, so it has to be put back
+		self.handler.block_change(target, current).await
+	}
+
+	/// The block state placed by the item the player is holding in a hand
+	fn held_block(&self, hand: Hand) -> Option<u16> {
+		let item = self.state.items.name(self.inventory.held(hand)?.item)?;
+		// Block items share their name with the block they place
+		self.state.blocks.world().default_state(item)
+	}
+
+	fn held_tool(&self) -> Option<Tool> {
+		let item = self
+			.state
+			.items
+			.name(self.inventory.held(Hand::Main)?.item)?;
+		Tool::from_item(item)
+	}
+
+	/// The kind of container at a position, if there is one
+	fn container_at(&self, position: BlockPos) -> Option<ContainerKind> {
+		let state = self.get_block(position);
+		self.state
+			.blocks
+			.world()
+			.get(state)
+			.and_then(|state| ContainerKind::of_block(state.name))
+	}
+
+	/// Opens a container the player clicked on, if they can reach it
+	async fn use_container(&mut self, position: BlockPos, kind: ContainerKind) -> Result<()> {
+		if in_reach(&self.session(), position, PLACE_REACH) {
+			self.open_container(position, kind).await
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Whether the player may change the block at a position, regardless of how far away it is
+	fn can_build(&self, session: &Session, position: BlockPos) -> bool {
+		if session.health <= 0.0
+			|| matches!(session.gamemode, Gamemode::Adventure | Gamemode::Spectator)
+			|| !self.has_permission("basalt.build", 0)
+		{
+			return false;
+		}
+		let radius = self.state.config().minecraft.spawn_protection as i32;
+		// Any operator can build near spawn, as in vanilla
+		if radius == 0 || self.has_permission("basalt.build.spawn_protection", 1) {
+			return true;
+		}
+		match self.state.world(&session.world) {
+			Some(world) => {
+				let (x, _, z) = world.lock().unwrap().spawn;
+				(position.x - x).abs().max((position.z - z).abs()) > radius
+			}
+			None => false,
+		}
+	}
+
+	fn can_dig(&self, session: &Session, position: BlockPos, state: u16) -> bool {
+		state != 0 && self.can_build(session, position) && in_reach(session, position, DIG_REACH)
+	}
+
+	fn break_block(&self, position: BlockPos) {
+		let world = self.session().world;
+		let container = self.container_at(position);
+		if let Some(world) = self.state.world(&world) {
+			world
+				.lock()
+				.unwrap()
+				.set_block(position.x, position.y, position.z, 0);
+		}
+		if container.is_some() {
+			self.notify_viewers(&world, position);
+		}
+	}
+
+	fn get_block(&self, position: BlockPos) -> u16 {
+		let world = self.session().world;
+		match self.state.world(&world) {
+			Some(world) => world
+				.lock()
+				.unwrap()
+				.get_block(position.x, position.y, position.z),
+			None => 0,
+		}
+	}
+}
+
+fn center(position: BlockPos) -> (f64, f64, f64) {
+	(
+		position.x as f64 + 0.5,
+		position.y as f64 + 0.5,
+		position.z as f64 + 0.5,
+	)
+}
+
+/// Whether the center of a block is close enough to the player's eyes for them to reach it
+pub(super) fn in_reach(session: &Session, position: BlockPos, reach: f64) -> bool {
+	let (x, y, z) = center(position);
+	let (dx, dy, dz) = (
+		session.position.x - x,
+		session.position.y + EYE_HEIGHT - y,
+		session.position.z - z,
+	);
+	dx * dx + dy * dy + dz * dz <= reach * reach
+}
@@ -0,0 +1,107 @@
+use ::log::info;
+use ::std::{io::Result, time::Instant};
+
+use crate::{
+	modern::types::chat::ChatComponent,
+	player::PlayerMessage,
+	types::{ChatMode, MessagePosition},
+};
+
+use super::Player;
+
+/// Counts how much a player has said recently, draining at a steady rate like vanilla's spam counter
+pub(super) struct SpamCounter {
+	count: f64,
+	updated: Instant,
+}
+
+impl SpamCounter {
+	pub(super) fn new() -> Self {
+		Self {
+			count: 0.0,
+			updated: Instant::now(),
+		}
+	}
+
+	/// Counts another message, returning whether the player has now gone over the limit
+	fn add(&mut self, limit: u32, interval: u64) -> bool {
+		let now = Instant::now();
+		if interval > 0 {
+			let drained = now.duration_since(self.updated).as_millis() as f64 / interval as f64;
+			self.count = (self.count - drained).max(0.0);
+		}
+		self.updated = now;
+		self.count += 1.0;
+		limit > 0 && self.count > limit as f64
+	}
+}
+
+/// Whether a character can't be typed in chat, which includes formatting codes
+fn is_illegal(c: char) -> bool {
+	c == '§' || c < ' ' || c == '\u{7f}'
+}
+
+impl Player {
+	/// Runs a command or says something in chat, after checking the message the way vanilla does
+	pub(super) async fn handle_chat(&mut self, text: String) -> Result<()> {
+		let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+		let allowed = match self.chat_mode {
+			ChatMode::Full => true,
+			ChatMode::CommandsOnly => text.starts_with('/'),
+			ChatMode::Hidden => false,
+		};
+		if !allowed {
+			let message = ChatComponent::text("Cannot send chat message").color("red");
+			return self
+				.handler
+				.send_message(&message, MessagePosition::System, None)
+				.await;
+		}
+		if text.chars().any(is_illegal) {
+			self.kick("Illegal characters in chat");
+			return Ok(());
+		}
+		let config = self.state.config();
+		if self
+			.spam
+			.add(config.chat.spam_limit, config.chat.spam_interval)
+			&& !self.has_permission("basalt.chat.spam", 1)
+		{
+			self.kick("Kicked for spamming");
+			return Ok(());
+		}
+		if let Some(command) = text.strip_prefix('/') {
+			info!("{} issued server command: {}", self.profile.name, text);
+			if let Some(sender) = self.sender() {
+				self.state.commands.run(command, &sender, &self.state);
+			}
+		} else if !text.is_empty() {
+			let message = config
+				.chat
+				.format
+				.replace("{name}", &self.profile.name)
+				.replace("{message}", &text);
+			info!("{}", message);
+			let message = ChatComponent::text(message);
+			for player in self.state.players.read().unwrap().values() {
+				player.send(PlayerMessage::Message {
+					message: message.clone(),
+					position: MessagePosition::Chat,
+					sender: Some(self.profile.uuid),
+				});
+			}
+		}
+		Ok(())
+	}
+
+	/// Kicks the player the same way other tasks would, so they are disconnected as soon as the event
+	/// being handled is done with
+	fn kick(&self, reason: &str) {
+		let players = self.state.players.read().unwrap();
+		if let Some(player) = players.get(&self.profile.uuid) {
+			if player.entity_id == self.entity_id {
+				player.disconnect(String::from(reason));
+			}
+		}
+	}
+}
@@ -0,0 +1,267 @@
+use ::log::{debug, warn};
+use ::std::{
+	fs,
+	io::{Error, ErrorKind, Result},
+	path::PathBuf,
+};
+use ::uuid::Uuid;
+
+use crate::{
+	compound, gzip,
+	inventory::PlayerInventory,
+	modern::types::ModernEncodable,
+	nbt::{Nbt, Tag},
+	state::ServerState,
+	types::Slot,
+	world::{storage::DATA_VERSION, BlockPos},
+};
+
+use super::{Gamemode, Player, Position, Rotation, Session};
+
+/// Where a player's data is saved, which is the `playerdata` directory of the default world just as
+/// vanilla keeps it in the directory of the overworld
+fn path(state: &ServerState, uuid: Uuid) -> PathBuf {
+	let world = state.worlds[&state.default_world].lock().unwrap();
+	world
+		.directory
+		.join("playerdata")
+		.join(format!("{}.dat", uuid))
+}
+
+/// Reads any kind of number from a tag, since vanilla isn't always consistent about which it uses
+fn number(tag: Option<&Tag>) -> Option<f64> {
+	match tag? {
+		Tag::Byte(value) => Some(*value as f64),
+		Tag::Short(value) => Some(*value as f64),
+		Tag::Int(value) => Some(*value as f64),
+		Tag::Long(value) => Some(*value as f64),
+		Tag::Float(value) => Some(*value as f64),
+		Tag::Double(value) => Some(*value),
+		_ => None,
+	}
+}
+
+fn numbers(tag: Option<&Tag>) -> Vec<f64> {
+	match tag {
+		Some(Tag::List(values)) => values.iter().filter_map(|v| number(Some(v))).collect(),
+		_ => Vec::new(),
+	}
+}
+
+/// Finds a world by name when basalt saved the file, and by dimension when vanilla did
+fn find_world(state: &ServerState, name: Option<&Tag>, dimension: Option<&Tag>) -> Option<String> {
+	match (name, dimension) {
+		(Some(Tag::String(name)), _) if state.worlds.contains_key(name) => Some(name.clone()),
+		(_, Some(Tag::String(dimension))) => state
+			.config()
+			.worlds
+			.iter()
+			.find(|world| world.dimension.identifier() == dimension)
+			.map(|world| world.name.clone()),
+		_ => None,
+	}
+}
+
+fn decode(data: &[u8]) -> Result<Tag> {
+	let data = gzip::decompress(data)?;
+	Ok(Nbt::read(&mut data.as_slice())?.root)
+}
+
+/// Restores a player's session and inventory to how they were when the player last left, leaving
+/// them untouched if the player hasn't played before
+pub(super) fn load(
+	state: &ServerState,
+	uuid: Uuid,
+	session: &mut Session,
+	inventory: &mut PlayerInventory,
+) -> Result<()> {
+	let path = path(state, uuid);
+	let data = match fs::read(&path) {
+		Ok(data) => data,
+		Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+		Err(e) => return Err(e),
+	};
+	let root = match decode(&data) {
+		Ok(root) => root,
+		Err(e) => {
+			// Moved out of the way so saving the player later can't overwrite what might still be
+			// recovered by hand
+			let corrupt = path.with_extension("dat_corrupt");
+			fs::rename(&path, &corrupt)?;
+			return Err(Error::new(
+				e.kind(),
+				format!("{} (moved to {})", e, corrupt.display()),
+			));
+		}
+	};
+	if let Some(world) = find_world(state, root.get("BasaltWorld"), root.get("Dimension")) {
+		session.world = world;
+	}
+	let spawn_world = find_world(
+		state,
+		root.get("BasaltSpawnWorld"),
+		root.get("SpawnDimension"),
+	);
+	let spawn = (
+		number(root.get("SpawnX")),
+		number(root.get("SpawnY")),
+		number(root.get("SpawnZ")),
+	);
+	if let (Some(world), (Some(x), Some(y), Some(z))) = (spawn_world, spawn) {
+		session.spawn = Some((world, BlockPos::new(x as i32, y as i32, z as i32)));
+	}
+	if let [x, y, z] = numbers(root.get("Pos"))[..] {
+		session.position = Position::new(x, y, z);
+	}
+	if let [yaw, pitch] = numbers(root.get("Rotation"))[..] {
+		session.rotation = Rotation {
+			yaw: yaw as f32,
+			pitch: pitch as f32,
+		};
+	}
+	if let Some(on_ground) = number(root.get("OnGround")) {
+		session.on_ground = on_ground != 0.0;
+	}
+	if let Some(gamemode) = number(root.get("playerGameType")) {
+		session.gamemode = Gamemode::from_id(gamemode as i32).unwrap_or(session.gamemode);
+	}
+	if let Some(health) = number(root.get("Health")) {
+		session.health = health as f32;
+	}
+	if let Some(food) = number(root.get("foodLevel")) {
+		session.food = food as i32;
+	}
+	if let Some(saturation) = number(root.get("foodSaturationLevel")) {
+		session.saturation = saturation as f32;
+	}
+	if let Some(level) = number(root.get("XpLevel")) {
+		session.experience.level = level as i32;
+	}
+	if let Some(progress) = number(root.get("XpP")) {
+		session.experience.progress = progress as f32;
+	}
+	if let Some(total) = number(root.get("XpTotal")) {
+		session.experience.total = total as i32;
+	}
+	if let Some(selected) = number(root.get("SelectedItemSlot")) {
+		if (0.0..9.0).contains(&selected) {
+			inventory.selected = selected as usize;
+		}
+	}
+	if let Some(Tag::List(items)) = root.get("Inventory") {
+		for item in items {
+			let (slot, name, count) = match (item.get("Slot"), item.get("id"), item.get("Count")) {
+				(Some(Tag::Byte(slot)), Some(Tag::String(name)), Some(Tag::Byte(count))) => {
+					(*slot, name, *count)
+				}
+				_ => continue,
+			};
+			let id = match state.items.id(name) {
+				Some(id) if count > 0 => id,
+				_ => {
+					debug!("Dropped {} x{} from a saved inventory", name, count);
+					continue;
+				}
+			};
+			let stack = Slot {
+				item: id,
+				count,
+				nbt: item.get("tag").cloned().map(Nbt::new),
+			};
+			if !inventory.restore(slot, stack) {
+				debug!("Dropped {} from unknown saved slot {}", name, slot);
+			}
+		}
+	}
+	Ok(())
+}
+
+impl Player {
+	/// Saves the player's session and inventory in vanilla's format, so they come back where they
+	/// left off and the file can be moved to a vanilla world
+	pub(super) fn save(&self) {
+		if let Err(e) = self.write_data() {
+			warn!("Couldn't save the data of {}: {}", self.profile.name, e);
+		}
+	}
+
+	fn write_data(&self) -> Result<()> {
+		let session = self.session();
+		let dimension = |name: &str| {
+			self.state
+				.world(name)
+				.map(|world| world.lock().unwrap().dimension.identifier())
+				.unwrap_or("minecraft:overworld")
+		};
+		let uuid = self.profile.uuid.as_u128();
+		let inventory = self
+			.inventory
+			.saved()
+			.into_iter()
+			.filter_map(|(slot, stack)| {
+				let mut item = compound! {
+					"Slot" => Tag::Byte(slot),
+					"id" => Tag::String(String::from(self.state.items.name(stack.item)?)),
+					"Count" => Tag::Byte(stack.count),
+				};
+				if let (Tag::Compound(values), Some(nbt)) = (&mut item, &stack.nbt) {
+					values.insert(String::from("tag"), nbt.root.clone());
+				}
+				Some(item)
+			})
+			.collect();
+		let Position { x, y, z } = session.position;
+		let mut root = compound! {
+			"DataVersion" => Tag::Int(DATA_VERSION),
+			"UUID" => Tag::IntArray(vec![
+				(uuid >> 96) as i32,
+				(uuid >> 64) as i32,
+				(uuid >> 32) as i32,
+				uuid as i32,
+			]),
+			"BasaltWorld" => Tag::String(session.world.clone()),
+			"Dimension" => Tag::String(String::from(dimension(&session.world))),
+			"Pos" => Tag::List(vec![Tag::Double(x), Tag::Double(y), Tag::Double(z)]),
+			"Rotation" => Tag::List(vec![
+				Tag::Float(session.rotation.yaw),
+				Tag::Float(session.rotation.pitch),
+			]),
+			"OnGround" => Tag::Byte(session.on_ground as i8),
+			"playerGameType" => Tag::Int(session.gamemode.id() as i32),
+			"Health" => Tag::Float(session.health),
+			"foodLevel" => Tag::Int(session.food),
+			"foodSaturationLevel" => Tag::Float(session.saturation),
+			"XpLevel" => Tag::Int(session.experience.level),
+			"XpP" => Tag::Float(session.experience.progress),
+			"XpTotal" => Tag::Int(session.experience.total),
+			"SelectedItemSlot" => Tag::Int(self.inventory.selected as i32),
+			"Inventory" => Tag::List(inventory),
+		};
+		if let (Tag::Compound(values), Some((world, block))) = (&mut root, &session.spawn) {
+			let spawn = vec![
+				("SpawnX", Tag::Int(block.x)),
+				("SpawnY", Tag::Int(block.y)),
+				("SpawnZ", Tag::Int(block.z)),
+				(
+					"SpawnDimension",
+					Tag::String(String::from(dimension(world))),
+				),
+				("BasaltSpawnWorld", Tag::String(world.clone())),
+			];
+			for (key, value) in spawn {
+				values.insert(String::from(key), value);
+			}
+		}
+		let mut buffer = Vec::new();
+		Nbt::new(root).write(&mut buffer)?;
+		let path = path(&self.state, self.profile.uuid);
+		if let Some(directory) = path.parent() {
+			fs::create_dir_all(directory)?;
+		}
+		// Written to another file first so a crash part way through can't leave the old data half
+		// overwritten
+		let temporary = path.with_extension("dat_tmp");
+		fs::write(&temporary, gzip::compress(&buffer))?;
+		fs::rename(&temporary, &path)
+	}
+}
@@ -0,0 +1,838 @@
+use ::log::{debug, info, warn};
+use ::std::{
+	collections::HashSet,
+	io::Result,
+	net::SocketAddr,
+	sync::{Arc, RwLock},
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use ::tokio::{
+	self,
+	sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+	time::{self, Instant},
+};
+use ::uuid::Uuid;
+
+mod building;
+mod chat;
+mod data;
+mod movement;
+mod respawn;
+mod window;
+
+use crate::{
+	auth::GameProfile,
+	command::Sender,
+	entity::{Entity, EntityUpdate},
+	inventory::{Click, PlayerInventory},
+	lists,
+	modern::types::chat::ChatComponent,
+	permissions,
+	state::ServerState,
+	types::{ChatMode, Event, Events, MessagePosition, ProtocolHandler},
+	world::{chunk::ChunkPos, BlockPos, Difficulty, Weather},
+};
+
+use building::Digging;
+use chat::SpamCounter;
+use movement::MovementValidator;
+use window::OpenWindow;
+
+/// How often handlers are given a chance to send anything they held back
+const FLUSH_INTERVAL: Duration = Duration::from_millis(50);
+/// How often clients are checked on, which must be well under the 30 seconds modern clients wait
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(10);
+/// How often a player's data is saved while they are online, which is as often as vanilla does it
+const SAVE_INTERVAL: Duration = Duration::from_secs(300);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Gamemode {
+	Survival,
+	Creative,
+	Adventure,
+	Spectator,
+}
+
+impl Gamemode {
+	pub const ALL: &'static [Gamemode] = &[
+		Gamemode::Survival,
+		Gamemode::Creative,
+		Gamemode::Adventure,
+		Gamemode::Spectator,
+	];
+
+	/// Whether players in this gamemode are allowed to fly
+	pub fn can_fly(&self) -> bool {
+		matches!(self, Self::Creative | Self::Spectator)
+	}
+
+	pub fn from_id(id: i32) -> Option<Self> {
+		match id {
+			0 => Some(Self::Survival),
+			1 => Some(Self::Creative),
+			2 => Some(Self::Adventure),
+			3 => Some(Self::Spectator),
+			_ => None,
+		}
+	}
+
+	pub fn id(&self) -> u8 {
+		match self {
+			Self::Survival => 0,
+			Self::Creative => 1,
+			Self::Adventure => 2,
+			Self::Spectator => 3,
+		}
+	}
+
+	/// Name used in commands and selectors
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::Survival => "survival",
+			Self::Creative => "creative",
+			Self::Adventure => "adventure",
+			Self::Spectator => "spectator",
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Position {
+	pub x: f64,
+	pub y: f64,
+	pub z: f64,
+}
+
+impl Position {
+	pub fn new(x: f64, y: f64, z: f64) -> Self {
+		Self { x, y, z }
+	}
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rotation {
+	pub yaw: f32,
+	pub pitch: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Experience {
+	pub level: i32,
+	/// Progress towards the next level, from 0 to 1
+	pub progress: f32,
+	/// Points collected since the player last died
+	pub total: i32,
+}
+
+/// Everything about a player that can change while they are online
+#[derive(Clone, Debug)]
+pub struct Session {
+	pub world: String,
+	pub position: Position,
+	pub rotation: Rotation,
+	pub on_ground: bool,
+	pub gamemode: Gamemode,
+	pub health: f32,
+	/// Hunger, where 20 is full
+	pub food: i32,
+	/// How much the player can exert themselves before hunger starts going down
+	pub saturation: f32,
+	pub experience: Experience,
+	/// Round trip time in milliseconds
+	pub ping: i32,
+	/// World and block the player respawns at, if they have been given a spawn point of their own
+	pub spawn: Option<(String, BlockPos)>,
+}
+
+/// Requests other tasks can make of a player's connection
+pub enum PlayerMessage {
+	/// Someone joined the server
+	AddToPlayerList {
+		profile: GameProfile,
+		gamemode: Gamemode,
+		ping: i32,
+	},
+	/// Another player's progress breaking a block changed
+	BlockBreakAnimation {
+		entity_id: i32,
+		position: BlockPos,
+		stage: i8,
+	},
+	/// Blocks and light changed in the player's world
+	BlockChanges {
+		blocks: Vec<(BlockPos, u16)>,
+		light: HashSet<ChunkPos>,
+	},
+	/// Someone changed what is in a container
+	ContainerChanged {
+		world: String,
+		position: BlockPos,
+	},
+	/// The server's difficulty changed
+	Difficulty(Difficulty),
+	Disconnect(String),
+	/// Entities around the player changed
+	Entities(Vec<EntityUpdate>),
+	Gamemode(Gamemode),
+	/// Puts a number of an item into the player's inventory
+	Give {
+		item: i32,
+		count: i32,
+	},
+	Kill,
+	/// Another player's latency changed
+	Latency {
+		uuid: Uuid,
+		ping: i32,
+	},
+	Message {
+		message: ChatComponent,
+		position: MessagePosition,
+		sender: Option<Uuid>,
+	},
+	/// Someone left the server
+	RemoveFromPlayerList(Uuid),
+	/// Saves the player's data now rather than waiting until they leave or it is next due
+	Save,
+	SetSpawn(Option<(String, BlockPos)>),
+	/// Moves the player anywhere, keeping the way they are facing if no rotation is given
+	Teleport {
+		world: String,
+		position: Position,
+		rotation: Option<Rotation>,
+	},
+	/// The age and time of day of the player's world
+	Time {
+		age: i64,
+		time: i64,
+	},
+	/// The player's permissions changed, so the commands they can use may have as well
+	UpdatePermissions,
+	/// The server's view distance changed
+	ViewDistance(u8),
+	/// The weather changed in the player's world
+	Weather(Weather),
+}
+
+/// A cheap, cloneable reference to an online player that can be used from any task
+#[derive(Clone)]
+pub struct PlayerHandle {
+	pub entity_id: i32,
+	pub profile: GameProfile,
+	/// Where the player is connected from
+	pub address: SocketAddr,
+	session: Arc<RwLock<Session>>,
+	sender: UnboundedSender<PlayerMessage>,
+}
+
+impl PlayerHandle {
+	pub fn disconnect(&self, reason: String) {
+		self.send(PlayerMessage::Disconnect(reason));
+	}
+
+	pub fn name(&self) -> &str {
+		&self.profile.name
+	}
+
+	/// Queues a message for the player's connection, returning false if they have already left
+	pub fn send(&self, message: PlayerMessage) -> bool {
+		self.sender.send(message).is_ok()
+	}
+
+	/// Takes a copy of the player's current session
+	pub fn session(&self) -> Session {
+		self.session.read().unwrap().clone()
+	}
+
+	pub fn uuid(&self) -> Uuid {
+		self.profile.uuid
+	}
+}
+
+/// A player who has logged in, owned by the task handling their connection
+pub struct Player {
+	pub entity_id: i32,
+	pub profile: GameProfile,
+	/// Which chat messages the client wants to see
+	chat_mode: ChatMode,
+	/// The block the player is breaking
+	digging: Option<Digging>,
+	handler: Box<dyn ProtocolHandler>,
+	inventory: PlayerInventory,
+	/// The keep alive waiting for an answer and when it was sent
+	keep_alive: Option<(i64, Instant)>,
+	/// When anything was last heard from the client
+	last_seen: Instant,
+	movement: MovementValidator,
+	receiver: UnboundedReceiver<PlayerMessage>,
+	session: Arc<RwLock<Session>>,
+	spam: SpamCounter,
+	state: Arc<ServerState>,
+	/// How many chunks away the client asked to see, or None until it sends its settings
+	view_distance: Option<u8>,
+	/// The container window the player has open
+	window: Option<OpenWindow>,
+	/// ID of the last window opened, which the next one follows on from
+	window_id: u8,
+}
+
+impl Player {
+	/// Creates a player where they were when they last left, or at the spawn of the default world if
+	/// they haven't played before, along with a handle to them
+	pub fn new(
+		handler: Box<dyn ProtocolHandler>,
+		profile: GameProfile,
+		address: SocketAddr,
+		state: Arc<ServerState>,
+	) -> (Self, PlayerHandle) {
+		let (sender, receiver) = mpsc::unbounded_channel();
+		let world = state.default_world.clone();
+		let position = state.worlds[&world].lock().unwrap().spawn_position();
+		let mut session = Session {
+			world,
+			position,
+			rotation: Rotation::default(),
+			on_ground: false,
+			gamemode: Gamemode::Survival,
+			health: 20.0,
+			food: 20,
+			saturation: 5.0,
+			experience: Experience::default(),
+			ping: 0,
+			spawn: None,
+		};
+		let mut inventory = PlayerInventory::new();
+		if let Err(e) = data::load(&state, profile.uuid, &mut session, &mut inventory) {
+			warn!("Couldn't load the data of {}: {}", profile.name, e);
+		}
+		let position = session.position;
+		let session = Arc::new(RwLock::new(session));
+		let entity_id = state.next_entity_id();
+		let handle = PlayerHandle {
+			entity_id,
+			profile: profile.clone(),
+			address,
+			session: session.clone(),
+			sender,
+		};
+		let player = Self {
+			entity_id,
+			profile,
+			chat_mode: ChatMode::Full,
+			digging: None,
+			handler,
+			inventory,
+			keep_alive: None,
+			last_seen: Instant::now(),
+			movement: MovementValidator::new(position),
+			receiver,
+			session,
+			spam: SpamCounter::new(),
+			state,
+			view_distance: None,
+			window: None,
+			window_id: 0,
+		};
+		(player, handle)
+	}
+
+	pub async fn disconnect(&mut self, reason: String) -> Result<()> {
+		info!("{} was disconnected: {}", self.profile.name, reason);
+		self.handler.disconnect(reason).await
+	}
+
+	pub fn name(&self) -> &str {
+		&self.profile.name
+	}
+
+	/// Sends chunks as far as both the server's view distance and the client's allow
+	async fn update_view_distance(&mut self, server: u8) -> Result<()> {
+		let view_distance = match self.view_distance {
+			Some(client) => server.min(client),
+			None => server,
+		};
+		self.handler.set_view_distance(view_distance).await
+	}
+
+	/// The player as whoever is running a command, or None if they aren't listed as online
+	fn sender(&self) -> Option<Sender> {
+		let players = self.state.players.read().unwrap();
+		players
+			.get(&self.profile.uuid)
+			.filter(|player| player.entity_id == self.entity_id)
+			.map(|player| Sender::Player(player.clone()))
+	}
+
+	/// Whether the player has a permission in the world they are in, falling back on them being an
+	/// operator of at least the given level
+	pub fn has_permission(&self, node: &str, level: u8) -> bool {
+		let world = self.session().world;
+		permissions::check(&self.state, &self.profile, &world, node, level)
+	}
+
+	/// Sends the player into the world and serves their connection until they leave
+	pub async fn run(mut self) -> Result<()> {
+		let session = self.session();
+		let events = self.handler.join(self.entity_id, &session).await?;
+		info!("{} joined the game", self.profile.name);
+		let result = match self.enter(&session).await {
+			Ok(()) => self.serve(events).await,
+			Err(e) => Err(e),
+		};
+		self.stop_digging();
+		self.put_away();
+		self.save();
+		self.leave();
+		result
+	}
+
+	/// Shows the player who is online, tells everyone else about them and adds them to their world
+	async fn enter(&mut self, session: &Session) -> Result<()> {
+		let online: Vec<_> = self
+			.state
+			.players
+			.read()
+			.unwrap()
+			.values()
+			.map(|player| {
+				let session = player.session();
+				(player.profile.clone(), session.gamemode, session.ping)
+			})
+			.collect();
+		for (profile, gamemode, ping) in &online {
+			self.handler
+				.add_to_player_list(profile, *gamemode, *ping)
+				.await?;
+		}
+		for player in self.state.players.read().unwrap().values() {
+			if player.entity_id != self.entity_id {
+				player.send(PlayerMessage::AddToPlayerList {
+					profile: self.profile.clone(),
+					gamemode: session.gamemode,
+					ping: session.ping,
+				});
+			}
+		}
+		if let Some(world) = self.state.world(&session.world) {
+			world.lock().unwrap().entities.insert(Entity::player(
+				self.entity_id,
+				self.profile.clone(),
+				session.position,
+				session.rotation,
+			));
+		}
+		self.send_permissions().await?;
+		self.send_surroundings().await?;
+		self.handler.set_inventory(0, &self.inventory.slots).await
+	}
+
+	/// Tells the client the player's operator level and the commands they can use, both of which
+	/// depend on their permissions
+	async fn send_permissions(&mut self) -> Result<()> {
+		let level = lists::op_level(&self.state, &self.profile);
+		self.handler.set_op_level(self.entity_id, level).await?;
+		if let Some(sender) = self.sender() {
+			let commands = self.state.commands.tree(&sender, &self.state);
+			self.handler.declare_commands(&commands).await?;
+		}
+		Ok(())
+	}
+
+	/// Shows the player the difficulty, along with the time and weather in their world
+	async fn send_surroundings(&mut self) -> Result<()> {
+		let difficulty = *self.state.difficulty.read().unwrap();
+		self.handler.set_difficulty(difficulty).await?;
+		let world = self.session().world;
+		let surroundings = self.state.world(&world).map(|world| {
+			let world = world.lock().unwrap();
+			(world.age, world.time, world.weather)
+		});
+		if let Some((age, time, weather)) = surroundings {
+			self.handler.update_time(age, time).await?;
+			if weather != Weather::Clear {
+				self.handler.set_weather(weather).await?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Removes the player from their world and everyone's player list
+	fn leave(&self) {
+		let session = self.session();
+		if let Some(world) = self.state.world(&session.world) {
+			world.lock().unwrap().entities.remove(self.entity_id);
+		}
+		let players = self.state.players.read().unwrap();
+		// Whoever replaced this player after logging in from elsewhere should stay listed
+		if players.get(&self.profile.uuid).map(|p| p.entity_id) != Some(self.entity_id) {
+			return;
+		}
+		for player in players.values() {
+			if player.entity_id != self.entity_id {
+				player.send(PlayerMessage::RemoveFromPlayerList(self.profile.uuid));
+			}
+		}
+	}
+
+	/// Handles events from the client and messages from other tasks until the player leaves
+	async fn serve(&mut self, mut events: Events) -> Result<()> {
+		let mut flush = time::interval(FLUSH_INTERVAL);
+		let mut keep_alive = time::interval(KEEP_ALIVE_INTERVAL);
+		let mut save = time::interval_at(Instant::now() + SAVE_INTERVAL, SAVE_INTERVAL);
+		let timeout = Duration::from_secs(self.state.config().network.timeout);
+		loop {
+			tokio::select! {
+				event = events.recv() => match event {
+					Some(event) => {
+						self.last_seen = Instant::now();
+						self.handle_event(event).await?;
+					}
+					// The client closed the connection
+					None => break,
+				},
+				message = self.receiver.recv() => match message {
+					Some(PlayerMessage::AddToPlayerList {
+						profile,
+						gamemode,
+						ping,
+					}) => self.handler.add_to_player_list(&profile, gamemode, ping).await?,
+					Some(PlayerMessage::BlockBreakAnimation {
+						entity_id,
+						position,
+						stage,
+					}) => {
+						self.handler
+							.block_break_animation(entity_id, position, stage)
+							.await?
+					}
+					Some(PlayerMessage::BlockChanges { blocks, light }) => {
+						self.handler.block_changes(&blocks, &light).await?
+					}
+					Some(PlayerMessage::ContainerChanged { world, position }) => {
+						self.refresh_container(world, position).await?
+					}
+					Some(PlayerMessage::Difficulty(difficulty)) => {
+						self.handler.set_difficulty(difficulty).await?
+					}
+					Some(PlayerMessage::Disconnect(reason)) => {
+						self.disconnect(reason).await?;
+						break;
+					}
+					Some(PlayerMessage::Message {
+						message,
+						position,
+						sender,
+					}) => {
+						if self.chat_mode.shows(position) {
+							self.handler.send_message(&message, position, sender).await?
+						}
+					}
+					Some(PlayerMessage::Entities(updates)) => self.update_entities(updates).await?,
+					Some(PlayerMessage::Gamemode(gamemode)) => self.set_gamemode(gamemode).await?,
+					Some(PlayerMessage::Give { item, count }) => self.give(item, count).await?,
+					Some(PlayerMessage::Kill) => self.kill().await?,
+					Some(PlayerMessage::Latency { uuid, ping }) => {
+						self.handler.update_latency(uuid, ping).await?
+					}
+					Some(PlayerMessage::RemoveFromPlayerList(uuid)) => {
+						self.handler.remove_from_player_list(uuid).await?
+					}
+					Some(PlayerMessage::Save) => self.save(),
+					Some(PlayerMessage::SetSpawn(spawn)) => {
+						self.update_session(|session| session.spawn = spawn)
+					}
+					Some(PlayerMessage::Teleport {
+						world,
+						position,
+						rotation,
+					}) => {
+						let rotation = rotation.unwrap_or(self.session().rotation);
+						self.move_to(world, position, rotation, false).await?
+					}
+					Some(PlayerMessage::Time { age, time }) => {
+						self.handler.update_time(age, time).await?
+					}
+					Some(PlayerMessage::UpdatePermissions) => self.send_permissions().await?,
+					Some(PlayerMessage::ViewDistance(view_distance)) => {
+						self.update_view_distance(view_distance).await?
+					}
+					Some(PlayerMessage::Weather(weather)) => self.handler.set_weather(weather).await?,
+					None => break,
+				},
+				_ = flush.tick() => {
+					self.update_digging();
+					self.handler.flush().await?
+				}
+				_ = keep_alive.tick() => {
+					if self.last_seen.elapsed() > timeout {
+						warn!(
+							"{} timed out after not responding for {} seconds",
+							self.profile.name,
+							timeout.as_secs()
+						);
+						self.disconnect(String::from("Timed out")).await?;
+						break;
+					}
+					self.send_keep_alive().await?;
+				}
+				_ = save.tick() => self.save(),
+			}
+		}
+		Ok(())
+	}
+
+	async fn handle_event(&mut self, event: Event) -> Result<()> {
+		match event {
+			Event::KeepAlive(id) => match self.keep_alive {
+				Some((expected, sent)) if expected == id => {
+					self.keep_alive = None;
+					let rtt = sent.elapsed().as_millis() as i32;
+					// Smoothed the same way as vanilla so a single slow response doesn't stand out
+					let mut ping = 0;
+					self.update_session(|session| {
+						session.ping = (session.ping * 3 + rtt) / 4;
+						ping = session.ping;
+					});
+					for player in self.state.players.read().unwrap().values() {
+						player.send(PlayerMessage::Latency {
+							uuid: self.profile.uuid,
+							ping,
+						});
+					}
+				}
+				_ => debug!(
+					"{} answered an unexpected keep alive {}",
+					self.profile.name, id
+				),
+			},
+			Event::Chat(text) => self.handle_chat(text).await?,
+			Event::ClickWindow {
+				window,
+				slot,
+				button,
+				action,
+				mode,
+				item,
+			} => {
+				let click = Click { slot, button, mode };
+				self.handle_click(window, click, action, item).await?
+			}
+			Event::ClientSettings {
+				view_distance,
+				chat_mode,
+			} => {
+				self.chat_mode = chat_mode;
+				self.view_distance = Some(view_distance.max(0) as u8);
+				let server = self.state.config().minecraft.view_distance;
+				self.update_view_distance(server).await?
+			}
+			Event::CloseWindow(window) => self.handle_close_window(window).await?,
+			Event::CreativeInventory { slot, item } => {
+				self.handle_creative_inventory(slot, item).await?
+			}
+			Event::Dig {
+				status, position, ..
+			} => self.handle_dig(status, position).await?,
+			Event::HeldItemChange(slot) => {
+				if (0..9).contains(&slot) {
+					self.inventory.selected = slot as usize;
+				}
+			}
+			Event::Move {
+				position,
+				rotation,
+				on_ground,
+			} => self.handle_move(position, rotation, on_ground).await?,
+			Event::Place {
+				position,
+				face,
+				hand,
+				block,
+			} => self.handle_place(position, face, hand, block).await?,
+			Event::TabComplete { id, text } => {
+				let input = text.strip_prefix('/').unwrap_or(&text);
+				if let Some(sender) = self.sender() {
+					let (start, matches) = self.state.commands.suggest(input, &sender, &self.state);
+					// The client counts from the start of the text, slash included
+					let offset = text.len() - input.len();
+					let length = input.len() - start;
+					self.handler
+						.tab_complete(id, start + offset, length, &matches)
+						.await?
+				}
+			}
+			Event::Respawn if self.session().health <= 0.0 => self.respawn().await?,
+			Event::TeleportConfirm(id) => self.movement.confirm_teleport(id),
+			_ => {}
+		}
+		Ok(())
+	}
+
+	/// Validates and applies movement reported by the client, moving them back if it wasn't allowed
+	async fn handle_move(
+		&mut self,
+		position: Option<Position>,
+		rotation: Option<Rotation>,
+		on_ground: bool,
+	) -> Result<()> {
+		// Anything sent before the client saw the last teleport is out of date
+		if self.movement.awaiting_teleport() {
+			return Ok(());
+		}
+		let session = self.session();
+		if let Some(position) = position {
+			let result = match self.state.world(&session.world) {
+				Some(world) => {
+					let mut world = world.lock().unwrap();
+					// Classic clients can always fly, so they are checked as if they were in creative
+					let gamemode = if self.handler.can_always_fly() {
+						Gamemode::Creative
+					} else {
+						session.gamemode
+					};
+					self.movement.check(&mut world, position, gamemode)
+				}
+				None => Ok(()),
+			};
+			if let Err(violation) = result {
+				warn!(
+					"{} {} and was moved back ({:.2}, {:.2}, {:.2} -> {:.2}, {:.2}, {:.2})",
+					self.profile.name,
+					violation,
+					session.position.x,
+					session.position.y,
+					session.position.z,
+					position.x,
+					position.y,
+					position.z
+				);
+				let back = self.movement.position();
+				let rotation = rotation.unwrap_or(session.rotation);
+				let id = self.handler.teleport(back, rotation).await?;
+				self.movement.teleported(back, id);
+				self.update_session(|session| {
+					session.position = back;
+					session.rotation = rotation;
+				});
+				self.sync_entity();
+				return Ok(());
+			}
+		}
+		let grounded = on_ground && self.movement.grounded();
+		self.update_session(|session| {
+			if let Some(position) = position {
+				session.position = position;
+			}
+			if let Some(rotation) = rotation {
+				session.rotation = rotation;
+			}
+			session.on_ground = grounded;
+		});
+		self.sync_entity();
+		if let Some(position) = position {
+			self.handler.update_view(position).await?;
+		}
+		Ok(())
+	}
+
+	/// Changes the player's gamemode, and how they are shown in everyone's player list
+	async fn set_gamemode(&mut self, gamemode: Gamemode) -> Result<()> {
+		self.update_session(|session| session.gamemode = gamemode);
+		self.handler.set_gamemode(gamemode).await?;
+		let ping = self.session().ping;
+		for player in self.state.players.read().unwrap().values() {
+			player.send(PlayerMessage::AddToPlayerList {
+				profile: self.profile.clone(),
+				gamemode,
+				ping,
+			});
+		}
+		Ok(())
+	}
+
+	/// Keeps the player's entity in step with their session, so other players see them move
+	fn sync_entity(&self) {
+		let session = self.session();
+		if let Some(world) = self.state.world(&session.world) {
+			if let Some(entity) = world.lock().unwrap().entities.get_mut(self.entity_id) {
+				entity.position = session.position;
+				entity.rotation = session.rotation;
+				entity.on_ground = session.on_ground;
+			}
+		}
+	}
+
+	/// Shows the player what happened to the entities around them
+	async fn update_entities(&mut self, updates: Vec<EntityUpdate>) -> Result<()> {
+		for update in updates {
+			match update {
+				EntityUpdate::Spawn(entity) => match &entity.profile {
+					Some(profile) => {
+						self.handler
+							.spawn_player(entity.id, profile, entity.position, entity.rotation)
+							.await?
+					}
+					None => {
+						self.handler
+							.spawn_entity(
+								entity.id,
+								entity.uuid,
+								entity.kind,
+								entity.position,
+								entity.rotation,
+							)
+							.await?
+					}
+				},
+				EntityUpdate::Move {
+					entity_id,
+					from,
+					to,
+					rotation,
+					on_ground,
+				} => {
+					self.handler
+						.move_entity(entity_id, from, to, rotation, on_ground)
+						.await?
+				}
+				EntityUpdate::Rotate {
+					entity_id,
+					rotation,
+					on_ground,
+				} => {
+					self.handler
+						.rotate_entity(entity_id, rotation, on_ground)
+						.await?
+				}
+				EntityUpdate::Destroy(entity_ids) => {
+					self.handler.despawn_entities(&entity_ids).await?
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Sends a keep alive, giving up on measuring the last one if it was never answered
+	async fn send_keep_alive(&mut self) -> Result<()> {
+		let id = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|time| time.as_millis() as i64)
+			.unwrap_or_default();
+		self.keep_alive = Some((id, Instant::now()));
+		self.handler.keep_alive(id).await
+	}
+
+	/// Takes a copy of the player's current session
+	pub fn session(&self) -> Session {
+		self.session.read().unwrap().clone()
+	}
+
+	pub fn uuid(&self) -> Uuid {
+		self.profile.uuid
+	}
+
+	/// Changes the player's session, which other tasks see through the player's handle
+	pub fn update_session<F: FnOnce(&mut Session)>(&self, update: F) {
+		update(&mut self.session.write().unwrap());
+	}
+}
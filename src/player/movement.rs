@@ -0,0 +1,447 @@
+use ::std::fmt::{self, Display, Formatter};
+use ::tokio::time::Instant;
+
+use crate::{
+	player::{Gamemode, Position},
+	world::{collision::BoundingBox, World},
+};
+
+const PLAYER_WIDTH: f64 = 0.6;
+/// Height of a crawling or swimming player, which is the smallest space a player can fit through
+const PLAYER_HEIGHT: f64 = 0.6;
+/// Amount the player's hitbox is shrunk by when checking for collisions, to allow for rounding
+const COLLISION_MARGIN: f64 = 0.05;
+/// How close a block under the player has to be for them to be standing on it
+const SUPPORT_MARGIN: f64 = 0.1;
+/// Horizontal blocks per second a player may move at on foot, which is sprint jumping at about 7.1
+/// with some to spare, and while flying
+const WALK_SPEED: f64 = 8.0;
+const FLY_SPEED: f64 = 25.0;
+/// Blocks per second a player may rise at on foot, which covers running up stairs and jumping, and
+/// while flying
+const CLIMB_SPEED: f64 = 7.0;
+const FLY_CLIMB_SPEED: f64 = 12.0;
+/// Distance a player may cover at once, to allow for packets arriving in bursts
+const MOVE_BURST: f64 = 10.0;
+/// Height a player may rise by at once, which is a little more than a jump
+const CLIMB_BURST: f64 = 2.0;
+/// Furthest a player may move in a single packet, which mostly limits how far they can fall
+const MAX_MOVE: f64 = 100.0;
+/// Furthest a player can get above the last block they were supported by by jumping
+const MAX_JUMP_HEIGHT: f64 = 1.3;
+/// Blocks per tick a player rises at when they jump
+const JUMP_VELOCITY: f64 = 0.42;
+/// Blocks per tick a player's vertical speed drops by each tick in midair, and the fraction of it
+/// left after air resistance, as in vanilla
+const GRAVITY: f64 = 0.08;
+const DRAG: f64 = 0.98;
+/// How much slower than gravity allows a player may fall in a single move, to allow for rounding
+const FALL_TOLERANCE: f64 = 0.1;
+/// Moves a player may make in midair while falling slower than gravity allows
+const MAX_HOVER: u32 = 10;
+/// Distance between the points checked along the path a player moved
+const SWEEP_STEP: f64 = 0.25;
+/// Coordinates past this are outside the world border
+const WORLD_LIMIT: f64 = 30_000_000.0;
+
+/// A reason a player's movement was rejected
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Violation {
+	Collision,
+	Flying,
+	InvalidPosition,
+	TooFast,
+}
+
+impl Display for Violation {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		f.write_str(match self {
+			Self::Collision => "moved into a block",
+			Self::Flying => "was flying",
+			Self::InvalidPosition => "sent an invalid position",
+			Self::TooFast => "moved too quickly",
+		})
+	}
+}
+
+/// Checks the movement a client reports against where the server thinks they could have gone
+pub struct MovementValidator {
+	/// Last position the player was allowed to move to
+	position: Position,
+	/// Horizontal distance the player may still cover
+	allowance: f64,
+	/// Height the player may still rise by
+	climb_allowance: f64,
+	updated: Instant,
+	/// Height the player was last supported by a block at
+	ground: f64,
+	/// Whether the player was last standing on a block
+	grounded: bool,
+	/// Blocks per tick the player should be moving up at if they are in midair, going by gravity
+	velocity: Option<f64>,
+	/// Number of moves the player has fallen slower than gravity allows since they were last
+	/// supported
+	hover: u32,
+	/// Teleport the player must confirm before their movement is accepted again
+	teleport: Option<i32>,
+}
+
+impl MovementValidator {
+	pub fn new(position: Position) -> Self {
+		Self {
+			position,
+			allowance: MOVE_BURST,
+			climb_allowance: CLIMB_BURST,
+			updated: Instant::now(),
+			ground: position.y,
+			grounded: false,
+			velocity: None,
+			hover: 0,
+			teleport: None,
+		}
+	}
+
+	/// Whether movement is being ignored until the client confirms a teleport
+	pub fn awaiting_teleport(&self) -> bool {
+		self.teleport.is_some()
+	}
+
+	pub fn confirm_teleport(&mut self, id: i32) {
+		if self.teleport == Some(id) {
+			self.teleport = None;
+		}
+	}
+
+	/// Whether the player was standing on a block at their last accepted position
+	pub fn grounded(&self) -> bool {
+		self.grounded
+	}
+
+	/// Last position the player was allowed to move to
+	pub fn position(&self) -> Position {
+		self.position
+	}
+
+	/// Starts over from a position the server moved the player to
+	pub fn teleported(&mut self, position: Position, id: Option<i32>) {
+		*self = Self::new(position);
+		self.teleport = id;
+	}
+
+	/// Checks a move to a new position, accepting it if nothing was wrong with it
+	pub fn check(
+		&mut self,
+		world: &mut World,
+		to: Position,
+		gamemode: Gamemode,
+	) -> Result<(), Violation> {
+		self.check_at(world, to, gamemode, Instant::now())
+	}
+
+	fn check_at(
+		&mut self,
+		world: &mut World,
+		to: Position,
+		gamemode: Gamemode,
+		now: Instant,
+	) -> Result<(), Violation> {
+		if !valid(to) {
+			return Err(Violation::InvalidPosition);
+		}
+		let (speed, climb_speed) = if gamemode.can_fly() {
+			(FLY_SPEED, FLY_CLIMB_SPEED)
+		} else {
+			(WALK_SPEED, CLIMB_SPEED)
+		};
+		let elapsed = now.duration_since(self.updated).as_secs_f64();
+		self.allowance = (self.allowance + elapsed * speed).min(MOVE_BURST);
+		self.climb_allowance = (self.climb_allowance + elapsed * climb_speed).min(CLIMB_BURST);
+		self.updated = now;
+		let (dx, dy, dz) = (
+			to.x - self.position.x,
+			to.y - self.position.y,
+			to.z - self.position.z,
+		);
+		let horizontal = (dx * dx + dz * dz).sqrt();
+		let distance = (dx * dx + dy * dy + dz * dz).sqrt();
+		if horizontal > self.allowance || dy > self.climb_allowance || distance > MAX_MOVE {
+			return Err(Violation::TooFast);
+		}
+		self.allowance -= horizontal;
+		self.climb_allowance -= dy.max(0.0);
+		// Players inside a block are let out of it rather than being stuck there
+		if gamemode != Gamemode::Spectator && !world.collides(&hitbox(self.position)) {
+			let steps = (distance / SWEEP_STEP).ceil() as usize;
+			for step in 1..=steps {
+				let t = step as f64 / steps as f64;
+				let point = Position::new(
+					self.position.x + dx * t,
+					self.position.y + dy * t,
+					self.position.z + dz * t,
+				);
+				if world.collides(&hitbox(point)) {
+					return Err(Violation::Collision);
+				}
+			}
+		}
+		// Only blocks under the player hold them up, apart from ones that can be climbed
+		let feet = BoundingBox {
+			min: (
+				to.x - PLAYER_WIDTH / 2.0,
+				to.y - SUPPORT_MARGIN,
+				to.z - PLAYER_WIDTH / 2.0,
+			),
+			max: (to.x + PLAYER_WIDTH / 2.0, to.y, to.z + PLAYER_WIDTH / 2.0),
+		};
+		let grounded = world.touches(&feet);
+		if grounded || gamemode.can_fly() || world.climbable(&hitbox(to)) {
+			self.ground = to.y;
+			self.velocity = None;
+			self.hover = 0;
+		} else {
+			if to.y > self.ground + MAX_JUMP_HEIGHT {
+				return Err(Violation::Flying);
+			}
+			let velocity = match self.velocity {
+				Some(velocity) => (velocity - GRAVITY) * DRAG,
+				// The player just left the ground, either by jumping or stepping off an edge
+				None => dy.min(JUMP_VELOCITY),
+			};
+			if dy > velocity + FALL_TOLERANCE {
+				self.hover += 1;
+				if self.hover > MAX_HOVER {
+					return Err(Violation::Flying);
+				}
+			}
+			self.velocity = Some(velocity);
+		}
+		self.grounded = grounded;
+		self.position = to;
+		Ok(())
+	}
+}
+
+/// The part of the player's hitbox that is checked for collisions
+fn hitbox(position: Position) -> BoundingBox {
+	BoundingBox::standing(
+		position.x,
+		position.y,
+		position.z,
+		PLAYER_WIDTH,
+		PLAYER_HEIGHT,
+	)
+	.grow(-COLLISION_MARGIN)
+}
+
+fn valid(position: Position) -> bool {
+	[position.x, position.y, position.z]
+		.iter()
+		.all(|v| v.is_finite() && v.abs() < WORLD_LIMIT)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		config::WorldConfig,
+		item::ItemRegistry,
+		world::{dimension::Dimension, registry::BlockRegistry},
+	};
+	use ::std::{sync::Arc, time::Duration};
+
+	const TICK: Duration = Duration::from_millis(50);
+	const STONE: u16 = 1;
+
+	/// A flat world whose ground is at y = 4
+	fn world(name: &str) -> World {
+		let config = WorldConfig {
+			name: String::from(name),
+			dimension: Dimension::Overworld,
+			directory: ::std::env::temp_dir()
+				.join(format!("basalt-movement-{}", name))
+				.to_string_lossy()
+				.into_owned(),
+			generator: String::from("flat"),
+			layers: None,
+			seed: None,
+			spawn: [0, 4, 0],
+		};
+		World::new(
+			&config,
+			Arc::new(BlockRegistry::new()),
+			Arc::new(ItemRegistry::new()),
+		)
+		.unwrap()
+	}
+
+	/// Moves a player by the same amount every tick, returning how many moves were accepted
+	fn walk(
+		world: &mut World,
+		from: Position,
+		step: (f64, f64, f64),
+		moves: usize,
+		gamemode: Gamemode,
+	) -> Result<(), (usize, Violation)> {
+		let mut validator = MovementValidator::new(from);
+		let start = validator.updated;
+		let mut position = from;
+		for i in 1..=moves {
+			position = Position::new(
+				position.x + step.0,
+				position.y + step.1,
+				position.z + step.2,
+			);
+			validator
+				.check_at(world, position, gamemode, start + TICK * i as u32)
+				.map_err(|violation| (i, violation))?;
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn limits_speed() {
+		let mut world = world("speed");
+		let ground = Position::new(0.5, 4.0, 0.5);
+		// Sprinting is about 0.28 blocks a tick
+		assert!(walk(
+			&mut world,
+			ground,
+			(0.28, 0.0, 0.0),
+			200,
+			Gamemode::Survival
+		)
+		.is_ok());
+		let (_, violation) = walk(
+			&mut world,
+			ground,
+			(0.56, 0.0, 0.0),
+			200,
+			Gamemode::Survival,
+		)
+		.unwrap_err();
+		assert_eq!(violation, Violation::TooFast);
+		assert!(walk(
+			&mut world,
+			ground,
+			(0.56, 0.0, 0.0),
+			200,
+			Gamemode::Creative
+		)
+		.is_ok());
+		// Teleporting up in one go is too fast even while flying
+		let mut validator = MovementValidator::new(ground);
+		assert_eq!(
+			validator.check(
+				&mut world,
+				Position::new(0.5, 60.0, 0.5),
+				Gamemode::Creative
+			),
+			Err(Violation::TooFast)
+		);
+	}
+
+	#[test]
+	fn allows_sprint_jumping() {
+		let mut world = world("jumping");
+		let mut position = Position::new(0.5, 4.0, 0.5);
+		let mut validator = MovementValidator::new(position);
+		let start = validator.updated;
+		let mut velocity = JUMP_VELOCITY;
+		for i in 1..=200 {
+			position.x += 0.35;
+			position.y += velocity;
+			velocity = (velocity - GRAVITY) * DRAG;
+			if position.y <= 4.0 {
+				position.y = 4.0;
+				velocity = JUMP_VELOCITY;
+			}
+			let now = start + TICK * i;
+			assert_eq!(
+				validator.check_at(&mut world, position, Gamemode::Survival, now),
+				Ok(())
+			);
+		}
+	}
+
+	#[test]
+	fn walls_are_not_ground() {
+		let mut world = world("wall");
+		for y in 4..40 {
+			world.set_block(1, y, 0, STONE);
+		}
+		let beside = Position::new(0.65, 4.0, 0.5);
+		let (_, violation) =
+			walk(&mut world, beside, (0.0, 0.2, 0.0), 100, Gamemode::Survival).unwrap_err();
+		assert_eq!(violation, Violation::Flying);
+		assert!(walk(&mut world, beside, (0.0, 0.2, 0.0), 100, Gamemode::Creative).is_ok());
+		// Walking into the wall isn't allowed either
+		let (_, violation) =
+			walk(&mut world, beside, (0.2, 0.0, 0.0), 5, Gamemode::Survival).unwrap_err();
+		assert_eq!(violation, Violation::Collision);
+	}
+
+	#[test]
+	fn ladders_can_be_climbed() {
+		let mut world = world("ladder");
+		let ladder = BlockRegistry::new()
+			.world()
+			.default_state("minecraft:ladder")
+			.unwrap();
+		for y in 4..40 {
+			world.set_block(0, y, 0, ladder);
+		}
+		let on_ladder = Position::new(0.5, 4.0, 0.5);
+		assert!(walk(
+			&mut world,
+			on_ladder,
+			(0.0, 0.12, 0.0),
+			200,
+			Gamemode::Survival
+		)
+		.is_ok());
+	}
+
+	#[test]
+	fn players_fall_at_the_speed_of_gravity() {
+		let mut world = world("falling");
+		let air = Position::new(0.5, 100.0, 0.5);
+		let mut validator = MovementValidator::new(air);
+		let start = validator.updated;
+		let mut position = air;
+		let mut velocity = 0.0;
+		for i in 1..=40 {
+			velocity = (velocity - GRAVITY) * DRAG;
+			position.y += velocity;
+			let now = start + TICK * i;
+			assert_eq!(
+				validator.check_at(&mut world, position, Gamemode::Survival, now),
+				Ok(())
+			);
+		}
+		// Floating down slowly gets noticed
+		let (moves, violation) =
+			walk(&mut world, air, (0.0, -0.05, 0.0), 100, Gamemode::Survival).unwrap_err();
+		assert_eq!(violation, Violation::Flying);
+		assert!(moves < 20);
+		// So does hovering
+		let (_, violation) =
+			walk(&mut world, air, (0.1, 0.0, 0.0), 100, Gamemode::Survival).unwrap_err();
+		assert_eq!(violation, Violation::Flying);
+	}
+
+	#[test]
+	fn rejects_invalid_positions() {
+		let mut world = world("invalid");
+		let mut validator = MovementValidator::new(Position::new(0.5, 4.0, 0.5));
+		for position in &[
+			Position::new(f64::NAN, 4.0, 0.5),
+			Position::new(0.5, f64::INFINITY, 0.5),
+			Position::new(WORLD_LIMIT, 4.0, 0.5),
+		] {
+			assert_eq!(
+				validator.check(&mut world, *position, Gamemode::Creative),
+				Err(Violation::InvalidPosition)
+			);
+		}
+	}
+}
@@ -0,0 +1,107 @@
+use ::log::info;
+use ::std::io::Result;
+
+use crate::{
+	entity::Entity, modern::types::chat::ChatComponent, player::PlayerMessage,
+	types::MessagePosition,
+};
+
+use super::{Player, Position, Rotation};
+
+impl Player {
+	/// The world and position the player respawns at, which is the spawn of the default world unless
+	/// they have a spawn point of their own
+	pub(super) fn spawn_point(&self) -> (String, Position) {
+		if let Some((world, block)) = self.session().spawn {
+			if self.state.world(&world).is_some() {
+				let position =
+					Position::new(block.x as f64 + 0.5, block.y as f64, block.z as f64 + 0.5);
+				return (world, position);
+			}
+		}
+		let world = self.state.default_world.clone();
+		let position = self.state.worlds[&world].lock().unwrap().spawn_position();
+		(world, position)
+	}
+
+	/// Moves the player anywhere, including into another world. Modern clients have to be respawned
+	/// to change world, which also happens when `respawn` is set.
+	pub(super) async fn move_to(
+		&mut self,
+		world: String,
+		position: Position,
+		rotation: Rotation,
+		respawn: bool,
+	) -> Result<()> {
+		let session = self.session();
+		if world != session.world || respawn {
+			self.stop_digging();
+			self.close_container().await?;
+			if let Some(old) = self.state.world(&session.world) {
+				old.lock().unwrap().entities.remove(self.entity_id);
+			}
+			self.update_session(|session| {
+				session.world = world.clone();
+				session.position = position;
+				session.rotation = rotation;
+			});
+			let session = self.session();
+			self.handler.respawn(&session).await?;
+			if let Some(new) = self.state.world(&world) {
+				new.lock().unwrap().entities.insert(Entity::player(
+					self.entity_id,
+					self.profile.clone(),
+					position,
+					rotation,
+				));
+			}
+			// The client forgets these along with the rest of the world it was in
+			self.send_permissions().await?;
+			self.send_surroundings().await?;
+			self.handler.set_inventory(0, &self.inventory.slots).await?;
+		} else {
+			self.update_session(|session| {
+				session.position = position;
+				session.rotation = rotation;
+			});
+			self.sync_entity();
+		}
+		let id = self.handler.teleport(position, rotation).await?;
+		self.movement.teleported(position, id);
+		self.handler.update_view(position).await
+	}
+
+	/// Brings the player back to life at their spawn point
+	pub(super) async fn respawn(&mut self) -> Result<()> {
+		let (world, position) = self.spawn_point();
+		self.update_session(|session| {
+			session.health = 20.0;
+			session.food = 20;
+			session.saturation = 5.0;
+		});
+		self.move_to(world, position, Rotation::default(), true)
+			.await
+	}
+
+	/// Kills the player, who respawns once they click the button on the death screen. Their inventory
+	/// is kept, as there are no item entities to drop it as yet.
+	pub(super) async fn kill(&mut self) -> Result<()> {
+		if !self.handler.has_health() {
+			return self.respawn().await;
+		}
+		self.stop_digging();
+		self.update_session(|session| session.health = 0.0);
+		let message = ChatComponent::text(format!("{} fell out of the world", self.profile.name));
+		info!("{}", message.to_plain());
+		for player in self.state.players.read().unwrap().values() {
+			player.send(PlayerMessage::Message {
+				message: message.clone(),
+				position: MessagePosition::System,
+				sender: None,
+			});
+		}
+		let session = self.session();
+		self.handler.update_health(&session).await?;
+		self.handler.death_screen(self.entity_id, &message).await
+	}
+}
@@ -0,0 +1,315 @@
+use ::log::debug;
+use ::std::io::Result;
+
+use crate::{
+	inventory::{Click, ContainerKind, CURSOR_SLOT, CURSOR_WINDOW, PLAYER_SLOTS},
+	item,
+	modern::types::chat::ChatComponent,
+	player::{
+		building::{in_reach, PLACE_REACH},
+		Gamemode, Player, PlayerMessage,
+	},
+	state::ServerState,
+	types::Slot,
+	world::BlockPos,
+};
+
+/// Largest window ID before they start again from 1, as in vanilla
+const MAX_WINDOW_ID: u8 = 100;
+
+/// A container window the player has open
+#[derive(Clone, Copy, Debug)]
+pub struct OpenWindow {
+	id: u8,
+	kind: ContainerKind,
+	position: BlockPos,
+}
+
+impl Player {
+	/// Opens the window of the container at a position, closing any window that was already open
+	pub(super) async fn open_container(
+		&mut self,
+		position: BlockPos,
+		kind: ContainerKind,
+	) -> Result<()> {
+		self.close_container().await?;
+		self.window_id = self.window_id % MAX_WINDOW_ID + 1;
+		let open = OpenWindow {
+			id: self.window_id,
+			kind,
+			position,
+		};
+		let world = self.session().world;
+		let contents = match with_container(&self.state, &world, open, |shared| shared.cloned()) {
+			Some(contents) => contents.unwrap_or_else(|| self.inventory.table.clone()),
+			None => return Ok(()),
+		};
+		self.window = Some(open);
+		self.handler
+			.open_window(
+				open.id,
+				kind,
+				&ChatComponent::translate(kind.title(), Vec::new()),
+			)
+			.await?;
+		self.handler
+			.set_inventory(open.id, &self.inventory.window(Some(&contents)))
+			.await
+	}
+
+	/// Closes the container window the player has open, if there is one
+	pub(super) async fn close_container(&mut self) -> Result<()> {
+		if let Some(open) = self.window.take() {
+			self.handler.close_window(open.id).await?;
+			self.put_away();
+		}
+		Ok(())
+	}
+
+	/// Puts items into the player's inventory in stacks no bigger than the item allows
+	pub(super) async fn give(&mut self, item: i32, count: i32) -> Result<()> {
+		let max = item::max_stack(item) as i32;
+		let mut remaining = count;
+		let mut lost = 0;
+		while remaining > 0 {
+			let size = remaining.min(max);
+			remaining -= size;
+			let stack = Slot {
+				item,
+				count: size as i8,
+				nbt: None,
+			};
+			if let Some(left) = self.inventory.give(stack) {
+				lost += left.count as i32;
+			}
+		}
+		if lost > 0 {
+			// There are no item entities to drop them as yet
+			debug!(
+				"{} was given {} items that didn't fit in their inventory",
+				self.profile.name, lost
+			);
+		}
+		self.handler.set_inventory(0, &self.inventory.slots).await
+	}
+
+	/// Puts the items on the cursor and in the crafting grid back into the inventory
+	pub(super) fn put_away(&mut self) {
+		let lost = self.inventory.close();
+		if !lost.is_empty() {
+			// There are no item entities to drop them as yet
+			debug!(
+				"{} had {} stacks that didn't fit in their inventory",
+				self.profile.name,
+				lost.len()
+			);
+		}
+	}
+
+	pub(super) async fn handle_close_window(&mut self, window: u8) -> Result<()> {
+		match self.window {
+			Some(open) if open.id == window => self.window = None,
+			_ if window == 0 => {}
+			_ => return Ok(()),
+		}
+		self.put_away();
+		self.handler.set_inventory(0, &self.inventory.slots).await
+	}
+
+	/// Applies a click in a window on the server, letting the client know whether it predicted the
+	/// outcome correctly and correcting it if not
+	pub(super) async fn handle_click(
+		&mut self,
+		window: u8,
+		click: Click,
+		action: i16,
+		item: Option<Slot>,
+	) -> Result<()> {
+		let session = self.session();
+		// Spectators can look inside containers but not move anything
+		if session.gamemode == Gamemode::Spectator || !(0..=6).contains(&click.mode) {
+			return self.reject_click(window, action, click).await;
+		}
+		let creative = session.gamemode == Gamemode::Creative;
+		let recipes = &self.state.recipes;
+		let item = item.as_ref();
+		let (result, contents) = if window == 0 {
+			(
+				self.inventory.click(None, click, item, creative, recipes),
+				None,
+			)
+		} else {
+			let open = match self.window {
+				Some(open) if open.id == window => open,
+				// Clicks can still arrive for a window the server has already closed
+				_ => return Ok(()),
+			};
+			let inventory = &mut self.inventory;
+			let clicked = if in_reach(&session, open.position, PLACE_REACH) {
+				with_container(&self.state, &session.world, open, |shared| match shared {
+					Some(container) => {
+						let result = inventory.click(
+							Some((open.kind, container)),
+							click,
+							item,
+							creative,
+							recipes,
+						);
+						(result, container.clone())
+					}
+					None => inventory.click_table(click, item, creative, recipes),
+				})
+			} else {
+				None
+			};
+			match clicked {
+				Some((result, contents)) => (result, Some(contents)),
+				None => {
+					debug!(
+						"{} clicked in a container they can no longer reach",
+						self.profile.name
+					);
+					self.handler
+						.window_confirmation(window, action, false)
+						.await?;
+					return self.close_container().await;
+				}
+			}
+		};
+		if !result.valid {
+			return self.reject_click(window, action, click).await;
+		}
+		self.handler
+			.window_confirmation(window, action, true)
+			.await?;
+		if let (Some(open), Some(_)) = (self.window, &contents) {
+			if open.kind.is_shared() && !result.changed.is_empty() {
+				self.notify_viewers(&session.world, open.position);
+			}
+		}
+		let slots = self.inventory.window(contents.as_deref());
+		for slot in result.changed {
+			self.handler
+				.set_slot(window, slot, slots[slot as usize].as_ref())
+				.await?;
+		}
+		if result.cursor_changed {
+			self.send_cursor().await?;
+		}
+		Ok(())
+	}
+
+	/// Tells the client a click was refused, sending it what the window really holds so it can undo
+	/// whatever it predicted
+	async fn reject_click(&mut self, window: u8, action: i16, click: Click) -> Result<()> {
+		debug!(
+			"{} made a click the server didn't agree with ({:?})",
+			self.profile.name, click
+		);
+		self.handler
+			.window_confirmation(window, action, false)
+			.await?;
+		let contents = match self.window {
+			Some(open) if open.id == window => {
+				match with_container(&self.state, &self.session().world, open, |shared| {
+					shared.cloned()
+				}) {
+					Some(contents) => contents.or_else(|| Some(self.inventory.table.clone())),
+					None => return self.close_container().await,
+				}
+			}
+			_ if window == 0 => None,
+			_ => return Ok(()),
+		};
+		let slots = self.inventory.window(contents.as_deref());
+		self.handler.set_inventory(window, &slots).await?;
+		self.send_cursor().await
+	}
+
+	/// Sets a slot of the player's inventory to an item a creative player picked
+	pub(super) async fn handle_creative_inventory(
+		&mut self,
+		slot: i16,
+		item: Option<Slot>,
+	) -> Result<()> {
+		if self.session().gamemode != Gamemode::Creative {
+			return self.handler.set_inventory(0, &self.inventory.slots).await;
+		}
+		// Items thrown out of the creative inventory disappear until there are item entities
+		if slot == CURSOR_SLOT {
+			return Ok(());
+		}
+		if !self.inventory.set(slot, item) && (0..PLAYER_SLOTS as i16).contains(&slot) {
+			let item = self.inventory.slots[slot as usize].clone();
+			self.handler.set_slot(0, slot, item.as_ref()).await?;
+		}
+		Ok(())
+	}
+
+	/// Shows the player the current contents of a container if they have it open
+	pub(super) async fn refresh_container(
+		&mut self,
+		world: String,
+		position: BlockPos,
+	) -> Result<()> {
+		let open = match self.window {
+			Some(open) if open.position == position && self.session().world == world => open,
+			_ => return Ok(()),
+		};
+		match with_container(&self.state, &world, open, |shared| shared.cloned()) {
+			Some(Some(contents)) => {
+				self.handler
+					.set_inventory(open.id, &self.inventory.window(Some(&contents)))
+					.await
+			}
+			Some(None) => Ok(()),
+			// The container was broken while the player was looking inside it
+			None => self.close_container().await,
+		}
+	}
+
+	/// Lets everyone else in a world know that a container there changed
+	pub(super) fn notify_viewers(&self, world: &str, position: BlockPos) {
+		for player in self.state.players.read().unwrap().values() {
+			if player.entity_id != self.entity_id && player.session().world == world {
+				player.send(PlayerMessage::ContainerChanged {
+					world: world.to_owned(),
+					position,
+				});
+			}
+		}
+	}
+
+	async fn send_cursor(&mut self) -> Result<()> {
+		self.handler
+			.set_slot(CURSOR_WINDOW, CURSOR_SLOT, self.inventory.cursor.as_ref())
+			.await
+	}
+}
+
+/// Runs a function on the contents of an open container as long as the block it belongs to is still
+/// there, where containers whose items aren't kept in the world have no contents
+fn with_container<R, F: FnOnce(Option<&mut Vec<Option<Slot>>>) -> R>(
+	state: &ServerState,
+	world: &str,
+	open: OpenWindow,
+	f: F,
+) -> Option<R> {
+	let world = state.world(world)?;
+	let mut world = world.lock().unwrap();
+	let position = open.position;
+	let block = world.get_block(position.x, position.y, position.z);
+	let kind = state
+		.blocks
+		.world()
+		.get(block)
+		.and_then(|block| ContainerKind::of_block(block.name));
+	if kind != Some(open.kind) {
+		return None;
+	}
+	if open.kind.is_shared() {
+		Some(f(Some(world.container(position, open.kind.size()))))
+	} else {
+		Some(f(None))
+	}
+}
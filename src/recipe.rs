@@ -0,0 +1,543 @@
+use ::log::{debug, warn};
+use ::serde_json::Value;
+use ::std::{
+	collections::{BTreeMap, HashMap},
+	error::Error,
+	fs,
+	path::Path,
+};
+
+use crate::{
+	item::ItemRegistry,
+	types::{BasaltError, Slot},
+};
+
+include!(concat!(env!("OUT_DIR"), "/datapack.rs"));
+
+/// How many tags deep a tag may refer to other tags before it is assumed to refer to itself
+const MAX_TAG_DEPTH: usize = 16;
+
+/// Items any one of which can fill a place in a recipe, where an empty ingredient is an empty slot
+pub type Ingredient = Vec<i32>;
+
+pub enum RecipeKind {
+	/// Ingredients laid out in a pattern, which may also be mirrored
+	Shaped {
+		width: usize,
+		height: usize,
+		/// Ingredients of the pattern, row by row
+		ingredients: Vec<Ingredient>,
+	},
+	/// Ingredients put anywhere in the grid
+	Shapeless { ingredients: Vec<Ingredient> },
+	Smelting {
+		ingredient: Ingredient,
+		experience: f32,
+		/// Ticks the item takes to smelt
+		cooking_time: i32,
+	},
+}
+
+impl RecipeKind {
+	/// Name vanilla gives the recipe type
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::Shaped { .. } => "minecraft:crafting_shaped",
+			Self::Shapeless { .. } => "minecraft:crafting_shapeless",
+			Self::Smelting { .. } => "minecraft:smelting",
+		}
+	}
+}
+
+pub struct Recipe {
+	pub id: String,
+	/// Recipes in the same group are shown together in the recipe book
+	pub group: String,
+	pub kind: RecipeKind,
+	pub result: Slot,
+}
+
+impl Recipe {
+	/// Whether the items in a crafting grid make this recipe
+	fn matches(&self, grid: &[Option<i32>], width: usize) -> bool {
+		match &self.kind {
+			RecipeKind::Shaped {
+				width: pattern_width,
+				height: pattern_height,
+				ingredients,
+			} => {
+				let (items, grid_width) = trim(grid, width);
+				let grid_height = if grid_width == 0 {
+					0
+				} else {
+					items.len() / grid_width
+				};
+				if grid_width != *pattern_width || grid_height != *pattern_height {
+					return false;
+				}
+				let fits = |mirrored: bool| {
+					items.iter().enumerate().all(|(index, item)| {
+						let (x, y) = (index % grid_width, index / grid_width);
+						let x = if mirrored { grid_width - 1 - x } else { x };
+						accepts(&ingredients[y * grid_width + x], *item)
+					})
+				};
+				fits(false) || fits(true)
+			}
+			RecipeKind::Shapeless { ingredients } => {
+				let items: Vec<i32> = grid.iter().flatten().copied().collect();
+				items.len() == ingredients.len()
+					&& assign(&items, ingredients, &mut vec![false; ingredients.len()])
+			}
+			RecipeKind::Smelting { .. } => false,
+		}
+	}
+}
+
+/// Whether an item, or an empty slot, can stand in for an ingredient
+fn accepts(ingredient: &[i32], item: Option<i32>) -> bool {
+	match item {
+		Some(item) => ingredient.contains(&item),
+		None => ingredient.is_empty(),
+	}
+}
+
+/// Cuts the empty rows and columns from around the items in a grid, returning what is left along
+/// with its width
+fn trim(grid: &[Option<i32>], width: usize) -> (Vec<Option<i32>>, usize) {
+	let filled: Vec<(usize, usize)> = grid
+		.iter()
+		.enumerate()
+		.filter(|(_, item)| item.is_some())
+		.map(|(index, _)| (index % width, index / width))
+		.collect();
+	let (left, right, top, bottom) = match (
+		filled.iter().map(|(x, _)| *x).min(),
+		filled.iter().map(|(x, _)| *x).max(),
+		filled.iter().map(|(_, y)| *y).min(),
+		filled.iter().map(|(_, y)| *y).max(),
+	) {
+		(Some(left), Some(right), Some(top), Some(bottom)) => (left, right, top, bottom),
+		_ => return (Vec::new(), 0),
+	};
+	let mut items = Vec::new();
+	for y in top..=bottom {
+		for x in left..=right {
+			items.push(grid[y * width + x]);
+		}
+	}
+	(items, right - left + 1)
+}
+
+/// Finds a way to give each item its own ingredient, trying every option since ingredients overlap
+fn assign(items: &[i32], ingredients: &[Ingredient], used: &mut Vec<bool>) -> bool {
+	let (item, rest) = match items.split_first() {
+		Some(split) => split,
+		None => return true,
+	};
+	for (index, ingredient) in ingredients.iter().enumerate() {
+		if !used[index] && ingredient.contains(item) {
+			used[index] = true;
+			if assign(rest, ingredients, used) {
+				return true;
+			}
+			used[index] = false;
+		}
+	}
+	false
+}
+
+/// Recipes loaded from the data pack bundled with the server, along with any in the configured data
+/// directory, which is laid out like the `data` directory of a vanilla data pack
+pub struct RecipeRegistry {
+	recipes: Vec<Recipe>,
+}
+
+impl RecipeRegistry {
+	pub fn load(directory: &str, items: &ItemRegistry) -> Result<Self, Box<dyn Error>> {
+		let mut files: BTreeMap<String, String> = BUNDLED
+			.iter()
+			.map(|(name, contents)| (name.to_string(), contents.to_string()))
+			.collect();
+		let directory = Path::new(directory);
+		if directory.is_dir() {
+			read_directory(directory, directory, &mut files)?;
+		}
+		let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+		let mut recipes = BTreeMap::new();
+		for (name, contents) in &files {
+			let value: Value = ::serde_json::from_str(contents).map_err(|e| {
+				BasaltError::new(format!("Couldn't read {} from the data pack: {}", name, e))
+			})?;
+			let parts: Vec<&str> = name.trim_end_matches(".json").split('/').collect();
+			match parts.as_slice() {
+				[namespace, "recipes", path @ ..] => {
+					recipes.insert(format!("{}:{}", namespace, path.join("/")), value);
+				}
+				[namespace, "tags", "items", path @ ..] => {
+					let values = value["values"].as_array().into_iter().flatten();
+					let tag = tags
+						.entry(format!("{}:{}", namespace, path.join("/")))
+						.or_default();
+					// Tags are added to unless they ask to be replaced, as they are in vanilla
+					if value["replace"].as_bool().unwrap_or(false) {
+						tag.clear();
+					}
+					tag.extend(values.filter_map(|value| value.as_str()).map(String::from));
+				}
+				_ => {}
+			}
+		}
+		let mut registry = Self {
+			recipes: Vec::new(),
+		};
+		for (id, value) in recipes {
+			match parse_recipe(&id, &value, items, &tags) {
+				Ok(Some(recipe)) => registry.recipes.push(recipe),
+				Ok(None) => debug!(
+					"Skipped recipe {}, which is of a type that isn't supported",
+					id
+				),
+				Err(reason) => warn!("Skipped recipe {}: {}", id, reason),
+			}
+		}
+		Ok(registry)
+	}
+
+	pub fn recipes(&self) -> &[Recipe] {
+		&self.recipes
+	}
+
+	/// Finds what the items in a crafting grid make, if anything
+	pub fn craft(&self, grid: &[Option<Slot>], width: usize) -> Option<Slot> {
+		let items: Vec<Option<i32>> = grid
+			.iter()
+			.map(|slot| slot.as_ref().map(|slot| slot.item))
+			.collect();
+		if items.iter().all(Option::is_none) {
+			return None;
+		}
+		self.recipes
+			.iter()
+			.find(|recipe| recipe.matches(&items, width))
+			.map(|recipe| recipe.result.clone())
+	}
+}
+
+/// Reads every file in a directory and those inside it, keyed by their path from the root
+fn read_directory(
+	root: &Path,
+	directory: &Path,
+	files: &mut BTreeMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+	for entry in fs::read_dir(directory)? {
+		let path = entry?.path();
+		if path.is_dir() {
+			read_directory(root, &path, files)?;
+		} else if path
+			.extension()
+			.map_or(false, |extension| extension == "json")
+		{
+			let name = path
+				.strip_prefix(root)?
+				.iter()
+				.filter_map(|part| part.to_str())
+				.collect::<Vec<_>>()
+				.join("/");
+			files.insert(name, fs::read_to_string(&path)?);
+		}
+	}
+	Ok(())
+}
+
+/// Turns a recipe from a data pack into a recipe, returning `None` for types that aren't supported
+fn parse_recipe(
+	id: &str,
+	value: &Value,
+	items: &ItemRegistry,
+	tags: &HashMap<String, Vec<String>>,
+) -> Result<Option<Recipe>, String> {
+	let group = value["group"].as_str().unwrap_or_default().to_owned();
+	let kind = match value["type"].as_str().unwrap_or_default() {
+		"minecraft:crafting_shaped" => {
+			let pattern: Vec<&str> = value["pattern"]
+				.as_array()
+				.ok_or("it has no pattern")?
+				.iter()
+				.filter_map(|row| row.as_str())
+				.collect();
+			let width = pattern.iter().map(|row| row.len()).max().unwrap_or(0);
+			let height = pattern.len();
+			if width == 0 || width > 3 || height > 3 {
+				return Err(String::from("its pattern doesn't fit in a crafting grid"));
+			}
+			let mut ingredients = Vec::with_capacity(width * height);
+			for row in pattern {
+				for index in 0..width {
+					ingredients.push(match row.chars().nth(index).unwrap_or(' ') {
+						' ' => Vec::new(),
+						key => ingredient(&value["key"][key.to_string()], items, tags)
+							.ok_or_else(|| format!("nothing can be used for '{}'", key))?,
+					});
+				}
+			}
+			RecipeKind::Shaped {
+				width,
+				height,
+				ingredients,
+			}
+		}
+		"minecraft:crafting_shapeless" => {
+			let ingredients = value["ingredients"]
+				.as_array()
+				.ok_or("it has no ingredients")?
+				.iter()
+				.map(|value| ingredient(value, items, tags))
+				.collect::<Option<Vec<_>>>()
+				.ok_or("nothing can be used for one of its ingredients")?;
+			if ingredients.is_empty() || ingredients.len() > 9 {
+				return Err(String::from("its ingredients don't fit in a crafting grid"));
+			}
+			RecipeKind::Shapeless { ingredients }
+		}
+		"minecraft:smelting" => RecipeKind::Smelting {
+			ingredient: ingredient(&value["ingredient"], items, tags)
+				.ok_or("nothing can be smelted with it")?,
+			experience: value["experience"].as_f64().unwrap_or_default() as f32,
+			cooking_time: value["cookingtime"].as_i64().unwrap_or(200) as i32,
+		},
+		_ => return Ok(None),
+	};
+	// Smelting recipes name their result directly rather than giving an item and count
+	let (result, count) = match &value["result"] {
+		Value::String(name) => (name.as_str(), 1),
+		result => (
+			result["item"].as_str().ok_or("it has no result")?,
+			result["count"].as_i64().unwrap_or(1),
+		),
+	};
+	let item = items
+		.id(result)
+		.ok_or_else(|| format!("its result {} isn't an item", result))?;
+	Ok(Some(Recipe {
+		id: id.to_owned(),
+		group,
+		kind,
+		result: Slot {
+			item,
+			count: count as i8,
+			nbt: None,
+		},
+	}))
+}
+
+/// Finds the items that fit an ingredient, which is an item, a tag, or a list of either
+fn ingredient(
+	value: &Value,
+	items: &ItemRegistry,
+	tags: &HashMap<String, Vec<String>>,
+) -> Option<Ingredient> {
+	let mut found = Vec::new();
+	let choices = match value {
+		Value::Array(choices) => choices.iter().collect(),
+		value => vec![value],
+	};
+	for choice in choices {
+		if let Some(name) = choice["item"].as_str() {
+			found.extend(items.id(name));
+		} else if let Some(tag) = choice["tag"].as_str() {
+			expand_tag(tag, items, tags, 0, &mut found);
+		}
+	}
+	found.dedup();
+	if found.is_empty() {
+		None
+	} else {
+		Some(found)
+	}
+}
+
+/// Adds the items in a tag, skipping any the server doesn't know about
+fn expand_tag(
+	tag: &str,
+	items: &ItemRegistry,
+	tags: &HashMap<String, Vec<String>>,
+	depth: usize,
+	found: &mut Vec<i32>,
+) {
+	if depth > MAX_TAG_DEPTH {
+		return;
+	}
+	for value in tags.get(tag).into_iter().flatten() {
+		match value.strip_prefix('#') {
+			Some(tag) => expand_tag(tag, items, tags, depth + 1, found),
+			None => found.extend(items.id(value)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::serde_json::json;
+
+	fn id(items: &ItemRegistry, name: &str) -> i32 {
+		items.id(name).unwrap()
+	}
+
+	fn slot(items: &ItemRegistry, name: &str) -> Option<Slot> {
+		Some(Slot {
+			item: id(items, name),
+			count: 1,
+			nbt: None,
+		})
+	}
+
+	fn parse(value: Value, items: &ItemRegistry) -> Recipe {
+		parse_recipe("test:recipe", &value, items, &HashMap::new())
+			.unwrap()
+			.unwrap()
+	}
+
+	#[test]
+	fn empty_rows_and_columns_are_trimmed() {
+		let grid = [
+			None,
+			None,
+			None,
+			None,
+			Some(1),
+			Some(2),
+			None,
+			None,
+			Some(3),
+		];
+		assert_eq!(trim(&grid, 3), (vec![Some(1), Some(2), None, Some(3)], 2));
+		assert_eq!(trim(&[None; 4], 2), (Vec::new(), 0));
+	}
+
+	#[test]
+	fn shaped_recipes_can_be_mirrored() {
+		let items = ItemRegistry::new();
+		let recipe = parse(
+			json!({
+				"type": "minecraft:crafting_shaped",
+				"pattern": ["SD", " D"],
+				"key": {
+					"S": { "item": "minecraft:stone" },
+					"D": { "item": "minecraft:dirt" }
+				},
+				"result": { "item": "minecraft:diamond" }
+			}),
+			&items,
+		);
+		let (s, d) = (
+			Some(id(&items, "minecraft:stone")),
+			Some(id(&items, "minecraft:dirt")),
+		);
+		assert!(recipe.matches(&[s, d, None, None, d, None, None, None, None], 3));
+		assert!(recipe.matches(&[None, None, None, None, d, s, None, d, None], 3));
+		assert!(!recipe.matches(&[None, d, s, d, None, None, None, None, None], 3));
+		assert!(!recipe.matches(&[s, d, None, d, None, None, None, None, None], 3));
+	}
+
+	#[test]
+	fn shapeless_ingredients_are_each_used_once() {
+		assert!(assign(&[1, 2], &[vec![1, 2], vec![1]], &mut vec![false; 2]));
+		assert!(!assign(
+			&[1, 1],
+			&[vec![1, 2], vec![2]],
+			&mut vec![false; 2]
+		));
+		let items = ItemRegistry::new();
+		let recipe = parse(
+			json!({
+				"type": "minecraft:crafting_shapeless",
+				"ingredients": [
+					[{ "item": "minecraft:stone" }, { "item": "minecraft:dirt" }],
+					{ "item": "minecraft:stone" }
+				],
+				"result": { "item": "minecraft:diamond", "count": 2 }
+			}),
+			&items,
+		);
+		let (s, d) = (
+			Some(id(&items, "minecraft:stone")),
+			Some(id(&items, "minecraft:dirt")),
+		);
+		assert!(recipe.matches(&[None, s, d, None], 2));
+		assert!(recipe.matches(&[s, None, None, s], 2));
+		assert!(!recipe.matches(&[d, None, None, d], 2));
+		assert!(!recipe.matches(&[s, s, s, None], 2));
+		assert_eq!(recipe.result.count, 2);
+	}
+
+	#[test]
+	fn tags_are_expanded() {
+		let items = ItemRegistry::new();
+		let mut tags = HashMap::new();
+		tags.insert(
+			String::from("test:stones"),
+			vec![
+				String::from("minecraft:stone"),
+				String::from("#test:dirts"),
+				String::from("minecraft:not_an_item"),
+			],
+		);
+		tags.insert(
+			String::from("test:dirts"),
+			vec![String::from("minecraft:dirt"), String::from("#test:stones")],
+		);
+		let found = ingredient(&json!({ "tag": "test:stones" }), &items, &tags).unwrap();
+		assert!(found.contains(&id(&items, "minecraft:stone")));
+		assert!(found.contains(&id(&items, "minecraft:dirt")));
+		assert_eq!(
+			ingredient(&json!({ "tag": "test:missing" }), &items, &tags),
+			None
+		);
+	}
+
+	#[test]
+	fn unusable_recipes_are_skipped() {
+		let items = ItemRegistry::new();
+		let tags = HashMap::new();
+		let too_big = json!({
+			"type": "minecraft:crafting_shaped",
+			"pattern": ["####"],
+			"key": { "#": { "item": "minecraft:stone" } },
+			"result": { "item": "minecraft:diamond" }
+		});
+		assert!(parse_recipe("test:big", &too_big, &items, &tags).is_err());
+		let unknown = json!({
+			"type": "minecraft:crafting_shapeless",
+			"ingredients": [{ "item": "minecraft:not_an_item" }],
+			"result": { "item": "minecraft:diamond" }
+		});
+		assert!(parse_recipe("test:unknown", &unknown, &items, &tags).is_err());
+		let stonecutting = json!({
+			"type": "minecraft:stonecutting",
+			"ingredient": { "item": "minecraft:stone" },
+			"result": "minecraft:stone_slab",
+			"count": 2
+		});
+		assert!(parse_recipe("test:cut", &stonecutting, &items, &tags)
+			.unwrap()
+			.is_none());
+	}
+
+	#[test]
+	fn bundled_recipes_can_be_crafted() {
+		let items = ItemRegistry::new();
+		let recipes = RecipeRegistry::load("", &items).unwrap();
+		let planks = slot(&items, "minecraft:oak_planks");
+		let crafted = recipes
+			.craft(&[None, planks.clone(), None, planks], 2)
+			.unwrap();
+		assert_eq!(crafted.item, id(&items, "minecraft:stick"));
+		assert_eq!(crafted.count, 4);
+		let log = slot(&items, "minecraft:oak_log");
+		let crafted = recipes.craft(&[None, None, None, log], 2).unwrap();
+		assert_eq!(crafted.item, id(&items, "minecraft:oak_planks"));
+		assert_eq!(recipes.craft(&[None, None, None, None], 2), None);
+	}
+}
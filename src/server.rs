@@ -1,38 +1,49 @@
-use ::log::error;
-use ::std::{error::Error, net::SocketAddr};
-use ::tokio::{self, net::TcpListener};
+use ::log::{error, info, warn};
+use ::std::{error::Error, io, net::SocketAddr, sync::Arc, time::Duration};
+use ::tokio::{self, net::TcpListener, time};
 
 use crate::{
 	auth::Authentication,
-	config::Config,
+	classic::V7,
 	connection::Connection,
+	lists,
 	modern::{
 		self,
 		types::{ModernEncodable, VarInt},
 	},
+	player::Player,
+	state::ServerState,
 	status,
+	types::ProtocolHandler,
 };
 
 pub struct Server {
 	auth: Authentication,
 	java: TcpListener,
+	state: Arc<ServerState>,
 }
 
 impl Server {
-	pub async fn listen(&mut self, config: &Config) -> Result<(), Box<dyn Error>> {
+	pub async fn listen(&mut self) -> Result<(), Box<dyn Error>> {
 		loop {
 			match self.java.accept().await {
-				Ok((sock, _addr)) => {
-					let config = config.clone();
+				Ok((sock, address)) => {
+					let state = self.state.clone();
+					// Banned addresses can still see the server in the server list, and are only turned
+					// away if they try to log in
+					let refusal = lists::check_address(&state, address.ip());
 					tokio::spawn(async move {
 						let connection = Connection::java(sock).await.unwrap();
 						match connection {
 							Connection::Classic(mut conn) => {
 								let id = u8::async_read(&mut conn).await.unwrap();
 								if id == 0xFE {
-									status::classic(&mut conn, &config).await.unwrap();
+									status::classic(&mut conn, &state).await.unwrap();
 								} else if id == 0x00 {
-									// TODO: Join the game
+									let handler = V7::new(conn, state.clone());
+									if let Err(e) = play(handler, state, address, refusal).await {
+										error!("Connection lost: {}", e);
+									}
 								}
 							}
 							Connection::Modern(mut conn) => {
@@ -46,11 +57,14 @@ impl Server {
 								let next: i32 = VarInt::async_read(&mut conn).await.unwrap().into();
 								if id == 0 {
 									if next == 1 {
-										status::modern(&mut conn, &config, version).await.unwrap();
+										status::modern(&mut conn, &state, version).await.unwrap();
 									} else if next == 2 {
-										let handler = modern::handler(conn, &config, version).await;
-										// TODO: Pass the handler to the player object
-										todo!();
+										let handler =
+											modern::handler(conn, state.clone(), version).await;
+										if let Err(e) = play(handler, state, address, refusal).await
+										{
+											error!("Connection lost: {}", e);
+										}
 									} else {
 										// ...
 									}
@@ -66,10 +80,81 @@ impl Server {
 		}
 	}
 
-	pub async fn new(config: &Config) -> Result<Self, Box<dyn Error>> {
-		let auth = Authentication::new(config).await?;
+	pub async fn new(state: Arc<ServerState>) -> Result<Self, Box<dyn Error>> {
+		let config = state.config();
+		let auth = Authentication::new(&config).await?;
+		if config.authentication.required {
+			error!(
+				"Authentication is required but isn't supported yet, so all logins will be refused"
+			);
+		}
 		let jaddr = SocketAddr::new(config.network.bind.parse().unwrap(), config.network.port);
 		let java = TcpListener::bind(jaddr).await?;
-		Ok(Self { auth, java })
+		Ok(Self { auth, java, state })
+	}
+}
+
+/// Logs a player in and keeps them in the game until they leave
+async fn play(
+	mut handler: Box<dyn ProtocolHandler>,
+	state: Arc<ServerState>,
+	address: SocketAddr,
+	refusal: Result<(), String>,
+) -> io::Result<()> {
+	if handler.is_dummy() {
+		return handler
+			.disconnect(String::from("Unsupported protocol version"))
+			.await;
+	}
+	if let Err(reason) = refusal {
+		info!("Connection from {} was refused: {}", address, reason);
+		return handler.disconnect(reason).await;
+	}
+	let timeout = Duration::from_secs(state.config().network.timeout);
+	let profile = match time::timeout(timeout, handler.login()).await {
+		Ok(Err(e)) if e.kind() == io::ErrorKind::PermissionDenied => {
+			info!("{}", e);
+			return Ok(());
+		}
+		Ok(profile) => profile?,
+		Err(_) => {
+			warn!(
+				"Client timed out after not logging in for {} seconds",
+				timeout.as_secs()
+			);
+			return handler.disconnect(String::from("Timed out")).await;
+		}
+	};
+	// Whoever was still logging in when the server started stopping would never be kicked
+	if state.is_stopping() {
+		return handler
+			.disconnect(state.config().minecraft.stop_message.clone())
+			.await;
+	}
+	if state.online() as i32 >= state.config().minecraft.max_players
+		&& !lists::bypasses_player_limit(&state, &profile)
+	{
+		info!("{} tried to join but the server is full", profile.name);
+		return handler
+			.disconnect(String::from("The server is full!"))
+			.await;
+	}
+	let uuid = profile.uuid;
+	let (player, handle) = Player::new(handler, profile, address, state.clone());
+	let previous = state.players.write().unwrap().insert(uuid, handle);
+	if let Some(previous) = previous {
+		previous.disconnect(String::from("You logged in from another location"));
+	}
+	let name = String::from(player.name());
+	let entity_id = player.entity_id;
+	let result = player.run().await;
+	{
+		let mut players = state.players.write().unwrap();
+		// Don't remove whoever replaced this player after logging in from elsewhere
+		if players.get(&uuid).map(|p| p.entity_id) == Some(entity_id) {
+			players.remove(&uuid);
+		}
 	}
+	info!("{} left the game", name);
+	result
 }
@@ -0,0 +1,210 @@
+use ::log::{info, warn};
+use ::std::{
+	collections::HashMap,
+	error::Error,
+	sync::{
+		atomic::{AtomicBool, AtomicI32, Ordering},
+		Arc, Mutex, RwLock,
+	},
+};
+use ::tokio::sync::Notify;
+use ::uuid::Uuid;
+
+use crate::{
+	command::Dispatcher,
+	config::{Config, CONFIG_PATH},
+	item::ItemRegistry,
+	lists::{IpBan, JsonList, Operator, PlayerBan, WhitelistEntry},
+	permissions::Permissions,
+	player::{PlayerHandle, PlayerMessage},
+	recipe::RecipeRegistry,
+	tick::TickHandle,
+	types::BasaltError,
+	world::{registry::BlockRegistry, Difficulty, World},
+};
+
+/// Everything shared between the tick loop and connection tasks
+///
+/// Locks must never be held across an `.await`. When more than one lock is needed at once they are
+/// taken in the order the fields are declared in, and worlds are locked in order of their name. This
+/// keeps the tick loop and connections from deadlocking each other.
+pub struct ServerState {
+	/// The config as last read, which is swapped out whenever it is reloaded
+	config: RwLock<Arc<Config>>,
+	pub blocks: Arc<BlockRegistry>,
+	pub items: Arc<ItemRegistry>,
+	pub recipes: RecipeRegistry,
+	pub tick: TickHandle,
+	pub commands: Dispatcher,
+	/// Whether only whitelisted players can join, which starts out as set in the config
+	pub whitelist_enabled: AtomicBool,
+	pub difficulty: RwLock<Difficulty>,
+	/// Players in `whitelist.json`, who can join while the whitelist is enabled
+	pub whitelist: Mutex<JsonList<WhitelistEntry>>,
+	/// Players in `banned-players.json`, who can't join until their ban expires
+	pub banned_players: Mutex<JsonList<PlayerBan>>,
+	/// Addresses in `banned-ips.json`, which can't be joined from until their ban expires
+	pub banned_ips: Mutex<JsonList<IpBan>>,
+	/// Players in `ops.json` and their operator levels
+	pub ops: Mutex<JsonList<Operator>>,
+	pub permissions: RwLock<Permissions>,
+	/// Everyone who has finished logging in, keyed by UUID
+	pub players: RwLock<HashMap<Uuid, PlayerHandle>>,
+	pub worlds: HashMap<String, Mutex<World>>,
+	/// The world new players join, which is the first one listed in the config
+	pub default_world: String,
+	entity_ids: AtomicI32,
+	/// Whether the server has been asked to stop, after which nobody else can join
+	stopping: AtomicBool,
+	stop_requested: Notify,
+}
+
+impl ServerState {
+	pub fn new(config: Config, tick: TickHandle) -> Result<Self, Box<dyn Error>> {
+		let blocks = Arc::new(BlockRegistry::new());
+		let items = Arc::new(ItemRegistry::new());
+		let mut worlds = HashMap::new();
+		for world in &config.worlds {
+			if worlds.contains_key(&world.name) {
+				return Err(Box::new(BasaltError::new(format!(
+					"World \"{}\" is defined more than once",
+					world.name
+				))));
+			}
+			worlds.insert(
+				world.name.clone(),
+				Mutex::new(World::new(world, blocks.clone(), items.clone())?),
+			);
+		}
+		let default_world = match config.worlds.first() {
+			Some(world) => world.name.clone(),
+			None => {
+				return Err(Box::new(BasaltError::new(String::from(
+					"At least one world must be configured",
+				))))
+			}
+		};
+		let recipes = RecipeRegistry::load(&config.minecraft.data_directory, &items)?;
+		let whitelist_enabled = AtomicBool::new(config.whitelist.enabled);
+		let difficulty = RwLock::new(config.minecraft.difficulty);
+		Ok(Self {
+			config: RwLock::new(Arc::new(config)),
+			blocks,
+			items,
+			recipes,
+			tick,
+			commands: Dispatcher::new(),
+			whitelist_enabled,
+			difficulty,
+			whitelist: Mutex::new(JsonList::load("whitelist.json")?),
+			banned_players: Mutex::new(JsonList::load("banned-players.json")?),
+			banned_ips: Mutex::new(JsonList::load("banned-ips.json")?),
+			ops: Mutex::new(JsonList::load("ops.json")?),
+			permissions: RwLock::new(Permissions::load("permissions.toml")?),
+			players: RwLock::new(HashMap::new()),
+			worlds,
+			default_world,
+			entity_ids: AtomicI32::new(0),
+			stopping: AtomicBool::new(false),
+			stop_requested: Notify::new(),
+		})
+	}
+
+	/// The current config, which stays the same for whoever holds on to it even if it is reloaded
+	pub fn config(&self) -> Arc<Config> {
+		self.config.read().unwrap().clone()
+	}
+
+	/// Reads the config file again and switches to it. Settings that need a restart keep their old
+	/// values until then, with a warning, and the rest take effect straight away.
+	pub fn reload_config(&self) -> Result<(), Box<dyn Error>> {
+		let mut config = Config::read(CONFIG_PATH)?;
+		let old = self.config();
+		for key in old.keep_restart_only(&mut config) {
+			warn!(
+				"Changing {} won't take effect until the server restarts",
+				key
+			);
+		}
+		// These can also be changed by commands, so they are only touched if the config changed them
+		if config.whitelist.enabled != old.whitelist.enabled {
+			self.whitelist_enabled
+				.store(config.whitelist.enabled, Ordering::Relaxed);
+		}
+		let difficulty = config.minecraft.difficulty;
+		if difficulty != old.minecraft.difficulty {
+			*self.difficulty.write().unwrap() = difficulty;
+			for player in self.players.read().unwrap().values() {
+				player.send(PlayerMessage::Difficulty(difficulty));
+			}
+		}
+		let view_distance = config.minecraft.view_distance;
+		if view_distance != old.minecraft.view_distance {
+			for player in self.players.read().unwrap().values() {
+				player.send(PlayerMessage::ViewDistance(view_distance));
+			}
+		}
+		*self.config.write().unwrap() = Arc::new(config);
+		info!("Reloaded {}", CONFIG_PATH);
+		self.refresh_lists();
+		Ok(())
+	}
+
+	/// Reads the whitelist, ban lists, ops and permissions again if their files were edited since
+	/// they were last read, telling online players if what they are allowed to do may have changed
+	pub fn refresh_lists(&self) {
+		self.whitelist.lock().unwrap().refresh();
+		self.banned_players.lock().unwrap().refresh();
+		self.banned_ips.lock().unwrap().refresh();
+		let ops = self.ops.lock().unwrap().refresh();
+		let permissions = self.permissions.write().unwrap().refresh();
+		if ops || permissions {
+			for player in self.players.read().unwrap().values() {
+				player.send(PlayerMessage::UpdatePermissions);
+			}
+		}
+	}
+
+	/// Allocates an entity ID that no other entity has used
+	pub fn next_entity_id(&self) -> i32 {
+		self.entity_ids.fetch_add(1, Ordering::Relaxed)
+	}
+
+	pub fn online(&self) -> usize {
+		self.players.read().unwrap().len()
+	}
+
+	/// Asks for the server to be stopped, which happens once `stopped` notices
+	pub fn stop(&self) {
+		self.stopping.store(true, Ordering::Relaxed);
+		self.stop_requested.notify_one();
+	}
+
+	/// Waits until something asks for the server to be stopped
+	pub async fn stopped(&self) {
+		self.stop_requested.notified().await
+	}
+
+	pub fn is_stopping(&self) -> bool {
+		self.stopping.load(Ordering::Relaxed)
+	}
+
+	pub fn world(&self, name: &str) -> Option<&Mutex<World>> {
+		self.worlds.get(name)
+	}
+
+	/// Saves the chunks that changed in every world, returning the names of any worlds that couldn't
+	/// be saved
+	pub fn save_worlds(&self) -> Vec<String> {
+		let mut names: Vec<_> = self.worlds.keys().collect();
+		names.sort();
+		let mut failed = Vec::new();
+		for name in names {
+			if let Err(e) = self.worlds[name].lock().unwrap().save() {
+				warn!("Couldn't save world {}: {}", name, e);
+				failed.push(name.clone());
+			}
+		}
+		failed
+	}
+}
@@ -2,14 +2,14 @@ use ::serde_json::json;
 use ::std::io::Result;
 use ::tokio::{io::AsyncWriteExt, net::TcpStream};
 
-use crate::{config::Config, util};
+use crate::{state::ServerState, util};
 
-pub async fn classic(conn: &mut TcpStream, config: &Config) -> Result<()> {
+pub async fn classic(conn: &mut TcpStream, state: &ServerState) -> Result<()> {
 	use crate::classic::types::ClassicEncodable;
 	todo!()
 }
 
-pub async fn modern(conn: &mut TcpStream, config: &Config, version: i32) -> Result<()> {
+pub async fn modern(conn: &mut TcpStream, state: &ServerState, version: i32) -> Result<()> {
 	use crate::modern::types::{ModernEncodable, VarInt};
 	let version = if crate::modern::supported(version) {
 		version
@@ -28,12 +28,12 @@ pub async fn modern(conn: &mut TcpStream, config: &Config, version: i32) -> Resu
 						"protocol": version
 					},
 					"players": {
-						"max": config.minecraft.max_players,
-						"online": 0,
+						"max": state.config().minecraft.max_players,
+						"online": state.online(),
 						"sample": []
 					},
 					"description": {
-						"text": config.minecraft.motd
+						"text": state.config().minecraft.motd
 					}
 				})
 				.to_string();
@@ -0,0 +1,272 @@
+use ::log::{debug, warn};
+use ::std::{
+	collections::{HashMap, VecDeque},
+	sync::Arc,
+	time::Duration,
+};
+use ::tokio::{
+	self,
+	sync::{mpsc, watch},
+	task::JoinHandle,
+	time::{self, Instant},
+};
+
+use crate::{player::PlayerMessage, state::ServerState, world::tracker::MIN_VIEW_DISTANCE};
+
+pub const TICKS_PER_SECOND: u32 = 20;
+pub const TICK_DURATION: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND as u64);
+/// How far behind the loop may fall before it gives up on catching up
+const MAX_LAG: Duration = Duration::from_secs(2);
+/// Number of ticks used to average the tick time
+const SAMPLE_SIZE: usize = 100;
+/// Ticks between saving the chunks that changed in every world, which is every five minutes like
+/// vanilla
+const AUTOSAVE_INTERVAL: u64 = 6000;
+/// Ticks between checking whether the whitelist, ban lists or ops were edited, which is every five
+/// seconds
+const LIST_REFRESH_INTERVAL: u64 = 100;
+/// Ticks between unloading the chunks no player can see, which is every thirty seconds
+const UNLOAD_INTERVAL: u64 = 600;
+
+/// Messages that other tasks can send to the tick loop
+pub enum TickMessage {
+	/// Stops the loop once the current tick has finished
+	Stop,
+}
+
+/// Performance of the tick loop over the last few seconds
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TickStats {
+	/// Number of ticks since the loop started
+	pub tick: u64,
+	/// Ticks per second, which is at most 20
+	pub tps: f64,
+	/// Average milliseconds spent processing each tick
+	pub mspt: f64,
+}
+
+/// A cloneable handle used by network tasks to talk to the tick loop
+#[derive(Clone)]
+pub struct TickHandle {
+	sender: mpsc::UnboundedSender<TickMessage>,
+	stats: watch::Receiver<TickStats>,
+}
+
+impl TickHandle {
+	/// How the tick loop has been keeping up as of the last tick
+	pub fn stats(&self) -> TickStats {
+		*self.stats.borrow()
+	}
+
+	pub fn stop(&self) {
+		let _ = self.sender.send(TickMessage::Stop);
+	}
+}
+
+/// The receiving half of the tick loop's channel, handed to `spawn` once the state exists
+pub struct TickReceiver {
+	receiver: mpsc::UnboundedReceiver<TickMessage>,
+	stats: watch::Sender<TickStats>,
+}
+
+/// Drives everything that changes from one tick to the next
+pub struct Ticker {
+	pub state: Arc<ServerState>,
+	pub tick: u64,
+	durations: VecDeque<Duration>,
+	starts: VecDeque<Instant>,
+}
+
+impl Ticker {
+	pub fn new(state: Arc<ServerState>) -> Self {
+		Self {
+			state,
+			tick: 0,
+			durations: VecDeque::with_capacity(SAMPLE_SIZE),
+			starts: VecDeque::with_capacity(TICKS_PER_SECOND as usize + 1),
+		}
+	}
+
+	fn stats(&self) -> TickStats {
+		let mspt = if self.durations.is_empty() {
+			0.0
+		} else {
+			let total: Duration = self.durations.iter().sum();
+			total.as_secs_f64() * 1000.0 / self.durations.len() as f64
+		};
+		let tps = match (self.starts.front(), self.starts.back()) {
+			(Some(first), Some(last)) if self.starts.len() > 1 => {
+				let elapsed = last.duration_since(*first).as_secs_f64();
+				if elapsed > 0.0 {
+					((self.starts.len() - 1) as f64 / elapsed).min(TICKS_PER_SECOND as f64)
+				} else {
+					TICKS_PER_SECOND as f64
+				}
+			}
+			_ => TICKS_PER_SECOND as f64,
+		};
+		TickStats {
+			tick: self.tick,
+			tps,
+			mspt,
+		}
+	}
+
+	/// Advances the game by a single tick
+	fn tick(&mut self) {
+		for world in self.state.worlds.values() {
+			world.lock().unwrap().tick();
+		}
+		self.track_entities();
+		self.send_block_changes();
+		self.send_world_changes();
+		self.tick += 1;
+		if self.tick % AUTOSAVE_INTERVAL == 0 {
+			self.state.save_worlds();
+		}
+		if self.tick % LIST_REFRESH_INTERVAL == 0 {
+			self.state.refresh_lists();
+		}
+		if self.tick % UNLOAD_INTERVAL == 0 {
+			self.unload_chunks();
+		}
+	}
+
+	/// Unloads the chunks in every world that are too far from any player to be seen
+	fn unload_chunks(&mut self) {
+		let view_distance = self
+			.state
+			.config()
+			.minecraft
+			.view_distance
+			.max(MIN_VIEW_DISTANCE);
+		for (name, world) in &self.state.worlds {
+			if let Err(e) = world.lock().unwrap().unload_unseen(view_distance) {
+				warn!("Couldn't unload chunks of {}: {}", name, e);
+			}
+		}
+	}
+
+	/// Tells every player about the entities that came into range, moved or went away
+	fn track_entities(&mut self) {
+		let view_distance = self.state.config().minecraft.view_distance;
+		let players = self.state.players.read().unwrap();
+		let handles: HashMap<_, _> = players.values().map(|p| (p.entity_id, p)).collect();
+		for world in self.state.worlds.values() {
+			let updates = world.lock().unwrap().entities.track(view_distance);
+			for (observer, updates) in updates {
+				if let Some(player) = handles.get(&observer) {
+					player.send(PlayerMessage::Entities(updates));
+				}
+			}
+		}
+	}
+
+	/// Tells every player about the blocks and light that changed in their world during the tick
+	fn send_block_changes(&mut self) {
+		let players = self.state.players.read().unwrap();
+		for world in self.state.worlds.values() {
+			let (name, blocks, light) = {
+				let mut world = world.lock().unwrap();
+				(
+					world.name.clone(),
+					world.take_block_changes(),
+					world.take_light_updates(),
+				)
+			};
+			if blocks.is_empty() && light.is_empty() {
+				continue;
+			}
+			for player in players.values().filter(|p| p.session().world == name) {
+				player.send(PlayerMessage::BlockChanges {
+					blocks: blocks.clone(),
+					light: light.clone(),
+				});
+			}
+		}
+	}
+
+	/// Tells every player when the weather changes in their world, and what time it is there every
+	/// second so their clock doesn't drift
+	fn send_world_changes(&mut self) {
+		let send_time = self.tick % TICKS_PER_SECOND as u64 == 0;
+		let players = self.state.players.read().unwrap();
+		for world in self.state.worlds.values() {
+			let (name, age, time, weather) = {
+				let mut world = world.lock().unwrap();
+				let weather = world.take_weather_change();
+				(world.name.clone(), world.age, world.time, weather)
+			};
+			if !send_time && weather.is_none() {
+				continue;
+			}
+			for player in players.values().filter(|p| p.session().world == name) {
+				if let Some(weather) = weather {
+					player.send(PlayerMessage::Weather(weather));
+				}
+				if send_time {
+					player.send(PlayerMessage::Time { age, time });
+				}
+			}
+		}
+	}
+}
+
+/// Creates the channel used to talk to the tick loop before it is started
+pub fn channel() -> (TickHandle, TickReceiver) {
+	let (sender, receiver) = mpsc::unbounded_channel();
+	let (stats_sender, stats) = watch::channel(TickStats::default());
+	(
+		TickHandle { sender, stats },
+		TickReceiver {
+			receiver,
+			stats: stats_sender,
+		},
+	)
+}
+
+/// Starts the tick loop on its own task
+pub fn spawn(state: Arc<ServerState>, receiver: TickReceiver) -> JoinHandle<()> {
+	tokio::spawn(run(Ticker::new(state), receiver))
+}
+
+async fn run(mut ticker: Ticker, channel: TickReceiver) {
+	let TickReceiver {
+		mut receiver,
+		stats,
+	} = channel;
+	let mut next = Instant::now();
+	loop {
+		tokio::select! {
+			message = receiver.recv() => match message {
+				Some(TickMessage::Stop) | None => break,
+			},
+			_ = time::sleep_until(next) => {
+				let start = Instant::now();
+				ticker.tick();
+				if ticker.starts.len() > TICKS_PER_SECOND as usize {
+					ticker.starts.pop_front();
+				}
+				ticker.starts.push_back(start);
+				if ticker.durations.len() == SAMPLE_SIZE {
+					ticker.durations.pop_front();
+				}
+				ticker.durations.push_back(start.elapsed());
+				let _ = stats.send(ticker.stats());
+				next += TICK_DURATION;
+				// Missed ticks are run back to back, unless so many were missed that it isn't worth it
+				let now = Instant::now();
+				if now > next + MAX_LAG {
+					let behind = now - next;
+					warn!(
+						"Can't keep up! Running {}ms or {} ticks behind",
+						behind.as_millis(),
+						behind.as_millis() / TICK_DURATION.as_millis()
+					);
+					next = now;
+				}
+			}
+		}
+	}
+	debug!("Tick loop stopped after {} ticks", ticker.tick);
+}
@@ -1,9 +1,23 @@
 use ::async_trait::async_trait;
 use ::std::{
+	collections::HashSet,
 	error::Error,
 	fmt::{self, Display, Formatter},
 	io,
 };
+use ::tokio::sync::mpsc::UnboundedReceiver;
+use ::uuid::Uuid;
+
+use crate::{
+	auth::GameProfile,
+	command::CommandNode,
+	entity::EntityKind,
+	inventory::ContainerKind,
+	modern::types::chat::ChatComponent,
+	nbt::Nbt,
+	player::{Gamemode, Position, Rotation, Session},
+	world::{chunk::ChunkPos, BlockPos, Difficulty, Weather},
+};
 
 #[derive(Debug)]
 pub struct BasaltError {
@@ -24,12 +38,306 @@ impl Display for BasaltError {
 
 impl Error for BasaltError {}
 
+/// Decoded packets from a client, independent of the protocol they arrived in
+#[derive(Debug)]
+pub enum Event {
+	/// Sent when the client wants to say something or run a command
+	Chat(String),
+	/// Sent when the client clicks a slot in an open window
+	ClickWindow {
+		window: u8,
+		slot: i16,
+		button: i8,
+		action: i16,
+		mode: i32,
+		item: Option<Slot>,
+	},
+	/// Sent when the client changes their settings
+	ClientSettings {
+		view_distance: i8,
+		chat_mode: ChatMode,
+	},
+	/// Sent when the client closes a window
+	CloseWindow(u8),
+	/// Sent when a creative mode client takes an item out of thin air
+	CreativeInventory { slot: i16, item: Option<Slot> },
+	/// Sent when the client starts, stops or finishes breaking a block
+	Dig {
+		status: DigStatus,
+		position: BlockPos,
+		face: u8,
+	},
+	/// Sent when the client selects a different hotbar slot
+	HeldItemChange(i16),
+	/// Sent when the client attacks or interacts with an entity
+	Interact { entity_id: i32, attack: bool },
+	/// Sent in response to a keep alive or ping from the server
+	KeepAlive(i64),
+	/// Sent when the client moves or turns, with anything that didn't change left out
+	Move {
+		position: Option<Position>,
+		rotation: Option<Rotation>,
+		on_ground: bool,
+	},
+	/// Sent when the client places a block. Modern clients send the block they clicked on along with
+	/// the face they clicked, while classic clients send where the block should go along with which
+	/// block it is.
+	Place {
+		position: BlockPos,
+		face: Option<u8>,
+		hand: Hand,
+		block: Option<u16>,
+	},
+	/// Sent when the client clicks the respawn button after dying
+	Respawn,
+	/// Sent when the client swings their arm
+	Swing(Hand),
+	/// Sent when the client wants suggestions for a partially typed command
+	TabComplete { id: i32, text: String },
+	/// Sent when the client accepts a teleport from the server
+	TeleportConfirm(i32),
+	/// Sent when the client uses the item in their hand
+	UseItem(Hand),
+}
+
+/// The stream of events a handler produces once the player has joined
+pub type Events = UnboundedReceiver<Event>;
+
+/// Which chat messages a client wants to see, as chosen in its settings
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChatMode {
+	Full,
+	CommandsOnly,
+	Hidden,
+}
+
+impl ChatMode {
+	/// Whether a message in a position should be sent to a client using this mode
+	pub fn shows(&self, position: MessagePosition) -> bool {
+		match self {
+			Self::Full => true,
+			Self::CommandsOnly => position != MessagePosition::Chat,
+			Self::Hidden => position == MessagePosition::ActionBar,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DigStatus {
+	Started,
+	Cancelled,
+	Finished,
+	DropStack,
+	DropItem,
+	/// Releasing a bow or finishing eating
+	ReleaseItem,
+	SwapHands,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Hand {
+	Main,
+	Off,
+}
+
+/// Where a chat message is displayed on the client
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessagePosition {
+	Chat,
+	System,
+	ActionBar,
+}
+
+/// A stack of items in an inventory slot
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slot {
+	pub item: i32,
+	pub count: i8,
+	pub nbt: Option<Nbt>,
+}
+
+/// A client connected with any protocol, exposing everything game code needs to talk to it
 #[async_trait]
-pub trait ProtocolHandler {
+pub trait ProtocolHandler: Send {
+	/// Tells the client whether the server went along with it starting, stopping or finishing
+	/// breaking a block, along with the state the block is really in
+	async fn acknowledge_dig(
+		&mut self,
+		position: BlockPos,
+		state: u16,
+		status: DigStatus,
+		successful: bool,
+	) -> io::Result<()>;
+	/// Adds a player to the player list, which modern clients need before they can see the player
+	async fn add_to_player_list(
+		&mut self,
+		profile: &GameProfile,
+		gamemode: Gamemode,
+		ping: i32,
+	) -> io::Result<()>;
+	/// Shows another player's progress breaking a block, from 0 to 9, or removes it for any other stage
+	async fn block_break_animation(
+		&mut self,
+		entity_id: i32,
+		position: BlockPos,
+		stage: i8,
+	) -> io::Result<()>;
+	/// Changes a single block in a chunk the client has loaded
+	async fn block_change(&mut self, position: BlockPos, state: u16) -> io::Result<()>;
+	/// Changes any number of blocks and resends the light in the chunks given, skipping anything in
+	/// chunks the client doesn't have loaded
+	async fn block_changes(
+		&mut self,
+		blocks: &[(BlockPos, u16)],
+		light: &HashSet<ChunkPos>,
+	) -> io::Result<()>;
+	/// Whether the client breaks blocks the moment they are clicked without saying when it started,
+	/// as classic clients do
+	fn breaks_instantly(&self) -> bool {
+		false
+	}
+	/// Whether the client lets the player fly whatever their game mode, as classic clients do
+	fn can_always_fly(&self) -> bool {
+		false
+	}
+	/// Closes a window the client has open
+	async fn close_window(&mut self, window: u8) -> io::Result<()>;
+	/// Shows the death screen, with a message saying how the player died
+	async fn death_screen(&mut self, entity_id: i32, message: &ChatComponent) -> io::Result<()>;
+	/// Tells the client which commands the player can use, so it can complete and highlight them
+	async fn declare_commands(&mut self, root: &CommandNode) -> io::Result<()>;
+	/// Removes entities from the client
+	async fn despawn_entities(&mut self, entity_ids: &[i32]) -> io::Result<()>;
 	async fn disconnect(&mut self, reason: String) -> io::Result<()>;
+	/// Moves an entity to an absolute position
+	async fn entity_teleport(
+		&mut self,
+		entity_id: i32,
+		position: Position,
+		rotation: Rotation,
+		on_ground: bool,
+	) -> io::Result<()>;
+	/// Called regularly so the handler can send anything it held back, such as rate limited chunks
+	async fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+	/// Whether the client has health and can die, which classic clients can't
+	fn has_health(&self) -> bool {
+		true
+	}
 	fn is_dummy(&self) -> bool {
 		false
 	}
+	/// Checks the client is still there. Modern clients answer with the same ID, which is used to
+	/// measure their latency.
+	async fn keep_alive(&mut self, id: i64) -> io::Result<()>;
+	/// Puts a player who has logged in into their world, returning the events they send from then on
+	async fn join(&mut self, entity_id: i32, session: &Session) -> io::Result<Events>;
+	/// Reads the login sequence, returning who the client claims to be
+	async fn login(&mut self) -> io::Result<GameProfile>;
+	/// Moves an entity the client can see from the position it was last sent at
+	async fn move_entity(
+		&mut self,
+		entity_id: i32,
+		from: Position,
+		to: Position,
+		rotation: Rotation,
+		on_ground: bool,
+	) -> io::Result<()>;
+	/// Opens a container's window, which is followed by its contents
+	async fn open_window(
+		&mut self,
+		window: u8,
+		kind: ContainerKind,
+		title: &ChatComponent,
+	) -> io::Result<()>;
+	async fn remove_from_player_list(&mut self, uuid: Uuid) -> io::Result<()>;
+	/// Puts the player back into the world in their session after dying or to change world, leaving
+	/// them to be teleported to where they should be afterwards
+	async fn respawn(&mut self, session: &Session) -> io::Result<()>;
+	/// Turns an entity the client can see without moving it
+	async fn rotate_entity(
+		&mut self,
+		entity_id: i32,
+		rotation: Rotation,
+		on_ground: bool,
+	) -> io::Result<()>;
+	/// Sends the player a message from another player, or from the server if there is no sender
+	async fn send_message(
+		&mut self,
+		message: &ChatComponent,
+		position: MessagePosition,
+		sender: Option<Uuid>,
+	) -> io::Result<()>;
+	/// Changes the difficulty shown in the client's menu
+	async fn set_difficulty(&mut self, difficulty: Difficulty) -> io::Result<()>;
+	/// Changes the player's gamemode along with what they are allowed to do in it
+	async fn set_gamemode(&mut self, gamemode: Gamemode) -> io::Result<()>;
+	/// Replaces the contents of a window, where window 0 is the player's own inventory
+	async fn set_inventory(&mut self, window: u8, items: &[Option<Slot>]) -> io::Result<()>;
+	/// Tells the client the player's operator level, so it knows which commands and controls to offer
+	async fn set_op_level(&mut self, entity_id: i32, level: u8) -> io::Result<()>;
+	async fn set_slot(&mut self, window: u8, slot: i16, item: Option<&Slot>) -> io::Result<()>;
+	/// Changes how many chunks around the player the client is sent, for clients that are sent
+	/// chunks at all
+	async fn set_view_distance(&mut self, _view_distance: u8) -> io::Result<()> {
+		Ok(())
+	}
+	/// Starts or stops rain and thunder
+	async fn set_weather(&mut self, weather: Weather) -> io::Result<()>;
+	/// Shows an entity other than a player to the client
+	async fn spawn_entity(
+		&mut self,
+		entity_id: i32,
+		uuid: Uuid,
+		kind: EntityKind,
+		position: Position,
+		rotation: Rotation,
+	) -> io::Result<()>;
+	/// Shows another player to the client, who must already be in their player list
+	async fn spawn_player(
+		&mut self,
+		entity_id: i32,
+		profile: &GameProfile,
+		position: Position,
+		rotation: Rotation,
+	) -> io::Result<()>;
+	/// Answers a request for ways to finish a command, where the suggestions replace `length` bytes
+	/// of the command starting at `start`
+	async fn tab_complete(
+		&mut self,
+		id: i32,
+		start: usize,
+		length: usize,
+		matches: &[String],
+	) -> io::Result<()>;
+	/// Moves the player, returning an ID the client will confirm the teleport with if it confirms them
+	async fn teleport(&mut self, position: Position, rotation: Rotation)
+		-> io::Result<Option<i32>>;
+	/// Shows a title in the middle of the screen, with times given in ticks
+	async fn title(
+		&mut self,
+		title: &ChatComponent,
+		subtitle: Option<&ChatComponent>,
+		fade_in: i32,
+		stay: i32,
+		fade_out: i32,
+	) -> io::Result<()>;
+	/// Sets the health and hunger shown on the client's HUD, which shows the death screen at 0 health
+	async fn update_health(&mut self, session: &Session) -> io::Result<()>;
+	/// Changes the latency shown next to a player in the player list
+	async fn update_latency(&mut self, uuid: Uuid, ping: i32) -> io::Result<()>;
+	/// Sets the age of the world and the time of day, both in ticks
+	async fn update_time(&mut self, age: i64, time: i64) -> io::Result<()>;
+	/// Loads and unloads chunks around the player's position
+	async fn update_view(&mut self, position: Position) -> io::Result<()>;
+	/// Tells the client whether a click in a window was accepted, so it can undo it if it wasn't
+	async fn window_confirmation(
+		&mut self,
+		window: u8,
+		action: i16,
+		accepted: bool,
+	) -> io::Result<()>;
 }
 
 pub enum ProtocolState {
@@ -0,0 +1,114 @@
+use crate::world::registry;
+
+/// The kinds of tool that break some blocks faster
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ToolKind {
+	Axe,
+	Hoe,
+	Pickaxe,
+	Shears,
+	Shovel,
+	Sword,
+}
+
+/// What a tool is made of, which decides how fast it is and which blocks it can harvest
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ToolTier {
+	Wood,
+	Stone,
+	Iron,
+	Diamond,
+	Netherite,
+	Gold,
+}
+
+impl ToolTier {
+	/// Highest harvest level of the blocks this tier can harvest
+	pub fn level(&self) -> u8 {
+		match self {
+			Self::Wood | Self::Gold => 0,
+			Self::Stone => 1,
+			Self::Iron => 2,
+			Self::Diamond => 3,
+			Self::Netherite => 4,
+		}
+	}
+
+	/// How many times faster than a bare hand this tier breaks blocks it is suited to
+	pub fn speed(&self) -> f32 {
+		match self {
+			Self::Wood => 2.0,
+			Self::Stone => 4.0,
+			Self::Iron => 6.0,
+			Self::Diamond => 8.0,
+			Self::Netherite => 9.0,
+			Self::Gold => 12.0,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Tool {
+	pub kind: ToolKind,
+	pub tier: ToolTier,
+}
+
+impl Tool {
+	/// Finds the tool an item is from its name, such as `minecraft:iron_pickaxe`
+	pub fn from_item(name: &str) -> Option<Self> {
+		let name = name.trim_start_matches("minecraft:");
+		let split = name.find('_')?;
+		let tier = match &name[..split] {
+			"wooden" => ToolTier::Wood,
+			"stone" => ToolTier::Stone,
+			"iron" => ToolTier::Iron,
+			"diamond" => ToolTier::Diamond,
+			"netherite" => ToolTier::Netherite,
+			"golden" => ToolTier::Gold,
+			_ => return None,
+		};
+		let kind = match &name[split + 1..] {
+			"axe" => ToolKind::Axe,
+			"hoe" => ToolKind::Hoe,
+			"pickaxe" => ToolKind::Pickaxe,
+			"shovel" => ToolKind::Shovel,
+			"sword" => ToolKind::Sword,
+			_ => return None,
+		};
+		Some(Self { kind, tier })
+	}
+}
+
+/// Number of ticks it takes to break a block the way vanilla works it out, or None if the block
+/// can't be broken at all. Blocks that break instantly take 0 ticks.
+pub fn break_ticks(state: u16, tool: Option<Tool>, on_ground: bool) -> Option<u32> {
+	let material = registry::material(state);
+	let hardness = material.hardness?;
+	if hardness == 0.0 {
+		return Some(0);
+	}
+	let suited = matches!((tool, material.tool), (Some(tool), Some(kind)) if tool.kind == kind);
+	let harvestable = match (material.harvest_level, tool) {
+		(None, _) => true,
+		(Some(level), Some(tool)) => suited && tool.tier.level() >= level,
+		(Some(_), None) => false,
+	};
+	let mut speed = match tool {
+		Some(tool) if suited => tool.tier.speed(),
+		_ => 1.0,
+	};
+	if !on_ground {
+		speed /= 5.0;
+	}
+	let damage = speed / hardness / if harvestable { 30.0 } else { 100.0 };
+	if damage >= 1.0 {
+		return Some(0);
+	}
+	Some((1.0 / damage).ceil() as u32)
+}
+
+/// Whether placing a block where this one is replaces it, rather than being blocked by it
+pub fn is_replaceable(state: u16) -> bool {
+	// Air, water and lava
+	matches!(state, 0 | 34..=65)
+}
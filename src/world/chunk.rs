@@ -0,0 +1,186 @@
+use ::std::mem;
+
+use crate::world::light::ChunkLight;
+
+pub const SECTION_COUNT: usize = 16;
+pub const SECTION_VOLUME: usize = 16 * 16 * 16;
+pub const BIOME_COUNT: usize = 1024;
+
+/// Position of a chunk column in chunk coordinates
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ChunkPos {
+	pub x: i32,
+	pub z: i32,
+}
+
+impl ChunkPos {
+	pub fn new(x: i32, z: i32) -> Self {
+		Self { x, z }
+	}
+
+	/// Finds the chunk containing the given block coordinates
+	pub fn from_block(x: i32, z: i32) -> Self {
+		Self::new(x >> 4, z >> 4)
+	}
+
+	/// Finds the chunk containing the given entity position
+	pub fn from_position(x: f64, z: f64) -> Self {
+		Self::from_block(x.floor() as i32, z.floor() as i32)
+	}
+
+	/// Chebyshev distance, which is how the client decides what is in view
+	pub fn distance(&self, other: &ChunkPos) -> i32 {
+		(self.x - other.x).abs().max((self.z - other.z).abs())
+	}
+
+	/// Euclidean distance squared, used to order chunk sends
+	pub fn distance_squared(&self, other: &ChunkPos) -> i32 {
+		let dx = self.x - other.x;
+		let dz = self.z - other.z;
+		dx * dx + dz * dz
+	}
+}
+
+/// A 16x16x16 cube of global block state IDs
+#[derive(Clone)]
+pub struct ChunkSection {
+	blocks: Vec<u16>,
+	block_count: i16,
+}
+
+impl ChunkSection {
+	pub fn new() -> Self {
+		Self {
+			blocks: vec![0; SECTION_VOLUME],
+			block_count: 0,
+		}
+	}
+
+	fn index(x: usize, y: usize, z: usize) -> usize {
+		(y << 8) | (z << 4) | x
+	}
+
+	pub fn blocks(&self) -> &[u16] {
+		&self.blocks
+	}
+
+	/// Number of non-air blocks in the section
+	pub fn block_count(&self) -> i16 {
+		self.block_count
+	}
+
+	pub fn get(&self, x: usize, y: usize, z: usize) -> u16 {
+		self.blocks[Self::index(x, y, z)]
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.block_count == 0
+	}
+
+	/// Replaces a block, returning the state that was there before
+	pub fn set(&mut self, x: usize, y: usize, z: usize, state: u16) -> u16 {
+		let old = mem::replace(&mut self.blocks[Self::index(x, y, z)], state);
+		if old == 0 && state != 0 {
+			self.block_count += 1;
+		} else if old != 0 && state == 0 {
+			self.block_count -= 1;
+		}
+		old
+	}
+}
+
+/// A full column of sections from y=0 to y=255
+#[derive(Clone)]
+pub struct Chunk {
+	pub pos: ChunkPos,
+	pub light: ChunkLight,
+	sections: Vec<Option<ChunkSection>>,
+	biomes: Vec<i32>,
+}
+
+impl Chunk {
+	pub fn new(pos: ChunkPos) -> Self {
+		Self {
+			pos,
+			light: ChunkLight::new(),
+			sections: vec![None; SECTION_COUNT],
+			biomes: vec![1; BIOME_COUNT],
+		}
+	}
+
+	pub fn biomes(&self) -> &[i32] {
+		&self.biomes
+	}
+
+	/// Fills the whole chunk with a single biome
+	pub fn fill_biome(&mut self, biome: i32) {
+		for b in self.biomes.iter_mut() {
+			*b = biome;
+		}
+	}
+
+	/// Replaces the biomes of the chunk, ignoring them unless there is one for every 4x4x4 area
+	pub fn set_biomes(&mut self, biomes: &[i32]) {
+		if biomes.len() == BIOME_COUNT {
+			self.biomes.copy_from_slice(biomes);
+		}
+	}
+
+	/// Gets a block using coordinates local to the chunk
+	pub fn get_block(&self, x: usize, y: usize, z: usize) -> u16 {
+		match self.sections.get(y >> 4) {
+			Some(Some(section)) => section.get(x, y & 15, z),
+			_ => 0,
+		}
+	}
+
+	/// Finds the height of the column, which is one above the highest non-air block
+	pub fn height(&self, x: usize, z: usize) -> usize {
+		for (index, section) in self.sections.iter().enumerate().rev() {
+			if let Some(section) = section {
+				if section.is_empty() {
+					continue;
+				}
+				for y in (0..16).rev() {
+					if section.get(x, y, z) != 0 {
+						return (index << 4) + y + 1;
+					}
+				}
+			}
+		}
+		0
+	}
+
+	pub fn section(&self, y: usize) -> Option<&ChunkSection> {
+		self.sections.get(y).and_then(|s| s.as_ref())
+	}
+
+	pub fn sections(&self) -> impl Iterator<Item = (usize, &ChunkSection)> {
+		self.sections
+			.iter()
+			.enumerate()
+			.filter_map(|(y, s)| s.as_ref().map(|s| (y, s)))
+	}
+
+	/// Sets a block using coordinates local to the chunk, returning the previous state
+	pub fn set_block(&mut self, x: usize, y: usize, z: usize, state: u16) -> u16 {
+		let index = y >> 4;
+		if index >= SECTION_COUNT {
+			return 0;
+		}
+		if self.sections[index].is_none() {
+			if state == 0 {
+				return 0;
+			}
+			self.sections[index] = Some(ChunkSection::new());
+		}
+		let old = self.sections[index]
+			.as_mut()
+			.unwrap()
+			.set(x, y & 15, z, state);
+		if self.sections[index].as_ref().unwrap().is_empty() {
+			self.sections[index] = None;
+		}
+		old
+	}
+}
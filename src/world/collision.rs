@@ -0,0 +1,70 @@
+use crate::world::{registry, BlockPos};
+
+/// An axis aligned box in world coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+	pub min: (f64, f64, f64),
+	pub max: (f64, f64, f64),
+}
+
+impl BoundingBox {
+	/// Creates a box standing on the given point, like an entity's hitbox
+	pub fn standing(x: f64, y: f64, z: f64, width: f64, height: f64) -> Self {
+		let half = width / 2.0;
+		Self {
+			min: (x - half, y, z - half),
+			max: (x + half, y + height, z + half),
+		}
+	}
+
+	/// Grows the box by the same amount in every direction, or shrinks it if the amount is negative
+	pub fn grow(&self, amount: f64) -> Self {
+		Self {
+			min: (
+				self.min.0 - amount,
+				self.min.1 - amount,
+				self.min.2 - amount,
+			),
+			max: (
+				self.max.0 + amount,
+				self.max.1 + amount,
+				self.max.2 + amount,
+			),
+		}
+	}
+
+	/// Whether the two boxes overlap, not counting boxes that only touch
+	pub fn intersects(&self, other: &BoundingBox) -> bool {
+		self.min.0 < other.max.0
+			&& self.max.0 > other.min.0
+			&& self.min.1 < other.max.1
+			&& self.max.1 > other.min.1
+			&& self.min.2 < other.max.2
+			&& self.max.2 > other.min.2
+	}
+
+	/// Every block position the box overlaps
+	pub fn blocks(&self) -> impl Iterator<Item = BlockPos> {
+		let (min_x, min_y, min_z) = (
+			self.min.0.floor() as i32,
+			self.min.1.floor() as i32,
+			self.min.2.floor() as i32,
+		);
+		let (max_x, max_y, max_z) = (
+			self.max.0.ceil() as i32,
+			self.max.1.ceil() as i32,
+			self.max.2.ceil() as i32,
+		);
+		(min_y..max_y).flat_map(move |y| {
+			(min_z..max_z).flat_map(move |z| (min_x..max_x).map(move |x| BlockPos::new(x, y, z)))
+		})
+	}
+}
+
+/// Whether a block state is a full cube that nothing can pass through
+///
+/// Blocks with any other shape count as empty, so that movement checks never stop a player from
+/// walking somewhere they could legitimately go.
+pub fn is_solid(state: u16) -> bool {
+	registry::material(state).solid
+}
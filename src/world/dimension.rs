@@ -0,0 +1,166 @@
+use ::serde::Deserialize;
+
+use crate::{compound, nbt::Tag};
+
+/// The kind of dimension a world behaves like on the client
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Dimension {
+	Overworld,
+	TheNether,
+	TheEnd,
+}
+
+const DIMENSIONS: &[Dimension] = &[
+	Dimension::Overworld,
+	Dimension::TheNether,
+	Dimension::TheEnd,
+];
+
+impl Dimension {
+	/// Biome ID every chunk in this dimension is filled with
+	pub fn biome(&self) -> i32 {
+		match self {
+			Self::Overworld => 1,
+			Self::TheNether => 8,
+			Self::TheEnd => 9,
+		}
+	}
+
+	pub fn identifier(&self) -> &'static str {
+		match self {
+			Self::Overworld => "minecraft:overworld",
+			Self::TheNether => "minecraft:the_nether",
+			Self::TheEnd => "minecraft:the_end",
+		}
+	}
+
+	/// The dimension type element sent in Join Game and Respawn
+	pub fn element(&self) -> Tag {
+		match self {
+			Self::Overworld => compound! {
+				"piglin_safe" => Tag::Byte(0),
+				"natural" => Tag::Byte(1),
+				"ambient_light" => Tag::Float(0.0),
+				"infiniburn" => Tag::String(String::from("minecraft:infiniburn_overworld")),
+				"respawn_anchor_works" => Tag::Byte(0),
+				"has_skylight" => Tag::Byte(1),
+				"bed_works" => Tag::Byte(1),
+				"effects" => Tag::String(String::from("minecraft:overworld")),
+				"has_raids" => Tag::Byte(1),
+				"logical_height" => Tag::Int(256),
+				"coordinate_scale" => Tag::Double(1.0),
+				"ultrawarm" => Tag::Byte(0),
+				"has_ceiling" => Tag::Byte(0),
+			},
+			Self::TheNether => compound! {
+				"piglin_safe" => Tag::Byte(1),
+				"natural" => Tag::Byte(0),
+				"ambient_light" => Tag::Float(0.1),
+				"fixed_time" => Tag::Long(18000),
+				"infiniburn" => Tag::String(String::from("minecraft:infiniburn_nether")),
+				"respawn_anchor_works" => Tag::Byte(1),
+				"has_skylight" => Tag::Byte(0),
+				"bed_works" => Tag::Byte(0),
+				"effects" => Tag::String(String::from("minecraft:the_nether")),
+				"has_raids" => Tag::Byte(0),
+				"logical_height" => Tag::Int(128),
+				"coordinate_scale" => Tag::Double(8.0),
+				"ultrawarm" => Tag::Byte(1),
+				"has_ceiling" => Tag::Byte(1),
+			},
+			Self::TheEnd => compound! {
+				"piglin_safe" => Tag::Byte(0),
+				"natural" => Tag::Byte(0),
+				"ambient_light" => Tag::Float(0.0),
+				"fixed_time" => Tag::Long(6000),
+				"infiniburn" => Tag::String(String::from("minecraft:infiniburn_end")),
+				"respawn_anchor_works" => Tag::Byte(0),
+				"has_skylight" => Tag::Byte(0),
+				"bed_works" => Tag::Byte(0),
+				"effects" => Tag::String(String::from("minecraft:the_end")),
+				"has_raids" => Tag::Byte(1),
+				"logical_height" => Tag::Int(256),
+				"coordinate_scale" => Tag::Double(1.0),
+				"ultrawarm" => Tag::Byte(0),
+				"has_ceiling" => Tag::Byte(0),
+			},
+		}
+	}
+}
+
+fn biome(
+	precipitation: &str,
+	temperature: f32,
+	downfall: f32,
+	category: &str,
+	sky: i32,
+	fog: i32,
+) -> Tag {
+	compound! {
+		"precipitation" => Tag::String(precipitation.into()),
+		"depth" => Tag::Float(0.125),
+		"temperature" => Tag::Float(temperature),
+		"scale" => Tag::Float(0.05),
+		"downfall" => Tag::Float(downfall),
+		"category" => Tag::String(category.into()),
+		"effects" => compound! {
+			"sky_color" => Tag::Int(sky),
+			"water_fog_color" => Tag::Int(329011),
+			"fog_color" => Tag::Int(fog),
+			"water_color" => Tag::Int(4159204),
+			"mood_sound" => compound! {
+				"tick_delay" => Tag::Int(6000),
+				"offset" => Tag::Double(2.0),
+				"sound" => Tag::String(String::from("minecraft:ambient.cave")),
+				"block_search_extent" => Tag::Int(8),
+			},
+		},
+	}
+}
+
+fn registry_entry(name: &str, id: i32, element: Tag) -> Tag {
+	compound! {
+		"name" => Tag::String(name.into()),
+		"id" => Tag::Int(id),
+		"element" => element,
+	}
+}
+
+/// Builds the dimension codec sent in Join Game, which registers every dimension type and biome
+pub fn codec() -> Tag {
+	let dimensions = DIMENSIONS
+		.iter()
+		.enumerate()
+		.map(|(id, dimension)| {
+			registry_entry(dimension.identifier(), id as i32, dimension.element())
+		})
+		.collect();
+	let biomes = vec![
+		registry_entry(
+			"minecraft:plains",
+			Dimension::Overworld.biome(),
+			biome("rain", 0.8, 0.4, "plains", 7907327, 12638463),
+		),
+		registry_entry(
+			"minecraft:nether_wastes",
+			Dimension::TheNether.biome(),
+			biome("none", 2.0, 0.0, "nether", 7254527, 3344392),
+		),
+		registry_entry(
+			"minecraft:the_end",
+			Dimension::TheEnd.biome(),
+			biome("none", 0.5, 0.5, "the_end", 0, 10518688),
+		),
+	];
+	compound! {
+		"minecraft:dimension_type" => compound! {
+			"type" => Tag::String(String::from("minecraft:dimension_type")),
+			"value" => Tag::List(dimensions),
+		},
+		"minecraft:worldgen/biome" => compound! {
+			"type" => Tag::String(String::from("minecraft:worldgen/biome")),
+			"value" => Tag::List(biomes),
+		},
+	}
+}
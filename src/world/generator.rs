@@ -0,0 +1,199 @@
+use crate::{
+	config::WorldConfig,
+	types::BasaltError,
+	world::chunk::{Chunk, ChunkPos},
+};
+
+/// Produces chunks that have never been generated before
+pub trait Generator {
+	fn generate(&self, pos: ChunkPos) -> Chunk;
+
+	/// Flat worlds have their horizon drawn at y=0 instead of sea level
+	fn is_flat(&self) -> bool {
+		false
+	}
+}
+
+/// Generates a superflat world made of horizontal layers
+pub struct Flat {
+	/// Block states from the bottom of the world upwards
+	layers: Vec<u16>,
+}
+
+impl Flat {
+	pub fn new(layers: Vec<u16>) -> Self {
+		Self { layers }
+	}
+}
+
+impl Default for Flat {
+	/// Bedrock, two layers of dirt and a layer of grass
+	fn default() -> Self {
+		Self::new(vec![33, 10, 10, 9])
+	}
+}
+
+impl Generator for Flat {
+	fn generate(&self, pos: ChunkPos) -> Chunk {
+		let mut chunk = Chunk::new(pos);
+		for (y, state) in self.layers.iter().enumerate() {
+			for z in 0..16 {
+				for x in 0..16 {
+					chunk.set_block(x, y, z, *state);
+				}
+			}
+		}
+		chunk
+	}
+
+	fn is_flat(&self) -> bool {
+		true
+	}
+}
+
+/// Generates nothing but air
+pub struct Void;
+
+impl Generator for Void {
+	fn generate(&self, pos: ChunkPos) -> Chunk {
+		Chunk::new(pos)
+	}
+}
+
+/// Generates rolling hills of grass and stone with lakes and beaches below sea level, shaped by the
+/// world's seed
+pub struct Hills {
+	seed: i64,
+}
+
+impl Hills {
+	const SEA_LEVEL: usize = 62;
+	const BEDROCK: u16 = 33;
+	const STONE: u16 = 1;
+	const DIRT: u16 = 10;
+	const GRASS: u16 = 9;
+	const SAND: u16 = 66;
+	const WATER: u16 = 34;
+
+	pub fn new(seed: i64) -> Self {
+		Self { seed }
+	}
+
+	/// A random value from 0 to 1 that is always the same for the same seed, octave and corner
+	fn random(&self, octave: i64, x: i64, z: i64) -> f64 {
+		let mut hash = self.seed as u64 ^ (octave as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+		for value in &[x, z] {
+			hash = (hash ^ *value as u64).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+			hash ^= hash >> 31;
+		}
+		(hash >> 11) as f64 / (1u64 << 53) as f64
+	}
+
+	/// Value noise smoothly interpolated between random values on a grid of the given size
+	fn noise(&self, octave: i64, x: i32, z: i32, size: i32) -> f64 {
+		let (cx, cz) = (x.div_euclid(size) as i64, z.div_euclid(size) as i64);
+		let fade = |t: f64| t * t * (3.0 - 2.0 * t);
+		let tx = fade(x.rem_euclid(size) as f64 / size as f64);
+		let tz = fade(z.rem_euclid(size) as f64 / size as f64);
+		let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+		lerp(
+			lerp(
+				self.random(octave, cx, cz),
+				self.random(octave, cx + 1, cz),
+				tx,
+			),
+			lerp(
+				self.random(octave, cx, cz + 1),
+				self.random(octave, cx + 1, cz + 1),
+				tx,
+			),
+			tz,
+		)
+	}
+
+	/// Height of the top block of a column
+	fn height(&self, x: i32, z: i32) -> usize {
+		let height = 50.0
+			+ self.noise(0, x, z, 64) * 32.0
+			+ self.noise(1, x, z, 16) * 8.0
+			+ self.noise(2, x, z, 4) * 2.0;
+		height as usize
+	}
+}
+
+impl Generator for Hills {
+	fn generate(&self, pos: ChunkPos) -> Chunk {
+		let mut chunk = Chunk::new(pos);
+		for z in 0..16 {
+			for x in 0..16 {
+				let height = self.height((pos.x << 4) + x as i32, (pos.z << 4) + z as i32);
+				let underwater = height < Self::SEA_LEVEL;
+				chunk.set_block(x, 0, z, Self::BEDROCK);
+				for y in 1..=height {
+					let state = if y + 3 < height {
+						Self::STONE
+					} else if underwater || height <= Self::SEA_LEVEL + 1 {
+						Self::SAND
+					} else if y < height {
+						Self::DIRT
+					} else {
+						Self::GRASS
+					};
+					chunk.set_block(x, y, z, state);
+				}
+				for y in height + 1..=Self::SEA_LEVEL {
+					chunk.set_block(x, y, z, Self::WATER);
+				}
+			}
+		}
+		chunk
+	}
+}
+
+/// Creates the generator named in a world's configuration
+pub fn from_config(config: &WorldConfig) -> Result<Box<dyn Generator + Send + Sync>, BasaltError> {
+	match config.generator.as_str() {
+		"flat" => Ok(Box::new(match &config.layers {
+			Some(layers) => Flat::new(layers.clone()),
+			None => Flat::default(),
+		})),
+		"hills" => Ok(Box::new(Hills::new(config.seed.unwrap_or(0)))),
+		"void" => Ok(Box::new(Void)),
+		other => Err(BasaltError::new(format!(
+			"Unknown generator \"{}\" for world \"{}\"",
+			other, config.name
+		))),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn heights(generator: &Hills) -> Vec<usize> {
+		(-40..40).map(|x| generator.height(x * 7, x * 3)).collect()
+	}
+
+	#[test]
+	fn hills_follow_the_seed() {
+		assert_eq!(heights(&Hills::new(42)), heights(&Hills::new(42)));
+		assert_ne!(heights(&Hills::new(42)), heights(&Hills::new(43)));
+		assert!(heights(&Hills::new(42))
+			.iter()
+			.all(|height| (50..92).contains(height)));
+	}
+
+	#[test]
+	fn hills_are_filled_up_to_sea_level() {
+		let generator = Hills::new(7);
+		let chunk = generator.generate(ChunkPos::new(-2, 5));
+		for z in 0..16 {
+			for x in 0..16 {
+				let height = generator.height(-32 + x as i32, 80 + z as i32);
+				assert_eq!(chunk.get_block(x, 0, z), Hills::BEDROCK);
+				assert_ne!(chunk.get_block(x, height, z), 0);
+				assert_eq!(chunk.height(x, z), height.max(Hills::SEA_LEVEL) + 1);
+			}
+		}
+	}
+}
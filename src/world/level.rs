@@ -0,0 +1,197 @@
+use ::std::{
+	fs,
+	io::{Error, ErrorKind, Result},
+	path::{Path, PathBuf},
+};
+
+use crate::{
+	compound, gzip,
+	modern::types::ModernEncodable,
+	nbt::{Nbt, Tag},
+	world::{storage::DATA_VERSION, Weather},
+};
+
+/// Version of the `level.dat` format vanilla has used since Anvil
+const LEVEL_VERSION: i32 = 19133;
+
+/// The parts of a world's `level.dat` that change while the server is running
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LevelData {
+	/// Number of ticks the world has existed for
+	pub age: i64,
+	/// Time of day in ticks
+	pub time: i64,
+	pub weather: Weather,
+	/// Ticks until the weather changes, if it is set to
+	pub weather_time: Option<i32>,
+}
+
+fn path(directory: &Path) -> PathBuf {
+	directory.join("level.dat")
+}
+
+/// Reads the root of a `level.dat`, returning None if there isn't one
+fn read(directory: &Path) -> Result<Option<Tag>> {
+	let path = path(directory);
+	let data = match fs::read(&path) {
+		Ok(data) => data,
+		Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+		Err(e) => return Err(e),
+	};
+	let decoded = gzip::decompress(&data).and_then(|data| Nbt::read(&mut data.as_slice()));
+	match decoded {
+		Ok(nbt) => Ok(Some(nbt.root)),
+		Err(e) => {
+			// Moved out of the way so saving the world later can't overwrite what might still be
+			// recovered by hand
+			let corrupt = path.with_extension("dat_corrupt");
+			fs::rename(&path, &corrupt)?;
+			Err(Error::new(
+				e.kind(),
+				format!("{} (moved to {})", e, corrupt.display()),
+			))
+		}
+	}
+}
+
+fn int(data: &Tag, name: &str) -> Option<i32> {
+	match data.get(name) {
+		Some(Tag::Int(value)) => Some(*value),
+		_ => None,
+	}
+}
+
+fn flag(data: &Tag, name: &str) -> bool {
+	matches!(data.get(name), Some(Tag::Byte(value)) if *value != 0)
+}
+
+/// Reads the time and weather a world was saved with, which vanilla also reads from the same keys
+pub fn load(directory: &Path) -> Result<Option<LevelData>> {
+	let root = match read(directory)? {
+		Some(root) => root,
+		None => return Ok(None),
+	};
+	let data = match root.get("Data") {
+		Some(data) => data,
+		None => return Ok(None),
+	};
+	let long = |name| match data.get(name) {
+		Some(Tag::Long(value)) => *value,
+		_ => 0,
+	};
+	let weather = match (flag(data, "raining"), flag(data, "thundering")) {
+		(true, true) => Weather::Thunder,
+		(true, false) => Weather::Rain,
+		_ => Weather::Clear,
+	};
+	let weather_time = match weather {
+		Weather::Clear => int(data, "clearWeatherTime"),
+		_ => int(data, "rainTime"),
+	};
+	Ok(Some(LevelData {
+		age: long("Time"),
+		time: long("DayTime"),
+		weather,
+		weather_time: weather_time.filter(|ticks| *ticks > 0),
+	}))
+}
+
+/// Writes the time and weather into a world's `level.dat`, keeping anything else vanilla saved in it
+pub fn save(directory: &Path, name: &str, level: &LevelData) -> Result<()> {
+	let mut root = read(directory)?.unwrap_or_else(|| compound! {});
+	let values = match &mut root {
+		Tag::Compound(values) => values,
+		_ => {
+			return Err(Error::new(
+				ErrorKind::InvalidData,
+				"level.dat isn't a compound",
+			))
+		}
+	};
+	let data = values
+		.entry(String::from("Data"))
+		.or_insert_with(|| compound! {});
+	let data = match data {
+		Tag::Compound(data) => data,
+		_ => return Err(Error::new(ErrorKind::InvalidData, "level.dat has no data")),
+	};
+	let weather_time = level.weather_time.unwrap_or(0);
+	let (raining, thundering, clear_time) = match level.weather {
+		Weather::Clear => (false, false, weather_time),
+		Weather::Rain => (true, false, 0),
+		Weather::Thunder => (true, true, 0),
+	};
+	let rain_time = if raining { weather_time } else { 0 };
+	let updated = vec![
+		("DataVersion", Tag::Int(DATA_VERSION)),
+		("version", Tag::Int(LEVEL_VERSION)),
+		("Time", Tag::Long(level.age)),
+		("DayTime", Tag::Long(level.time)),
+		("raining", Tag::Byte(raining as i8)),
+		("thundering", Tag::Byte(thundering as i8)),
+		("rainTime", Tag::Int(rain_time)),
+		(
+			"thunderTime",
+			Tag::Int(if thundering { rain_time } else { 0 }),
+		),
+		("clearWeatherTime", Tag::Int(clear_time)),
+	];
+	for (key, value) in updated {
+		data.insert(String::from(key), value);
+	}
+	data.entry(String::from("LevelName"))
+		.or_insert_with(|| Tag::String(String::from(name)));
+	let mut buffer = Vec::new();
+	Nbt::new(root).write(&mut buffer)?;
+	// Written to another file first so a crash part way through can't leave the old data half
+	// overwritten
+	let path = path(directory);
+	let temporary = path.with_extension("dat_tmp");
+	fs::write(&temporary, gzip::compress(&buffer))?;
+	fs::rename(&temporary, &path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn keeps_what_it_doesnt_know_about() {
+		let directory = ::std::env::temp_dir().join("basalt-level");
+		let _ = fs::remove_dir_all(&directory);
+		fs::create_dir_all(&directory).unwrap();
+		assert_eq!(load(&directory).unwrap(), None);
+		let root = compound! {
+			"Data" => compound! {
+				"LevelName" => Tag::String(String::from("Vanilla")),
+				"GameType" => Tag::Int(1),
+			},
+		};
+		let mut buffer = Vec::new();
+		Nbt::new(root).write(&mut buffer).unwrap();
+		fs::write(path(&directory), gzip::compress(&buffer)).unwrap();
+		let level = LevelData {
+			age: 123_456,
+			time: 18000,
+			weather: Weather::Thunder,
+			weather_time: Some(600),
+		};
+		save(&directory, "world", &level).unwrap();
+		assert_eq!(load(&directory).unwrap(), Some(level));
+		let data = read(&directory).unwrap().unwrap();
+		let data = data.get("Data").unwrap();
+		assert_eq!(
+			data.get("LevelName"),
+			Some(&Tag::String(String::from("Vanilla")))
+		);
+		assert_eq!(data.get("GameType"), Some(&Tag::Int(1)));
+		let clear = LevelData {
+			weather: Weather::Clear,
+			weather_time: None,
+			..level
+		};
+		save(&directory, "world", &clear).unwrap();
+		assert_eq!(load(&directory).unwrap(), Some(clear));
+		let _ = fs::remove_dir_all(&directory);
+	}
+}
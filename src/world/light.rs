@@ -0,0 +1,446 @@
+use ::std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::world::{
+	chunk::{Chunk, ChunkPos, SECTION_COUNT},
+	registry,
+};
+
+/// Light sections include one below and one above the block sections
+pub const LIGHT_SECTION_COUNT: usize = SECTION_COUNT + 2;
+pub const NIBBLE_ARRAY_SIZE: usize = 2048;
+pub const MAX_LIGHT: u8 = 15;
+
+const DIRECTIONS: [(i32, i32, i32); 6] = [
+	(0, -1, 0),
+	(0, 1, 0),
+	(-1, 0, 0),
+	(1, 0, 0),
+	(0, 0, -1),
+	(0, 0, 1),
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LightType {
+	Sky,
+	Block,
+}
+
+/// 4096 light levels packed two to a byte
+#[derive(Clone)]
+pub struct NibbleArray {
+	data: Vec<u8>,
+}
+
+impl NibbleArray {
+	pub fn new(level: u8) -> Self {
+		Self {
+			data: vec![(level << 4) | level; NIBBLE_ARRAY_SIZE],
+		}
+	}
+
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.data
+	}
+
+	pub fn get(&self, x: usize, y: usize, z: usize) -> u8 {
+		let index = (y << 8) | (z << 4) | x;
+		(self.data[index >> 1] >> ((index & 1) << 2)) & 0xF
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.data.iter().all(|b| *b == 0)
+	}
+
+	pub fn set(&mut self, x: usize, y: usize, z: usize, level: u8) {
+		let index = (y << 8) | (z << 4) | x;
+		let shift = (index & 1) << 2;
+		let byte = &mut self.data[index >> 1];
+		*byte = (*byte & !(0xF << shift)) | ((level & 0xF) << shift);
+	}
+}
+
+/// Sky and block light for every section of a chunk, with index 0 being the section below y=0
+#[derive(Clone)]
+pub struct ChunkLight {
+	pub sky: Vec<NibbleArray>,
+	pub block: Vec<NibbleArray>,
+}
+
+impl ChunkLight {
+	pub fn new() -> Self {
+		let mut sky = vec![NibbleArray::new(0); LIGHT_SECTION_COUNT];
+		sky[LIGHT_SECTION_COUNT - 1] = NibbleArray::new(MAX_LIGHT);
+		Self {
+			sky,
+			block: vec![NibbleArray::new(0); LIGHT_SECTION_COUNT],
+		}
+	}
+
+	fn array(&self, kind: LightType) -> &Vec<NibbleArray> {
+		match kind {
+			LightType::Sky => &self.sky,
+			LightType::Block => &self.block,
+		}
+	}
+
+	/// Gets a light level using coordinates local to the chunk
+	pub fn get(&self, kind: LightType, x: usize, y: usize, z: usize) -> u8 {
+		self.array(kind)[(y >> 4) + 1].get(x, y & 15, z)
+	}
+
+	pub fn set(&mut self, kind: LightType, x: usize, y: usize, z: usize, level: u8) {
+		let array = match kind {
+			LightType::Sky => &mut self.sky,
+			LightType::Block => &mut self.block,
+		};
+		array[(y >> 4) + 1].set(x, y & 15, z, level);
+	}
+}
+
+/// How much light a block state absorbs, from 0 (fully transparent) to 15
+pub fn opacity(state: u16) -> u8 {
+	registry::material(state).opacity
+}
+
+/// How much light a block state gives off
+pub fn emission(state: u16) -> u8 {
+	registry::material(state).emission
+}
+
+/// Propagates light between loaded chunks, remembering which chunks it changed
+pub struct LightEngine<'a> {
+	chunks: &'a mut HashMap<ChunkPos, Chunk>,
+	changed: HashSet<ChunkPos>,
+}
+
+impl<'a> LightEngine<'a> {
+	pub fn new(chunks: &'a mut HashMap<ChunkPos, Chunk>) -> Self {
+		Self {
+			chunks,
+			changed: HashSet::new(),
+		}
+	}
+
+	/// Consumes the engine, returning every chunk whose light was modified
+	pub fn changed(self) -> HashSet<ChunkPos> {
+		self.changed
+	}
+
+	fn block(&self, x: i32, y: i32, z: i32) -> Option<u16> {
+		let chunk = self.chunks.get(&ChunkPos::from_block(x, z))?;
+		Some(chunk.get_block((x & 15) as usize, y as usize, (z & 15) as usize))
+	}
+
+	fn get(&self, kind: LightType, x: i32, y: i32, z: i32) -> Option<u8> {
+		if y < 0 || y > 255 {
+			return None;
+		}
+		let chunk = self.chunks.get(&ChunkPos::from_block(x, z))?;
+		Some(
+			chunk
+				.light
+				.get(kind, (x & 15) as usize, y as usize, (z & 15) as usize),
+		)
+	}
+
+	fn set(&mut self, kind: LightType, x: i32, y: i32, z: i32, level: u8) {
+		let pos = ChunkPos::from_block(x, z);
+		if let Some(chunk) = self.chunks.get_mut(&pos) {
+			chunk.light.set(
+				kind,
+				(x & 15) as usize,
+				y as usize,
+				(z & 15) as usize,
+				level,
+			);
+			self.changed.insert(pos);
+		}
+	}
+
+	/// Lights a freshly generated chunk and lets light flow in from its loaded neighbours
+	pub fn light_chunk(&mut self, pos: ChunkPos) {
+		let mut sky = VecDeque::new();
+		let mut block = VecDeque::new();
+		let mut top = 0;
+		if let Some(chunk) = self.chunks.get_mut(&pos) {
+			chunk.light = ChunkLight::new();
+			for z in 0..16 {
+				for x in 0..16 {
+					top = top.max(chunk.height(x, z));
+				}
+			}
+			for z in 0..16 {
+				for x in 0..16 {
+					let mut open = true;
+					for y in (0..256).rev() {
+						let state = chunk.get_block(x, y, z);
+						let world = ((pos.x << 4) + x as i32, y as i32, (pos.z << 4) + z as i32);
+						// Sky light falls straight down without dimming until something absorbs it
+						open = open && opacity(state) == 0;
+						if open {
+							chunk.light.set(LightType::Sky, x, y, z, MAX_LIGHT);
+							if y <= top {
+								sky.push_back(world);
+							}
+						}
+						let emitted = emission(state);
+						if emitted > 0 {
+							chunk.light.set(LightType::Block, x, y, z, emitted);
+							block.push_back(world);
+						}
+					}
+				}
+			}
+			self.changed.insert(pos);
+		} else {
+			return;
+		}
+		// Light sitting on the borders of neighbouring chunks can now spread into this one
+		for (dx, dz) in &[(-1, 0), (1, 0), (0, -1), (0, 1)] {
+			let neighbour = ChunkPos::new(pos.x + dx, pos.z + dz);
+			if !self.chunks.contains_key(&neighbour) {
+				continue;
+			}
+			for i in 0..16 {
+				let (x, z) = match (dx, dz) {
+					(-1, 0) => (15, i),
+					(1, 0) => (0, i),
+					(0, -1) => (i, 15),
+					_ => (i, 0),
+				};
+				let x = (neighbour.x << 4) + x;
+				let z = (neighbour.z << 4) + z;
+				for y in 0..256 {
+					if self.get(LightType::Sky, x, y, z).unwrap_or(0) > 1 {
+						sky.push_back((x, y, z));
+					}
+					if self.get(LightType::Block, x, y, z).unwrap_or(0) > 1 {
+						block.push_back((x, y, z));
+					}
+				}
+			}
+		}
+		self.propagate(LightType::Sky, sky);
+		self.propagate(LightType::Block, block);
+	}
+
+	/// Updates light around a block that changed from one state to another
+	pub fn update_block(&mut self, x: i32, y: i32, z: i32, old: u16, new: u16) {
+		if opacity(old) == opacity(new) && emission(old) == emission(new) {
+			return;
+		}
+		for kind in &[LightType::Sky, LightType::Block] {
+			let level = self.get(*kind, x, y, z).unwrap_or(0);
+			self.set(*kind, x, y, z, 0);
+			let mut relight = self.unpropagate(*kind, x, y, z, level);
+			if *kind == LightType::Block {
+				let emitted = emission(new);
+				if emitted > 0 {
+					self.set(*kind, x, y, z, emitted);
+					relight.push_back((x, y, z));
+				}
+			}
+			if *kind == LightType::Sky && y == 255 && opacity(new) == 0 {
+				self.set(*kind, x, y, z, MAX_LIGHT);
+				relight.push_back((x, y, z));
+			}
+			// Neighbours may be able to light the block back up if it became more transparent
+			for (dx, dy, dz) in &DIRECTIONS {
+				if self.get(*kind, x + dx, y + dy, z + dz).unwrap_or(0) > 0 {
+					relight.push_back((x + dx, y + dy, z + dz));
+				}
+			}
+			self.propagate(*kind, relight);
+		}
+	}
+
+	/// Spreads light outwards from every queued position
+	fn propagate(&mut self, kind: LightType, mut queue: VecDeque<(i32, i32, i32)>) {
+		while let Some((x, y, z)) = queue.pop_front() {
+			let level = match self.get(kind, x, y, z) {
+				Some(level) if level > 1 => level,
+				_ => continue,
+			};
+			for (dx, dy, dz) in &DIRECTIONS {
+				let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+				let current = match self.get(kind, nx, ny, nz) {
+					Some(current) => current,
+					None => continue,
+				};
+				let absorbed = opacity(self.block(nx, ny, nz).unwrap_or(0));
+				let next =
+					if kind == LightType::Sky && *dy == -1 && level == MAX_LIGHT && absorbed == 0 {
+						MAX_LIGHT
+					} else {
+						level.saturating_sub(absorbed.max(1))
+					};
+				if next > current {
+					self.set(kind, nx, ny, nz, next);
+					queue.push_back((nx, ny, nz));
+				}
+			}
+		}
+	}
+
+	/// Darkens everything that was lit by a position, returning the brighter positions that should
+	/// spread their light back into the darkened area
+	fn unpropagate(
+		&mut self,
+		kind: LightType,
+		x: i32,
+		y: i32,
+		z: i32,
+		level: u8,
+	) -> VecDeque<(i32, i32, i32)> {
+		let mut queue = VecDeque::new();
+		let mut relight = VecDeque::new();
+		queue.push_back((x, y, z, level));
+		while let Some((x, y, z, level)) = queue.pop_front() {
+			for (dx, dy, dz) in &DIRECTIONS {
+				let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+				let current = match self.get(kind, nx, ny, nz) {
+					Some(current) if current > 0 => current,
+					_ => continue,
+				};
+				let beam = kind == LightType::Sky && *dy == -1 && level == MAX_LIGHT;
+				if current < level || (beam && current == MAX_LIGHT) {
+					let emitted = if kind == LightType::Block {
+						emission(self.block(nx, ny, nz).unwrap_or(0))
+					} else {
+						0
+					};
+					self.set(kind, nx, ny, nz, emitted);
+					if emitted > 0 {
+						relight.push_back((nx, ny, nz));
+					}
+					queue.push_back((nx, ny, nz, current));
+				} else {
+					relight.push_back((nx, ny, nz));
+				}
+			}
+		}
+		relight
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const AIR: u16 = 0;
+	const STONE: u16 = 1;
+	const TORCH: u16 = 1435;
+
+	/// Two chunks side by side with a stone floor filling y=0 to y=3
+	fn chunks() -> HashMap<ChunkPos, Chunk> {
+		let mut chunks = HashMap::new();
+		for pos in &[ChunkPos::new(0, 0), ChunkPos::new(1, 0)] {
+			let mut chunk = Chunk::new(*pos);
+			for y in 0..4 {
+				for z in 0..16 {
+					for x in 0..16 {
+						chunk.set_block(x, y, z, STONE);
+					}
+				}
+			}
+			chunks.insert(*pos, chunk);
+		}
+		for pos in &[ChunkPos::new(0, 0), ChunkPos::new(1, 0)] {
+			LightEngine::new(&mut chunks).light_chunk(*pos);
+		}
+		chunks
+	}
+
+	fn light(chunks: &HashMap<ChunkPos, Chunk>, kind: LightType, x: i32, y: i32, z: i32) -> u8 {
+		chunks[&ChunkPos::from_block(x, z)].light.get(
+			kind,
+			(x & 15) as usize,
+			y as usize,
+			(z & 15) as usize,
+		)
+	}
+
+	fn set_block(chunks: &mut HashMap<ChunkPos, Chunk>, x: i32, y: i32, z: i32, state: u16) {
+		let old = chunks
+			.get_mut(&ChunkPos::from_block(x, z))
+			.unwrap()
+			.set_block((x & 15) as usize, y as usize, (z & 15) as usize, state);
+		LightEngine::new(chunks).update_block(x, y, z, old, state);
+	}
+
+	#[test]
+	fn nibble_array() {
+		let mut array = NibbleArray::new(0);
+		array.set(1, 2, 3, 7);
+		array.set(0, 2, 3, 15);
+		assert_eq!(array.get(1, 2, 3), 7);
+		assert_eq!(array.get(0, 2, 3), 15);
+		assert_eq!(array.get(2, 2, 3), 0);
+		assert!(!array.is_empty());
+	}
+
+	#[test]
+	fn sky_light_falls_to_the_ground() {
+		let chunks = chunks();
+		for y in &[4, 100, 255] {
+			assert_eq!(light(&chunks, LightType::Sky, 8, *y, 8), MAX_LIGHT);
+		}
+		assert_eq!(light(&chunks, LightType::Sky, 8, 3, 8), 0);
+		assert_eq!(light(&chunks, LightType::Sky, 8, 0, 8), 0);
+	}
+
+	#[test]
+	fn sky_light_spreads_under_a_roof() {
+		let mut chunks = chunks();
+		for z in 0..16 {
+			for x in 0..8 {
+				set_block(&mut chunks, x, 10, z, STONE);
+			}
+		}
+		assert_eq!(light(&chunks, LightType::Sky, 8, 5, 8), MAX_LIGHT);
+		assert_eq!(light(&chunks, LightType::Sky, 7, 5, 8), 14);
+		assert_eq!(light(&chunks, LightType::Sky, 5, 5, 8), 12);
+		assert_eq!(light(&chunks, LightType::Sky, 0, 9, 8), 7);
+		assert_eq!(light(&chunks, LightType::Sky, 5, 11, 8), MAX_LIGHT);
+	}
+
+	#[test]
+	fn sky_light_returns_when_a_block_is_removed() {
+		let mut chunks = chunks();
+		set_block(&mut chunks, 8, 10, 8, STONE);
+		assert_eq!(light(&chunks, LightType::Sky, 8, 9, 8), 14);
+		assert_eq!(light(&chunks, LightType::Sky, 8, 4, 8), 14);
+		assert_eq!(light(&chunks, LightType::Sky, 9, 9, 8), MAX_LIGHT);
+		set_block(&mut chunks, 8, 10, 8, AIR);
+		assert_eq!(light(&chunks, LightType::Sky, 8, 9, 8), MAX_LIGHT);
+		assert_eq!(light(&chunks, LightType::Sky, 8, 4, 8), MAX_LIGHT);
+	}
+
+	#[test]
+	fn block_light_spreads_across_chunks() {
+		let mut chunks = chunks();
+		set_block(&mut chunks, 15, 4, 8, TORCH);
+		assert_eq!(light(&chunks, LightType::Block, 15, 4, 8), 14);
+		assert_eq!(light(&chunks, LightType::Block, 15, 4, 11), 11);
+		assert_eq!(light(&chunks, LightType::Block, 16, 4, 8), 13);
+		assert_eq!(light(&chunks, LightType::Block, 20, 6, 8), 7);
+		// The stone floor absorbs all of it
+		assert_eq!(light(&chunks, LightType::Block, 15, 3, 8), 0);
+	}
+
+	#[test]
+	fn block_light_is_removed_with_its_source() {
+		let mut chunks = chunks();
+		set_block(&mut chunks, 15, 4, 8, TORCH);
+		set_block(&mut chunks, 8, 4, 8, TORCH);
+		set_block(&mut chunks, 15, 4, 8, AIR);
+		assert_eq!(light(&chunks, LightType::Block, 15, 4, 8), 7);
+		assert_eq!(light(&chunks, LightType::Block, 16, 4, 8), 6);
+		assert_eq!(light(&chunks, LightType::Block, 22, 4, 8), 0);
+		set_block(&mut chunks, 8, 4, 8, AIR);
+		for x in 0..32 {
+			assert_eq!(light(&chunks, LightType::Block, x, 4, 8), 0);
+			assert_eq!(light(&chunks, LightType::Block, x, 5, 8), 0);
+		}
+	}
+}
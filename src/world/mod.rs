@@ -0,0 +1,516 @@
+use ::log::{debug, warn};
+use ::serde::Deserialize;
+use ::std::{
+	collections::{HashMap, HashSet},
+	error::Error,
+	fs, io,
+	path::PathBuf,
+	sync::Arc,
+};
+
+pub mod block;
+pub mod chunk;
+pub mod collision;
+pub mod dimension;
+pub mod generator;
+pub mod level;
+pub mod light;
+pub mod region;
+pub mod registry;
+pub mod storage;
+pub mod tracker;
+
+use crate::{
+	config::WorldConfig,
+	entity::{EntityKind, EntityStore},
+	item::ItemRegistry,
+	player::Position,
+	types::Slot,
+};
+
+use chunk::{Chunk, ChunkPos};
+use collision::BoundingBox;
+use dimension::Dimension;
+use generator::Generator;
+use level::LevelData;
+use light::LightEngine;
+use registry::BlockRegistry;
+use storage::{ChunkReader, ChunkStorage, Container, ReadChunks, SavedChunk};
+
+/// Position of a single block in world coordinates
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BlockPos {
+	pub x: i32,
+	pub y: i32,
+	pub z: i32,
+}
+
+impl BlockPos {
+	pub fn new(x: i32, y: i32, z: i32) -> Self {
+		Self { x, y, z }
+	}
+
+	/// Gets the neighbouring block on the given face, numbered the way the client does
+	pub fn offset(&self, face: u8) -> Self {
+		match face {
+			0 => Self::new(self.x, self.y - 1, self.z),
+			1 => Self::new(self.x, self.y + 1, self.z),
+			2 => Self::new(self.x, self.y, self.z - 1),
+			3 => Self::new(self.x, self.y, self.z + 1),
+			4 => Self::new(self.x - 1, self.y, self.z),
+			_ => Self::new(self.x + 1, self.y, self.z),
+		}
+	}
+}
+
+/// Length of a Minecraft day in ticks
+pub const DAY_LENGTH: i64 = 24000;
+
+/// How dangerous the server is, which is the same in every world
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+	Peaceful,
+	Easy,
+	Normal,
+	Hard,
+}
+
+impl Difficulty {
+	pub const ALL: &'static [Difficulty] = &[
+		Difficulty::Peaceful,
+		Difficulty::Easy,
+		Difficulty::Normal,
+		Difficulty::Hard,
+	];
+
+	pub fn id(&self) -> u8 {
+		*self as u8
+	}
+
+	/// Name used in commands and the config
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::Peaceful => "peaceful",
+			Self::Easy => "easy",
+			Self::Normal => "normal",
+			Self::Hard => "hard",
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Weather {
+	Clear,
+	Rain,
+	Thunder,
+}
+
+pub struct World {
+	pub name: String,
+	pub dimension: Dimension,
+	/// Where the world's data is stored on disk
+	pub directory: PathBuf,
+	pub spawn: (i32, i32, i32),
+	/// Seed shown by `/seed`, which shapes the terrain of generators that aren't flat
+	pub seed: i64,
+	/// Number of ticks the world has existed for
+	pub age: i64,
+	/// Time of day in ticks, where 0 is sunrise
+	pub time: i64,
+	pub weather: Weather,
+	/// Ticks until the weather clears up again, where None means it lasts until it is changed
+	weather_time: Option<i32>,
+	/// Whether the weather changed since the last call to `take_weather_change`
+	weather_changed: bool,
+	chunks: HashMap<ChunkPos, Chunk>,
+	pub entities: EntityStore,
+	generator: Box<dyn Generator + Send + Sync>,
+	/// Blocks changed since the last call to `take_block_changes`, along with their new states
+	block_changes: Vec<(BlockPos, u16)>,
+	/// Chunks whose light changed since the last call to `take_light_updates`
+	light_updates: HashSet<ChunkPos>,
+	/// Items in container blocks that have been opened, keyed by the container's position
+	containers: HashMap<BlockPos, Container>,
+	storage: Arc<ChunkStorage>,
+	/// How many times chunks have been unloaded, so chunks read without the world locked can be
+	/// thrown away if they might have been saved again since
+	unloads: u64,
+	/// Chunks changed since they were last saved
+	dirty: HashSet<ChunkPos>,
+	/// Chunks that were saved but couldn't be loaded, which are never saved over so they can still
+	/// be recovered
+	unreadable: HashSet<ChunkPos>,
+}
+
+impl World {
+	pub fn new(
+		config: &WorldConfig,
+		blocks: Arc<BlockRegistry>,
+		items: Arc<ItemRegistry>,
+	) -> Result<Self, Box<dyn Error>> {
+		let generator = generator::from_config(config)?;
+		let directory = PathBuf::from(&config.directory);
+		fs::create_dir_all(&directory)?;
+		let storage = Arc::new(ChunkStorage::new(directory.join("region"), blocks, items));
+		let level = level::load(&directory)
+			.unwrap_or_else(|e| {
+				warn!("Couldn't load the level data of {}: {}", config.name, e);
+				None
+			})
+			.unwrap_or(LevelData {
+				age: 0,
+				time: 0,
+				weather: Weather::Clear,
+				weather_time: None,
+			});
+		let [x, y, z] = config.spawn;
+		Ok(Self {
+			name: config.name.clone(),
+			dimension: config.dimension,
+			directory,
+			spawn: (x, y, z),
+			seed: config.seed.unwrap_or(0),
+			age: level.age,
+			time: level.time % DAY_LENGTH,
+			weather: level.weather,
+			weather_time: level.weather_time,
+			weather_changed: false,
+			chunks: HashMap::new(),
+			entities: EntityStore::new(),
+			generator,
+			block_changes: Vec::new(),
+			light_updates: HashSet::new(),
+			containers: HashMap::new(),
+			storage,
+			unloads: 0,
+			dirty: HashSet::new(),
+			unreadable: HashSet::new(),
+		})
+	}
+
+	/// Gets a chunk, loading or generating it and lighting it first if it isn't loaded yet
+	///
+	/// Loading reads from disk with the world locked, so chunks that are about to be needed should
+	/// be read ahead of time with a `reader`.
+	pub fn chunk(&mut self, pos: ChunkPos) -> &Chunk {
+		if !self.chunks.contains_key(&pos) {
+			let saved = self.storage.load(pos);
+			self.add_chunk(pos, saved);
+		}
+		&self.chunks[&pos]
+	}
+
+	/// Makes a reader for whichever of the chunks aren't loaded yet, which can read them from disk
+	/// without the world locked, or None if they all are
+	pub fn reader(&self, positions: &[ChunkPos]) -> Option<ChunkReader> {
+		let positions: Vec<_> = positions
+			.iter()
+			.filter(|pos| !self.chunks.contains_key(pos))
+			.copied()
+			.collect();
+		if positions.is_empty() {
+			return None;
+		}
+		Some(ChunkReader {
+			storage: self.storage.clone(),
+			unloads: self.unloads,
+			positions,
+		})
+	}
+
+	/// Adds chunks read by a reader that haven't been loaded some other way in the meantime
+	pub fn add_read_chunks(&mut self, read: ReadChunks) {
+		// A chunk that was unloaded while it was being read may have been saved since, so what was
+		// read could be out of date
+		if read.unloads != self.unloads {
+			return;
+		}
+		for (pos, saved) in read.chunks {
+			if !self.chunks.contains_key(&pos) {
+				self.add_chunk(pos, saved);
+			}
+		}
+	}
+
+	/// Adds a chunk as it was loaded, generating it if it wasn't saved, and lights it
+	fn add_chunk(&mut self, pos: ChunkPos, saved: io::Result<SavedChunk>) {
+		let chunk = match saved {
+			Ok(Some((chunk, containers))) => {
+				self.containers.extend(containers);
+				chunk
+			}
+			Ok(None) => self.generate(pos),
+			Err(e) => {
+				warn!(
+					"Couldn't load chunk {} {} of {}, so it won't be saved: {}",
+					pos.x, pos.z, self.name, e
+				);
+				self.unreadable.insert(pos);
+				self.generate(pos)
+			}
+		};
+		self.chunks.insert(pos, chunk);
+		let mut engine = LightEngine::new(&mut self.chunks);
+		engine.light_chunk(pos);
+		let mut changed = engine.changed();
+		changed.remove(&pos);
+		self.light_updates.extend(changed);
+	}
+
+	fn generate(&self, pos: ChunkPos) -> Chunk {
+		let mut chunk = self.generator.generate(pos);
+		chunk.fill_biome(self.dimension.biome());
+		chunk
+	}
+
+	/// Writes the time and weather along with every chunk that changed since it was last saved,
+	/// returning how many chunks were written
+	pub fn save(&mut self) -> io::Result<usize> {
+		let mut dirty: Vec<_> = self.dirty.drain().collect();
+		dirty.sort_by_key(|pos| (pos.x >> 5, pos.z >> 5));
+		let mut saved = 0;
+		for (index, pos) in dirty.iter().enumerate() {
+			let chunk = match self.chunks.get(pos) {
+				Some(chunk) if !self.unreadable.contains(pos) => chunk,
+				_ => continue,
+			};
+			let containers: Vec<_> = self
+				.containers
+				.iter()
+				.filter(|(position, _)| ChunkPos::from_block(position.x, position.z) == *pos)
+				.map(|(position, container)| (*position, container))
+				.collect();
+			if let Err(e) = self.storage.save(chunk, &containers) {
+				self.dirty.extend(&dirty[index..]);
+				return Err(e);
+			}
+			saved += 1;
+		}
+		let level = LevelData {
+			age: self.age,
+			time: self.time,
+			weather: self.weather,
+			weather_time: self.weather_time,
+		};
+		level::save(&self.directory, &self.name, &level)?;
+		debug!("Saved {} chunks of {}", saved, self.name);
+		Ok(saved)
+	}
+
+	/// Saves and forgets the chunks that are further than the view distance from every player in the
+	/// world, returning how many were unloaded
+	pub fn unload_unseen(&mut self, view_distance: u8) -> io::Result<usize> {
+		let players: Vec<ChunkPos> = self
+			.entities
+			.values()
+			.filter(|entity| entity.kind == EntityKind::Player)
+			.map(|entity| ChunkPos::from_position(entity.position.x, entity.position.z))
+			.collect();
+		// One more chunk is kept around the view so its edge doesn't have to be relit
+		let reach = view_distance as i32 + 1;
+		let unseen: HashSet<ChunkPos> = self
+			.chunks
+			.keys()
+			// Chunks that couldn't be loaded are never saved, so changes to them are kept in memory
+			.filter(|pos| !self.unreadable.contains(pos))
+			.filter(|pos| players.iter().all(|player| player.distance(pos) > reach))
+			.copied()
+			.collect();
+		if unseen.iter().any(|pos| self.dirty.contains(pos)) {
+			self.save()?;
+		}
+		if !unseen.is_empty() {
+			self.unloads += 1;
+		}
+		self.chunks.retain(|pos, _| !unseen.contains(pos));
+		self.containers
+			.retain(|position, _| !unseen.contains(&ChunkPos::from_block(position.x, position.z)));
+		if !unseen.is_empty() {
+			debug!("Unloaded {} chunks of {}", unseen.len(), self.name);
+		}
+		Ok(unseen.len())
+	}
+
+	/// Where players appear in the world, which is the middle of the spawn block
+	pub fn spawn_position(&self) -> Position {
+		let (x, y, z) = self.spawn;
+		Position::new(x as f64 + 0.5, y as f64, z as f64 + 0.5)
+	}
+
+	/// Name of the world as sent to clients
+	pub fn identifier(&self) -> String {
+		format!("basalt:{}", self.name)
+	}
+
+	pub fn is_flat(&self) -> bool {
+		self.generator.is_flat()
+	}
+
+	/// Whether any block that can't be passed through overlaps the box
+	pub fn collides(&mut self, bounds: &BoundingBox) -> bool {
+		bounds
+			.blocks()
+			.any(|pos| collision::is_solid(self.get_block(pos.x, pos.y, pos.z)))
+	}
+
+	/// Whether any block other than air overlaps the box
+	pub fn touches(&mut self, bounds: &BoundingBox) -> bool {
+		bounds
+			.blocks()
+			.any(|pos| self.get_block(pos.x, pos.y, pos.z) != 0)
+	}
+
+	/// Whether a block that can be climbed, such as a ladder or water, overlaps the box
+	pub fn climbable(&mut self, bounds: &BoundingBox) -> bool {
+		bounds
+			.blocks()
+			.any(|pos| registry::material(self.get_block(pos.x, pos.y, pos.z)).climbable)
+	}
+
+	pub fn get_block(&mut self, x: i32, y: i32, z: i32) -> u16 {
+		if y < 0 || y > 255 {
+			return 0;
+		}
+		self.chunk(ChunkPos::from_block(x, z)).get_block(
+			(x & 15) as usize,
+			y as usize,
+			(z & 15) as usize,
+		)
+	}
+
+	/// Sets a block and relights the area around it, returning the previous state
+	pub fn set_block(&mut self, x: i32, y: i32, z: i32, state: u16) -> u16 {
+		if y < 0 || y > 255 {
+			return 0;
+		}
+		let pos = ChunkPos::from_block(x, z);
+		self.chunk(pos);
+		let old = self.chunks.get_mut(&pos).unwrap().set_block(
+			(x & 15) as usize,
+			y as usize,
+			(z & 15) as usize,
+			state,
+		);
+		if old == state {
+			return old;
+		}
+		let mut engine = LightEngine::new(&mut self.chunks);
+		engine.update_block(x, y, z, old, state);
+		self.light_updates.extend(engine.changed());
+		self.block_changes.push((BlockPos::new(x, y, z), state));
+		self.dirty.insert(pos);
+		// Whatever was in a container is lost along with it until items can be dropped
+		self.containers.remove(&BlockPos::new(x, y, z));
+		old
+	}
+
+	/// Gets the items in the container at a position, creating it empty if it hasn't been opened
+	/// yet, and marks its chunk as needing to be saved
+	pub fn container(&mut self, position: BlockPos, size: usize) -> &mut Vec<Option<Slot>> {
+		self.dirty
+			.insert(ChunkPos::from_block(position.x, position.z));
+		let container = self
+			.containers
+			.entry(position)
+			.or_insert_with(|| vec![None; size]);
+		if container.len() < size {
+			container.resize(size, None);
+		}
+		container
+	}
+
+	/// Advances the world by a single tick
+	pub fn tick(&mut self) {
+		self.age += 1;
+		self.time = (self.time + 1) % DAY_LENGTH;
+		if let Some(ticks) = self.weather_time {
+			if ticks <= 1 {
+				self.set_weather(Weather::Clear, None);
+			} else {
+				self.weather_time = Some(ticks - 1);
+			}
+		}
+	}
+
+	/// Changes the weather for a number of ticks, after which it clears up
+	pub fn set_weather(&mut self, weather: Weather, ticks: Option<i32>) {
+		self.weather_changed |= weather != self.weather;
+		self.weather = weather;
+		self.weather_time = ticks;
+	}
+
+	/// Takes the new weather if it changed since this was last called
+	pub fn take_weather_change(&mut self) -> Option<Weather> {
+		if ::std::mem::take(&mut self.weather_changed) {
+			Some(self.weather)
+		} else {
+			None
+		}
+	}
+
+	/// Takes the blocks that need to be resent to clients, in the order they changed
+	pub fn take_block_changes(&mut self) -> Vec<(BlockPos, u16)> {
+		::std::mem::take(&mut self.block_changes)
+	}
+
+	/// Takes the set of chunks that need their light resent to clients
+	pub fn take_light_updates(&mut self) -> HashSet<ChunkPos> {
+		::std::mem::take(&mut self.light_updates)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::std::path::Path;
+
+	use crate::{auth::GameProfile, entity::Entity, player::Rotation};
+
+	fn world(directory: &Path) -> World {
+		let config = WorldConfig {
+			name: String::from("world"),
+			dimension: Dimension::Overworld,
+			directory: directory.to_string_lossy().into_owned(),
+			generator: String::from("flat"),
+			layers: None,
+			seed: None,
+			spawn: [0, 4, 0],
+		};
+		World::new(
+			&config,
+			Arc::new(BlockRegistry::new()),
+			Arc::new(ItemRegistry::new()),
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn unseen_chunks_are_saved_and_unloaded() {
+		let directory = ::std::env::temp_dir().join("basalt-world-unload");
+		let _ = fs::remove_dir_all(&directory);
+		let mut world = world(&directory);
+		let profile = GameProfile::offline(String::from("Alice")).unwrap();
+		world.entities.insert(Entity::player(
+			0,
+			profile,
+			Position::new(8.0, 4.0, 8.0),
+			Rotation {
+				yaw: 0.0,
+				pitch: 0.0,
+			},
+		));
+		world.set_block(1, 10, 1, 1);
+		world.set_block(200, 10, 200, 1);
+		world.time = 6000;
+		assert_eq!(world.unload_unseen(2).unwrap(), 1);
+		assert!(world.chunks.contains_key(&ChunkPos::new(0, 0)));
+		assert!(!world.chunks.contains_key(&ChunkPos::new(12, 12)));
+		assert_eq!(world.get_block(200, 10, 200), 1);
+		assert_eq!(world.unload_unseen(2).unwrap(), 1);
+		let mut reloaded = self::world(&directory);
+		assert_eq!(reloaded.time, 6000);
+		assert_eq!(reloaded.get_block(200, 10, 200), 1);
+		let _ = fs::remove_dir_all(&directory);
+	}
+}
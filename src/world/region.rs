@@ -0,0 +1,253 @@
+use ::flate2::{
+	read::{GzDecoder, ZlibDecoder},
+	write::ZlibEncoder,
+	Compression,
+};
+use ::std::{
+	fs::{self, File, OpenOptions},
+	io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write},
+	path::{Path, PathBuf},
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::world::chunk::ChunkPos;
+
+const SECTOR_SIZE: u64 = 4096;
+/// Sectors taken up by the chunk locations and timestamps at the start of every file
+const HEADER_SECTORS: usize = 2;
+/// Number of chunks along each side of a region
+const REGION_SIZE: i32 = 32;
+const COMPRESSION_GZIP: u8 = 1;
+const COMPRESSION_ZLIB: u8 = 2;
+const COMPRESSION_NONE: u8 = 3;
+
+fn invalid(message: String) -> Error {
+	Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Where the region holding a chunk is stored, named the same way as vanilla's `.mca` files
+pub fn path(directory: &Path, pos: ChunkPos) -> PathBuf {
+	directory.join(format!(
+		"r.{}.{}.mca",
+		pos.x.div_euclid(REGION_SIZE),
+		pos.z.div_euclid(REGION_SIZE)
+	))
+}
+
+/// A file of 32x32 chunks in vanilla's Anvil format, each compressed on its own and padded to a
+/// whole number of 4KiB sectors
+pub struct RegionFile {
+	file: File,
+	/// Sector offset of every chunk shifted left by 8 and added to its number of sectors, or 0 for
+	/// chunks that haven't been saved
+	locations: Vec<u32>,
+	/// Whether each sector of the file is taken by the header or a chunk
+	used: Vec<bool>,
+}
+
+impl RegionFile {
+	/// Opens the region holding a chunk, returning None if it doesn't exist and `create` is false
+	pub fn open(directory: &Path, pos: ChunkPos, create: bool) -> Result<Option<Self>> {
+		let path = path(directory, pos);
+		if !create && !path.exists() {
+			return Ok(None);
+		}
+		fs::create_dir_all(directory)?;
+		let mut file = OpenOptions::new()
+			.read(true)
+			.write(true)
+			.create(true)
+			.open(&path)?;
+		let length = file.metadata()?.len();
+		if length < HEADER_SECTORS as u64 * SECTOR_SIZE {
+			file.set_len(HEADER_SECTORS as u64 * SECTOR_SIZE)?;
+		}
+		let mut header = vec![0; SECTOR_SIZE as usize];
+		file.seek(SeekFrom::Start(0))?;
+		file.read_exact(&mut header)?;
+		let locations: Vec<u32> = header
+			.chunks(4)
+			.map(|entry| u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]))
+			.collect();
+		let sectors = ((length + SECTOR_SIZE - 1) / SECTOR_SIZE) as usize;
+		let mut used = vec![false; sectors.max(HEADER_SECTORS)];
+		for entry in used.iter_mut().take(HEADER_SECTORS) {
+			*entry = true;
+		}
+		for location in locations.iter().filter(|location| **location != 0) {
+			let (offset, count) = ((location >> 8) as usize, (location & 0xFF) as usize);
+			if offset < HEADER_SECTORS || offset + count > used.len() {
+				return Err(invalid(format!(
+					"{} has a chunk outside of the file",
+					path.display()
+				)));
+			}
+			for entry in &mut used[offset..offset + count] {
+				*entry = true;
+			}
+		}
+		Ok(Some(Self {
+			file,
+			locations,
+			used,
+		}))
+	}
+
+	fn index(pos: ChunkPos) -> usize {
+		(pos.x.rem_euclid(REGION_SIZE) + pos.z.rem_euclid(REGION_SIZE) * REGION_SIZE) as usize
+	}
+
+	/// Reads and decompresses a chunk's data, returning None if it hasn't been saved
+	pub fn read(&mut self, pos: ChunkPos) -> Result<Option<Vec<u8>>> {
+		let location = self.locations[Self::index(pos)];
+		if location == 0 {
+			return Ok(None);
+		}
+		self.file
+			.seek(SeekFrom::Start((location >> 8) as u64 * SECTOR_SIZE))?;
+		let mut header = [0; 5];
+		self.file.read_exact(&mut header)?;
+		let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+		if length == 0 || length > (location & 0xFF) as u64 * SECTOR_SIZE {
+			return Err(invalid(format!(
+				"Chunk {} {} has an invalid length",
+				pos.x, pos.z
+			)));
+		}
+		let mut compressed = vec![0; length as usize - 1];
+		self.file.read_exact(&mut compressed)?;
+		let mut data = Vec::new();
+		match header[4] {
+			COMPRESSION_GZIP => GzDecoder::new(compressed.as_slice()).read_to_end(&mut data)?,
+			COMPRESSION_ZLIB => ZlibDecoder::new(compressed.as_slice()).read_to_end(&mut data)?,
+			COMPRESSION_NONE => return Ok(Some(compressed)),
+			other => {
+				return Err(invalid(format!(
+					"Chunk {} {} uses unknown compression {}",
+					pos.x, pos.z, other
+				)))
+			}
+		};
+		Ok(Some(data))
+	}
+
+	/// Compresses and writes a chunk's data, moving it elsewhere in the file if it no longer fits
+	pub fn write(&mut self, pos: ChunkPos, data: &[u8]) -> Result<()> {
+		let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+		encoder.write_all(data)?;
+		let compressed = encoder.finish()?;
+		let mut buffer = Vec::with_capacity(compressed.len() + 5);
+		buffer.extend_from_slice(&(compressed.len() as u32 + 1).to_be_bytes());
+		buffer.push(COMPRESSION_ZLIB);
+		buffer.extend_from_slice(&compressed);
+		let count = ((buffer.len() as u64 + SECTOR_SIZE - 1) / SECTOR_SIZE) as usize;
+		if count > 0xFF {
+			return Err(invalid(format!(
+				"Chunk {} {} is too big to be saved",
+				pos.x, pos.z
+			)));
+		}
+		buffer.resize(count * SECTOR_SIZE as usize, 0);
+		let index = Self::index(pos);
+		let location = self.locations[index];
+		let (old_offset, old_count) = ((location >> 8) as usize, (location & 0xFF) as usize);
+		let offset = if location != 0 && count <= old_count {
+			old_offset
+		} else {
+			for entry in &mut self.used[old_offset..old_offset + old_count] {
+				*entry = false;
+			}
+			self.allocate(count)
+		};
+		for entry in &mut self.used[offset..offset + count] {
+			*entry = true;
+		}
+		self.file
+			.seek(SeekFrom::Start(offset as u64 * SECTOR_SIZE))?;
+		self.file.write_all(&buffer)?;
+		let location = ((offset as u32) << 8) | count as u32;
+		self.locations[index] = location;
+		let timestamp = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|time| time.as_secs() as u32)
+			.unwrap_or(0);
+		self.file.seek(SeekFrom::Start(index as u64 * 4))?;
+		self.file.write_all(&location.to_be_bytes())?;
+		self.file
+			.seek(SeekFrom::Start(SECTOR_SIZE + index as u64 * 4))?;
+		self.file.write_all(&timestamp.to_be_bytes())?;
+		Ok(())
+	}
+
+	/// Finds the first run of free sectors long enough for a chunk, growing the file if there is none
+	fn allocate(&mut self, count: usize) -> usize {
+		let mut start = HEADER_SECTORS;
+		for (index, used) in self.used.iter().enumerate().skip(HEADER_SECTORS) {
+			if *used {
+				start = index + 1;
+			} else if index + 1 - start == count {
+				return start;
+			}
+		}
+		if self.used.len() < start + count {
+			self.used.resize(start + count, false);
+		}
+		start
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn directory(name: &str) -> PathBuf {
+		let directory = ::std::env::temp_dir().join(format!("basalt-region-{}", name));
+		let _ = fs::remove_dir_all(&directory);
+		directory
+	}
+
+	#[test]
+	fn paths_match_vanilla() {
+		let directory = Path::new("region");
+		assert_eq!(
+			path(directory, ChunkPos::new(0, 31)),
+			directory.join("r.0.0.mca")
+		);
+		assert_eq!(
+			path(directory, ChunkPos::new(-1, 32)),
+			directory.join("r.-1.1.mca")
+		);
+	}
+
+	#[test]
+	fn chunks_survive_being_moved_and_reopened() {
+		let directory = directory("reopen");
+		let small = vec![7; 100];
+		let large: Vec<u8> = (0..50_000u32)
+			.map(|i| (i.wrapping_mul(2654435761) >> 24) as u8)
+			.collect();
+		let mut region = RegionFile::open(&directory, ChunkPos::new(0, 0), true)
+			.unwrap()
+			.unwrap();
+		region.write(ChunkPos::new(0, 0), &small).unwrap();
+		region.write(ChunkPos::new(1, 0), &small).unwrap();
+		// Too big for the sector it had, so it has to move past the second chunk
+		region.write(ChunkPos::new(0, 0), &large).unwrap();
+		region.write(ChunkPos::new(31, 31), &small).unwrap();
+		drop(region);
+		let mut region = RegionFile::open(&directory, ChunkPos::new(31, 31), false)
+			.unwrap()
+			.unwrap();
+		assert_eq!(region.read(ChunkPos::new(0, 0)).unwrap(), Some(large));
+		assert_eq!(
+			region.read(ChunkPos::new(1, 0)).unwrap(),
+			Some(small.clone())
+		);
+		assert_eq!(region.read(ChunkPos::new(31, 31)).unwrap(), Some(small));
+		assert_eq!(region.read(ChunkPos::new(5, 5)).unwrap(), None);
+		assert!(RegionFile::open(&directory, ChunkPos::new(32, 0), false)
+			.unwrap()
+			.is_none());
+		let _ = fs::remove_dir_all(&directory);
+	}
+}
@@ -0,0 +1,212 @@
+use ::std::collections::HashMap;
+
+use crate::world::block::ToolKind;
+
+/// Protocol version whose block state IDs worlds are stored with
+pub const WORLD_PROTOCOL: i32 = 754;
+
+/// A single block state as listed in a vanilla data report
+#[derive(Clone, Copy, Debug)]
+pub struct BlockState {
+	pub id: u16,
+	pub name: &'static str,
+	pub properties: &'static [(&'static str, &'static str)],
+	/// Whether this is the state the block is in unless something says otherwise
+	pub default: bool,
+	pub material: Material,
+}
+
+impl BlockState {
+	pub fn property(&self, name: &str) -> Option<&'static str> {
+		self.properties
+			.iter()
+			.find(|(key, _)| *key == name)
+			.map(|(_, value)| *value)
+	}
+}
+
+/// How a block state behaves in the world, as listed by block name in `data/materials.json`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Material {
+	/// How long the block takes to break, or None if it can't be broken
+	pub hardness: Option<f32>,
+	/// The kind of tool that breaks the block faster
+	pub tool: Option<ToolKind>,
+	/// Harvest level the tool has to have for the block to drop anything, if it needs a tool at all
+	pub harvest_level: Option<u8>,
+	/// How much light the block absorbs, from 0 (fully transparent) to 15
+	pub opacity: u8,
+	/// How much light the block gives off
+	pub emission: u8,
+	/// Whether the block is a full cube that nothing can pass through
+	pub solid: bool,
+	/// Whether players can move up through the block without jumping, as with ladders and water
+	pub climbable: bool,
+}
+
+impl Material {
+	/// What states missing from the palette behave like
+	const AIR: Self = Self {
+		hardness: Some(0.0),
+		tool: None,
+		harvest_level: None,
+		opacity: 0,
+		emission: 0,
+		solid: false,
+		climbable: false,
+	};
+}
+
+/// A classic block and the modern block it stands for
+pub struct ClassicBlock {
+	id: u8,
+	block: &'static str,
+	/// Properties the modern block has to have, where any not listed can be anything
+	properties: &'static [(&'static str, &'static str)],
+	/// Modern blocks classic has nothing like, which are shown as this block instead
+	substitutes: &'static [&'static str],
+}
+
+include!(concat!(env!("OUT_DIR"), "/blocks.rs"));
+
+/// Looks up the material of a state stored in a world
+pub fn material(state: u16) -> Material {
+	PALETTES
+		.iter()
+		.find(|(protocol, _)| *protocol == WORLD_PROTOCOL)
+		.and_then(|(_, states)| {
+			states
+				.binary_search_by_key(&state, |state| state.id)
+				.ok()
+				.map(|index| states[index].material)
+		})
+		.unwrap_or(Material::AIR)
+}
+
+/// Every block state of a single protocol version
+pub struct Palette {
+	states: &'static [BlockState],
+	/// Indices of each block's states, keyed by the block's name
+	blocks: HashMap<&'static str, Vec<usize>>,
+}
+
+impl Palette {
+	fn new(states: &'static [BlockState]) -> Self {
+		let mut blocks: HashMap<_, Vec<_>> = HashMap::new();
+		for (index, state) in states.iter().enumerate() {
+			blocks.entry(state.name).or_default().push(index);
+		}
+		Self { states, blocks }
+	}
+
+	pub fn get(&self, id: u16) -> Option<&BlockState> {
+		self.states
+			.binary_search_by_key(&id, |state| state.id)
+			.ok()
+			.map(|index| &self.states[index])
+	}
+
+	pub fn default_state(&self, name: &str) -> Option<u16> {
+		self.id(name, &[])
+	}
+
+	/// Finds the state of a block with the given properties, taking any that are left out from the
+	/// block's default state
+	pub fn id(&self, name: &str, properties: &[(&str, &str)]) -> Option<u16> {
+		let states: Vec<_> = self
+			.blocks
+			.get(name)?
+			.iter()
+			.map(|index| &self.states[*index])
+			.collect();
+		let default = states
+			.iter()
+			.find(|state| state.default)
+			.or_else(|| states.first())?;
+		let wanted = |key: &str| {
+			properties
+				.iter()
+				.find(|(name, _)| *name == key)
+				.map(|(_, value)| *value)
+				.or_else(|| default.property(key))
+		};
+		if properties
+			.iter()
+			.any(|(key, _)| default.property(key).is_none())
+		{
+			return None;
+		}
+		states
+			.iter()
+			.find(|state| {
+				state
+					.properties
+					.iter()
+					.all(|(key, value)| wanted(key) == Some(*value))
+			})
+			.map(|state| state.id)
+	}
+}
+
+/// Block states for every supported version, generated from the vanilla data reports in `data`
+pub struct BlockRegistry {
+	palettes: HashMap<i32, Palette>,
+	/// Classic block each modern state is shown as
+	to_classic: HashMap<u16, u8>,
+	/// Modern state each classic block is stored as
+	from_classic: [u16; 256],
+}
+
+impl BlockRegistry {
+	pub fn new() -> Self {
+		let palettes: HashMap<_, _> = PALETTES
+			.iter()
+			.map(|(protocol, states)| (*protocol, Palette::new(states)))
+			.collect();
+		let world = &palettes[&WORLD_PROTOCOL];
+		// Anything classic doesn't have is shown as stone unless something closer is listed
+		let stone = world.default_state("minecraft:stone").unwrap_or(1);
+		let mut from_classic = [stone; 256];
+		for block in CLASSIC {
+			if let Some(id) = world.id(block.block, block.properties) {
+				from_classic[block.id as usize] = id;
+			}
+		}
+		let mut to_classic = HashMap::new();
+		// States matching a classic block exactly come first, then blocks that are only similar
+		for block in CLASSIC.iter().filter(|block| !block.properties.is_empty()) {
+			if let Some(id) = world.id(block.block, block.properties) {
+				to_classic.entry(id).or_insert(block.id);
+			}
+		}
+		for block in CLASSIC.iter().filter(|block| block.properties.is_empty()) {
+			for name in Some(&block.block).into_iter().chain(block.substitutes) {
+				for index in world.blocks.get(name).into_iter().flatten() {
+					to_classic
+						.entry(world.states[*index].id)
+						.or_insert(block.id);
+				}
+			}
+		}
+		Self {
+			palettes,
+			to_classic,
+			from_classic,
+		}
+	}
+
+	/// The block states worlds are stored with
+	pub fn world(&self) -> &Palette {
+		&self.palettes[&WORLD_PROTOCOL]
+	}
+
+	/// Converts a stored block state into the closest classic block
+	pub fn to_classic(&self, state: u16) -> u8 {
+		self.to_classic.get(&state).copied().unwrap_or(1)
+	}
+
+	/// Converts a classic block into the block state it is stored as
+	pub fn from_classic(&self, block: u8) -> u16 {
+		self.from_classic[block as usize]
+	}
+}
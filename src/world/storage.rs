@@ -0,0 +1,395 @@
+use ::log::debug;
+use ::std::{
+	collections::HashMap,
+	io::{Error, ErrorKind, Result},
+	path::PathBuf,
+	sync::{Arc, Mutex},
+};
+
+use crate::{
+	compound,
+	item::ItemRegistry,
+	modern::types::ModernEncodable,
+	nbt::{Nbt, Tag},
+	types::Slot,
+	world::{
+		chunk::{Chunk, ChunkPos, SECTION_COUNT, SECTION_VOLUME},
+		region::RegionFile,
+		registry::BlockRegistry,
+		BlockPos,
+	},
+};
+
+/// Data version of 1.16.5, which vanilla uses to tell which version saved a file
+pub const DATA_VERSION: i32 = 2586;
+/// Fewest bits vanilla uses for each block of a section
+const MIN_BITS: usize = 4;
+
+/// Items kept in a container block, as stored in a world
+pub type Container = Vec<Option<Slot>>;
+
+fn invalid(message: String) -> Error {
+	Error::new(ErrorKind::InvalidData, message)
+}
+
+/// A chunk as it was saved along with the contents of its containers, or None if it wasn't saved
+pub type SavedChunk = Option<(Chunk, HashMap<BlockPos, Container>)>;
+
+/// Saves and loads the chunks of a world in vanilla's region format
+pub struct ChunkStorage {
+	/// The `region` directory of the world
+	directory: PathBuf,
+	blocks: Arc<BlockRegistry>,
+	items: Arc<ItemRegistry>,
+	/// Held while a region file is open, since chunks can be read without the world locked and
+	/// mustn't be read while they are half written
+	files: Mutex<()>,
+}
+
+impl ChunkStorage {
+	pub fn new(directory: PathBuf, blocks: Arc<BlockRegistry>, items: Arc<ItemRegistry>) -> Self {
+		Self {
+			directory,
+			blocks,
+			items,
+			files: Mutex::new(()),
+		}
+	}
+
+	/// Loads a chunk along with the contents of its containers, returning None if it hasn't been
+	/// saved or vanilla didn't finish generating it
+	pub fn load(&self, pos: ChunkPos) -> Result<SavedChunk> {
+		let data = {
+			let _files = self.files.lock().unwrap();
+			match RegionFile::open(&self.directory, pos, false)? {
+				Some(mut region) => region.read(pos)?,
+				None => None,
+			}
+		};
+		let data = match data {
+			Some(data) => data,
+			None => return Ok(None),
+		};
+		let root = Nbt::read(&mut data.as_slice())?.root;
+		let level = root
+			.get("Level")
+			.ok_or_else(|| invalid(format!("Chunk {} {} has no level", pos.x, pos.z)))?;
+		match level.get("Status") {
+			Some(Tag::String(status)) if status != "full" => return Ok(None),
+			_ => (),
+		}
+		let mut chunk = Chunk::new(pos);
+		if let Some(Tag::List(sections)) = level.get("Sections") {
+			for section in sections {
+				self.read_section(&mut chunk, section)?;
+			}
+		}
+		if let Some(Tag::IntArray(biomes)) = level.get("Biomes") {
+			chunk.set_biomes(biomes);
+		}
+		let mut containers = HashMap::new();
+		if let Some(Tag::List(entities)) = level.get("TileEntities") {
+			for entity in entities {
+				if let Some((position, items)) = self.read_container(entity) {
+					containers.insert(position, items);
+				}
+			}
+		}
+		Ok(Some((chunk, containers)))
+	}
+
+	fn read_section(&self, chunk: &mut Chunk, section: &Tag) -> Result<()> {
+		// Sections above and below the world only hold light
+		let y = match section.get("Y") {
+			Some(Tag::Byte(y)) if *y >= 0 && (*y as usize) < SECTION_COUNT => *y as usize,
+			_ => return Ok(()),
+		};
+		let (palette, states) = match (section.get("Palette"), section.get("BlockStates")) {
+			(Some(Tag::List(palette)), Some(Tag::LongArray(states))) => (palette, states),
+			_ => return Ok(()),
+		};
+		let world = self.blocks.world();
+		let palette: Vec<u16> = palette
+			.iter()
+			.map(|entry| {
+				let name = match entry.get("Name") {
+					Some(Tag::String(name)) => name.as_str(),
+					_ => "minecraft:air",
+				};
+				let properties: Vec<(&str, &str)> = match entry.get("Properties") {
+					Some(Tag::Compound(properties)) => properties
+						.iter()
+						.filter_map(|(key, value)| match value {
+							Tag::String(value) => Some((key.as_str(), value.as_str())),
+							_ => None,
+						})
+						.collect(),
+					_ => Vec::new(),
+				};
+				world.id(name, &properties).unwrap_or_else(|| {
+					debug!("Replaced unknown block {} with air", name);
+					0
+				})
+			})
+			.collect();
+		let bits = bits(palette.len());
+		let per_long = 64 / bits;
+		if states.len() != (SECTION_VOLUME + per_long - 1) / per_long {
+			return Err(invalid(format!(
+				"Section {} of chunk {} {} has the wrong number of blocks",
+				y, chunk.pos.x, chunk.pos.z
+			)));
+		}
+		let mask = (1u64 << bits) - 1;
+		for index in 0..SECTION_VOLUME {
+			let long = states[index / per_long] as u64;
+			let value = (long >> ((index % per_long) * bits)) & mask;
+			let state = palette.get(value as usize).copied().unwrap_or(0);
+			if state != 0 {
+				chunk.set_block(
+					index & 15,
+					(y << 4) | (index >> 8),
+					(index >> 4) & 15,
+					state,
+				);
+			}
+		}
+		Ok(())
+	}
+
+	fn read_container(&self, entity: &Tag) -> Option<(BlockPos, Container)> {
+		let position = match (entity.get("x"), entity.get("y"), entity.get("z")) {
+			(Some(Tag::Int(x)), Some(Tag::Int(y)), Some(Tag::Int(z))) => BlockPos::new(*x, *y, *z),
+			_ => return None,
+		};
+		let items = match entity.get("Items") {
+			Some(Tag::List(items)) => items,
+			_ => return None,
+		};
+		let mut container = Vec::new();
+		for item in items {
+			let (slot, name, count) = match (item.get("Slot"), item.get("id"), item.get("Count")) {
+				(Some(Tag::Byte(slot)), Some(Tag::String(name)), Some(Tag::Byte(count)))
+					if *slot >= 0 && *count > 0 =>
+				{
+					(*slot as usize, name, *count)
+				}
+				_ => continue,
+			};
+			let id = match self.items.id(name) {
+				Some(id) => id,
+				None => {
+					debug!("Dropped unknown item {} from a container", name);
+					continue;
+				}
+			};
+			if container.len() <= slot {
+				container.resize(slot + 1, None);
+			}
+			container[slot] = Some(Slot {
+				item: id,
+				count,
+				nbt: item.get("tag").cloned().map(Nbt::new),
+			});
+		}
+		Some((position, container))
+	}
+
+	/// Saves a chunk and the contents of its containers, replacing whatever was saved before
+	pub fn save(&self, chunk: &Chunk, containers: &[(BlockPos, &Container)]) -> Result<()> {
+		let world = self.blocks.world();
+		let mut sections = Vec::new();
+		for (y, section) in chunk.sections() {
+			let mut palette = Vec::new();
+			let mut indices = HashMap::new();
+			for state in section.blocks() {
+				indices.entry(*state).or_insert_with(|| {
+					palette.push(*state);
+					palette.len() - 1
+				});
+			}
+			let bits = bits(palette.len());
+			let per_long = 64 / bits;
+			let mut states = vec![0u64; (SECTION_VOLUME + per_long - 1) / per_long];
+			for (index, state) in section.blocks().iter().enumerate() {
+				states[index / per_long] |= (indices[state] as u64) << ((index % per_long) * bits);
+			}
+			let palette = palette
+				.into_iter()
+				.map(|state| {
+					let state = world.get(state);
+					let mut entry = compound! {
+						"Name" => Tag::String(String::from(state.map_or("minecraft:air", |s| s.name))),
+					};
+					let properties = state.map(|state| state.properties).unwrap_or_default();
+					if let (Tag::Compound(values), false) = (&mut entry, properties.is_empty()) {
+						let properties = properties
+							.iter()
+							.map(|(key, value)| {
+								(String::from(*key), Tag::String(String::from(*value)))
+							})
+							.collect();
+						values.insert(String::from("Properties"), Tag::Compound(properties));
+					}
+					entry
+				})
+				.collect();
+			sections.push(compound! {
+				"Y" => Tag::Byte(y as i8),
+				"Palette" => Tag::List(palette),
+				"BlockStates" => Tag::LongArray(states.into_iter().map(|long| long as i64).collect()),
+			});
+		}
+		let entities = containers
+			.iter()
+			.map(|(position, container)| {
+				let block = world.get(chunk.get_block(
+					(position.x & 15) as usize,
+					position.y as usize,
+					(position.z & 15) as usize,
+				));
+				let items = container
+					.iter()
+					.enumerate()
+					.filter_map(|(slot, stack)| {
+						let stack = stack.as_ref()?;
+						let mut item = compound! {
+							"Slot" => Tag::Byte(slot as i8),
+							"id" => Tag::String(String::from(self.items.name(stack.item)?)),
+							"Count" => Tag::Byte(stack.count),
+						};
+						if let (Tag::Compound(values), Some(nbt)) = (&mut item, &stack.nbt) {
+							values.insert(String::from("tag"), nbt.root.clone());
+						}
+						Some(item)
+					})
+					.collect();
+				compound! {
+					"id" => Tag::String(String::from(block.map_or("minecraft:chest", |b| b.name))),
+					"x" => Tag::Int(position.x),
+					"y" => Tag::Int(position.y),
+					"z" => Tag::Int(position.z),
+					"keepPacked" => Tag::Byte(0),
+					"Items" => Tag::List(items),
+				}
+			})
+			.collect();
+		let root = compound! {
+			"DataVersion" => Tag::Int(DATA_VERSION),
+			"Level" => compound! {
+				"xPos" => Tag::Int(chunk.pos.x),
+				"zPos" => Tag::Int(chunk.pos.z),
+				"Status" => Tag::String(String::from("full")),
+				// Light isn't saved, so vanilla has to light the chunk again just like basalt does
+				"isLightOn" => Tag::Byte(0),
+				"Biomes" => Tag::IntArray(chunk.biomes().to_vec()),
+				"Sections" => Tag::List(sections),
+				"TileEntities" => Tag::List(entities),
+				"Entities" => Tag::List(Vec::new()),
+			},
+		};
+		let mut buffer = Vec::new();
+		Nbt::new(root).write(&mut buffer)?;
+		let _files = self.files.lock().unwrap();
+		let mut region = RegionFile::open(&self.directory, chunk.pos, true)?
+			.ok_or_else(|| Error::new(ErrorKind::NotFound, "Region wasn't created"))?;
+		region.write(chunk.pos, &buffer)
+	}
+}
+
+/// Chunks to be read from disk on a thread that may block, without the world locked
+pub struct ChunkReader {
+	pub(super) storage: Arc<ChunkStorage>,
+	/// How many times the world had unloaded chunks when the reader was made
+	pub(super) unloads: u64,
+	pub(super) positions: Vec<ChunkPos>,
+}
+
+impl ChunkReader {
+	pub fn read(self) -> ReadChunks {
+		let storage = self.storage;
+		ReadChunks {
+			unloads: self.unloads,
+			chunks: self
+				.positions
+				.into_iter()
+				.map(|pos| (pos, storage.load(pos)))
+				.collect(),
+		}
+	}
+}
+
+/// Chunks read by a `ChunkReader`, to be added to the world they were read from
+pub struct ReadChunks {
+	pub(super) unloads: u64,
+	pub(super) chunks: Vec<(ChunkPos, Result<SavedChunk>)>,
+}
+
+/// Number of bits each block of a section takes up with a palette of the given size, which never
+/// spans two longs in 1.16
+fn bits(palette: usize) -> usize {
+	let mut bits = MIN_BITS;
+	while (1 << bits) < palette {
+		bits += 1;
+	}
+	bits
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::std::fs;
+
+	#[test]
+	fn palette_bits() {
+		assert_eq!(bits(1), 4);
+		assert_eq!(bits(16), 4);
+		assert_eq!(bits(17), 5);
+		assert_eq!(bits(300), 9);
+	}
+
+	#[test]
+	fn chunks_round_trip() {
+		let directory = ::std::env::temp_dir().join("basalt-storage-round-trip");
+		let _ = fs::remove_dir_all(&directory);
+		let blocks = Arc::new(BlockRegistry::new());
+		let items = Arc::new(ItemRegistry::new());
+		let storage = ChunkStorage::new(directory.clone(), blocks.clone(), items.clone());
+		let pos = ChunkPos::new(-3, 7);
+		let mut chunk = Chunk::new(pos);
+		chunk.fill_biome(8);
+		let states: Vec<u16> = (1..40).collect();
+		for (i, state) in states.iter().enumerate() {
+			chunk.set_block(i % 16, i * 5, (i * 7) % 16, *state);
+		}
+		let stick = items.id("minecraft:stick").unwrap();
+		let container = vec![
+			None,
+			Some(Slot {
+				item: stick,
+				count: 12,
+				nbt: None,
+			}),
+		];
+		let position = BlockPos::new(-3 * 16 + 2, 200, 7 * 16 + 3);
+		chunk.set_block(
+			2,
+			200,
+			3,
+			blocks.world().default_state("minecraft:chest").unwrap(),
+		);
+		assert!(storage.load(pos).unwrap().is_none());
+		storage.save(&chunk, &[(position, &container)]).unwrap();
+		let (loaded, containers) = storage.load(pos).unwrap().unwrap();
+		for y in 0..256 {
+			for z in 0..16 {
+				for x in 0..16 {
+					assert_eq!(loaded.get_block(x, y, z), chunk.get_block(x, y, z));
+				}
+			}
+		}
+		assert_eq!(loaded.biomes(), chunk.biomes());
+		assert_eq!(containers[&position], container);
+		let _ = fs::remove_dir_all(&directory);
+	}
+}
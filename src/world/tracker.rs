@@ -0,0 +1,131 @@
+use ::std::{cmp::Reverse, collections::HashSet, time::Instant};
+
+use crate::world::chunk::ChunkPos;
+
+/// Most chunks a connection may have queued up at once
+const CHUNK_BURST: f64 = 32.0;
+/// Chunks per second a connection is refilled with
+const CHUNK_RATE: f64 = 160.0;
+
+pub const MIN_VIEW_DISTANCE: u8 = 2;
+pub const MAX_VIEW_DISTANCE: u8 = 32;
+
+/// The result of moving a tracker to a new center
+pub struct ViewUpdate {
+	/// Set if the player crossed into a different chunk
+	pub center_changed: bool,
+	/// Chunks that left the view and should be unloaded by the client
+	pub unload: Vec<ChunkPos>,
+}
+
+/// Keeps track of which chunks a single client has loaded
+pub struct ChunkTracker {
+	view_distance: i32,
+	center: Option<ChunkPos>,
+	loaded: HashSet<ChunkPos>,
+	/// Chunks waiting to be sent, with the farthest first so the nearest can be popped
+	pending: Vec<ChunkPos>,
+	budget: f64,
+	refilled: Instant,
+}
+
+impl ChunkTracker {
+	pub fn new(view_distance: u8) -> Self {
+		Self {
+			view_distance: view_distance.max(MIN_VIEW_DISTANCE).min(MAX_VIEW_DISTANCE) as i32,
+			center: None,
+			loaded: HashSet::new(),
+			pending: Vec::new(),
+			budget: CHUNK_BURST,
+			refilled: Instant::now(),
+		}
+	}
+
+	/// Forgets every chunk the client had, keeping the view distance, for when it is sent to
+	/// another world
+	pub fn clear(&mut self) {
+		self.center = None;
+		self.loaded.clear();
+		self.pending.clear();
+	}
+
+	pub fn is_loaded(&self, pos: &ChunkPos) -> bool {
+		self.loaded.contains(pos)
+	}
+
+	pub fn view_distance(&self) -> i32 {
+		self.view_distance
+	}
+
+	/// Changes how many chunks the view reaches, queueing chunks that came into view and returning
+	/// ones that left it
+	pub fn set_view_distance(&mut self, view_distance: u8) -> Vec<ChunkPos> {
+		let view_distance = view_distance.max(MIN_VIEW_DISTANCE).min(MAX_VIEW_DISTANCE) as i32;
+		if view_distance == self.view_distance {
+			return Vec::new();
+		}
+		self.view_distance = view_distance;
+		// Forgetting the center makes moving back to it work out the view again
+		match self.center.take() {
+			Some(center) => self.move_to(center).unload,
+			None => Vec::new(),
+		}
+	}
+
+	/// Recenters the view, queueing chunks that came into view and returning ones that left
+	pub fn move_to(&mut self, center: ChunkPos) -> ViewUpdate {
+		if self.center == Some(center) {
+			return ViewUpdate {
+				center_changed: false,
+				unload: Vec::new(),
+			};
+		}
+		self.center = Some(center);
+		let distance = self.view_distance;
+		let mut unload: Vec<ChunkPos> = self
+			.loaded
+			.iter()
+			.filter(|pos| pos.distance(&center) > distance)
+			.copied()
+			.collect();
+		unload.sort_by_key(|pos| (pos.x, pos.z));
+		for pos in &unload {
+			self.loaded.remove(pos);
+		}
+		self.pending.clear();
+		for x in (center.x - distance)..=(center.x + distance) {
+			for z in (center.z - distance)..=(center.z + distance) {
+				let pos = ChunkPos::new(x, z);
+				if !self.loaded.contains(&pos) {
+					self.pending.push(pos);
+				}
+			}
+		}
+		self.pending
+			.sort_by_key(|pos| Reverse(pos.distance_squared(&center)));
+		ViewUpdate {
+			center_changed: true,
+			unload,
+		}
+	}
+
+	/// Takes as many of the nearest pending chunks as the rate limit allows and marks them loaded
+	pub fn poll(&mut self) -> Vec<ChunkPos> {
+		let now = Instant::now();
+		let elapsed = now.duration_since(self.refilled).as_secs_f64();
+		self.refilled = now;
+		self.budget = (self.budget + elapsed * CHUNK_RATE).min(CHUNK_BURST);
+		let mut batch = Vec::new();
+		while self.budget >= 1.0 {
+			match self.pending.pop() {
+				Some(pos) => {
+					self.loaded.insert(pos);
+					batch.push(pos);
+					self.budget -= 1.0;
+				}
+				None => break,
+			}
+		}
+		batch
+	}
+}